@@ -0,0 +1,127 @@
+use std::time::Instant;
+
+use cocoa::appkit::{
+    NSApp, NSApplication, NSApplicationActivationPolicyRegular, NSBackingStoreBuffered, NSWindow,
+    NSWindowStyleMask,
+};
+use cocoa::base::{id, nil, NO};
+use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize};
+
+use iced_nsview::headless::CustomDrawContext;
+use iced_nsview::{
+    button, wgpu, Application, Button, Color, Column, Command, Element, IcedView, Settings, Size,
+    Text, Viewport,
+};
+
+fn main() {
+    let size = Size::new(800, 600);
+    let app = unsafe { init_app() };
+    let window = unsafe { init_window(&size) };
+    let scale_factor = unsafe { window.backingScaleFactor() };
+
+    let backdrop = Backdrop::new();
+    let physical_size = Size::new(
+        (f64::from(size.width) * scale_factor).round() as u32,
+        (f64::from(size.height) * scale_factor).round() as u32,
+    );
+    let viewport = Viewport::with_physical_size(physical_size, scale_factor);
+    let view = IcedView::new(backdrop, viewport, Settings::default());
+
+    unsafe {
+        NSWindow::setContentView_(window, view.raw_object());
+        app.run();
+    }
+}
+
+unsafe fn init_app() -> id {
+    let _pool = NSAutoreleasePool::new(nil);
+    let app = NSApp();
+    NSApplication::setActivationPolicy_(app, NSApplicationActivationPolicyRegular);
+
+    app
+}
+
+unsafe fn init_window(size: &Size<u32>) -> id {
+    let window = NSWindow::alloc(nil)
+        .initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(size.width as f64, size.height as f64),
+            ),
+            NSWindowStyleMask::NSTitledWindowMask
+                | NSWindowStyleMask::NSClosableWindowMask
+                | NSWindowStyleMask::NSResizableWindowMask,
+            NSBackingStoreBuffered,
+            NO,
+        )
+        .autorelease();
+    window.makeKeyAndOrderFront_(nil);
+    window
+}
+
+struct Backdrop {
+    started_at: Instant,
+    count: u32,
+    button: button::State,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Pressed,
+}
+
+impl Backdrop {
+    fn new() -> Backdrop {
+        Backdrop {
+            started_at: Instant::now(),
+            count: 0,
+            button: Default::default(),
+        }
+    }
+}
+
+impl Application for Backdrop {
+    type Message = Message;
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        let Message::Pressed = message;
+        self.count += 1;
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<Message> {
+        Column::new()
+            .padding(20)
+            .spacing(20)
+            .push(Text::new("The background is drawn by the application.").color(Color::WHITE))
+            .push(Button::new(&mut self.button, Text::new("Press me")).on_press(Message::Pressed))
+            .push(Text::new(format!("Pressed {} times", self.count)).color(Color::WHITE))
+            .into()
+    }
+
+    // The color pulses with the time, so it changes whenever the view is redrawn, e.g. when the
+    // cursor moves.
+    fn custom_draw(&self, context: &mut CustomDrawContext<'_>) {
+        let phase = self.started_at.elapsed().as_secs_f64();
+        let level = (phase.sin() + 1.0) / 4.0;
+
+        context
+            .encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: context.target,
+                    resolve_target: None,
+                    load_op: wgpu::LoadOp::Clear,
+                    store_op: wgpu::StoreOp::Store,
+                    clear_color: wgpu::Color {
+                        r: level,
+                        g: 0.05,
+                        b: 0.25 - level / 2.0,
+                        a: 1.0,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+    }
+}
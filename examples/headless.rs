@@ -0,0 +1,29 @@
+use iced_nsview::headless::HeadlessRenderer;
+use iced_nsview::{Application, Command, Element, Settings, Size, Text, Viewport};
+
+// No window is opened, so it runs on a machine without a display, e.g. in CI.
+fn main() {
+    let viewport = Viewport::with_physical_size(Size::new(400, 300), 2.0);
+    let mut iced = HeadlessRenderer::with_settings(Hello, &viewport, &Settings::default());
+    iced.update(&viewport, None);
+
+    let frame = iced.capture(&viewport).expect("Capture the frame");
+    println!(
+        "Captured a frame of {}x{} pixels.",
+        frame.width, frame.height
+    );
+}
+
+struct Hello;
+
+impl Application for Hello {
+    type Message = ();
+
+    fn update(&mut self, _message: ()) -> Command<()> {
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<'_, ()> {
+        Text::new("Hello, headless!").into()
+    }
+}
@@ -0,0 +1,107 @@
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use cocoa::appkit::{
+    NSApp, NSApplication, NSApplicationActivationPolicyRegular, NSBackingStoreBuffered, NSWindow,
+    NSWindowStyleMask,
+};
+use cocoa::base::{id, nil, NO};
+use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize};
+
+use iced_native::Event;
+
+use iced_nsview::{
+    button, mouse, Application, Button, Command, Element, IcedView, Length, Settings, Size, Text,
+    Viewport,
+};
+
+// How long the injected click may take to reach `update`, which is one frame in practice.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn main() {
+    let size = Size::new(800, 600);
+    let app = unsafe { init_app() };
+    let window = unsafe { init_window(&size) };
+    let scale_factor = unsafe { window.backingScaleFactor() };
+
+    let app_button = AppButton::default();
+    let physical_size = Size::new(
+        (f64::from(size.width) * scale_factor).round() as u32,
+        (f64::from(size.height) * scale_factor).round() as u32,
+    );
+    let viewport = Viewport::with_physical_size(physical_size, scale_factor);
+    let mut view = IcedView::new(app_button, viewport, Settings::default());
+
+    // The button is pressed without any native event, so the example exits once the press
+    // reaches `update`, and fails if it never does. The button fills the window, so its
+    // center is the one of the window.
+    let x = size.width as f32 / 2.0;
+    let y = size.height as f32 / 2.0;
+    view.queue_events(vec![
+        Event::Mouse(mouse::Event::CursorMoved { x, y }),
+        Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)),
+        Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)),
+    ]);
+    thread::spawn(|| {
+        thread::sleep(TIMEOUT);
+        eprintln!(
+            "The injected click didn't reach update within {:?}.",
+            TIMEOUT
+        );
+        process::exit(1);
+    });
+
+    unsafe {
+        NSWindow::setContentView_(window, view.raw_object());
+        app.run();
+    }
+}
+
+unsafe fn init_app() -> id {
+    let _pool = NSAutoreleasePool::new(nil);
+    let app = NSApp();
+    NSApplication::setActivationPolicy_(app, NSApplicationActivationPolicyRegular);
+
+    app
+}
+
+unsafe fn init_window(size: &Size<u32>) -> id {
+    let window = NSWindow::alloc(nil)
+        .initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(size.width as f64, size.height as f64),
+            ),
+            NSWindowStyleMask::NSTitledWindowMask
+                | NSWindowStyleMask::NSClosableWindowMask
+                | NSWindowStyleMask::NSResizableWindowMask,
+            NSBackingStoreBuffered,
+            NO,
+        )
+        .autorelease();
+    window.makeKeyAndOrderFront_(nil);
+    window
+}
+
+#[derive(Default)]
+struct AppButton {
+    button: button::State,
+}
+
+impl Application for AppButton {
+    type Message = ();
+
+    fn update(&mut self, _message: ()) -> Command<()> {
+        println!("The injected click reached update.");
+        process::exit(0)
+    }
+
+    fn view(&mut self) -> Element<'_, ()> {
+        Button::new(&mut self.button, Text::new("Press me"))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .on_press(())
+            .into()
+    }
+}
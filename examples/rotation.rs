@@ -0,0 +1,144 @@
+use cocoa::appkit::{
+    NSApp, NSApplication, NSApplicationActivationPolicyRegular, NSBackingStoreBuffered, NSWindow,
+    NSWindowStyleMask,
+};
+use cocoa::base::{id, nil, NO};
+use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize};
+
+use iced_nsview::event::{Event, GestureEvent, Phase};
+use iced_nsview::{
+    slider, Align, Application, Column, Command, Element, IcedView, Length, Row, Settings, Size,
+    Slider, Text, Viewport,
+};
+
+fn main() {
+    let size = Size::new(800, 600);
+    let app = unsafe { init_app() };
+    let window = unsafe { init_window(&size) };
+    let scale_factor = unsafe { window.backingScaleFactor() };
+
+    let knob = Knob::new();
+    let physical_size = Size::new(
+        (f64::from(size.width) * scale_factor).round() as u32,
+        (f64::from(size.height) * scale_factor).round() as u32,
+    );
+    let viewport = Viewport::with_physical_size(physical_size, scale_factor);
+    let view = IcedView::new(knob, viewport, Settings::default());
+
+    unsafe {
+        NSWindow::setContentView_(window, view.raw_object());
+        app.run();
+    }
+}
+
+unsafe fn init_app() -> id {
+    let _pool = NSAutoreleasePool::new(nil);
+    let app = NSApp();
+    NSApplication::setActivationPolicy_(app, NSApplicationActivationPolicyRegular);
+
+    app
+}
+
+unsafe fn init_window(size: &Size<u32>) -> id {
+    let window = NSWindow::alloc(nil)
+        .initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(size.width as f64, size.height as f64),
+            ),
+            NSWindowStyleMask::NSTitledWindowMask
+                | NSWindowStyleMask::NSClosableWindowMask
+                | NSWindowStyleMask::NSResizableWindowMask,
+            NSBackingStoreBuffered,
+            NO,
+        )
+        .autorelease();
+    window.makeKeyAndOrderFront_(nil);
+    window
+}
+
+struct Knob {
+    value: f32,
+    rotation: f32,
+    slider: slider::State,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    ValueChanged(f32),
+    Rotated(f32, Phase),
+}
+
+impl Knob {
+    // Degrees of rotation needed to sweep the whole range of the knob.
+    const SWEEP: f32 = 270.0;
+
+    fn new() -> Knob {
+        Knob {
+            value: 0.5,
+            rotation: 0.0,
+            slider: Default::default(),
+        }
+    }
+}
+
+impl Application for Knob {
+    type Message = Message;
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::ValueChanged(value) => self.value = value,
+            Message::Rotated(delta, phase) => {
+                if phase == Phase::Began {
+                    self.rotation = 0.0;
+                }
+                self.rotation += delta;
+                // Clockwise rotation increases the value.
+                self.value = (self.value - delta / Self::SWEEP).max(0.0).min(1.0);
+            }
+        }
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<Message> {
+        let slider = Row::new().width(Length::Units(500)).push(
+            Slider::new(
+                &mut self.slider,
+                0.0..=1.0,
+                self.value,
+                Message::ValueChanged,
+            )
+            .step(0.001),
+        );
+
+        Row::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_items(Align::Center)
+            .push(
+                Column::new()
+                    .width(Length::Fill)
+                    .align_items(Align::Center)
+                    .padding(10)
+                    .spacing(10)
+                    .push(Text::new("Rotate two fingers on the trackpad"))
+                    .push(slider)
+                    .push(Text::new(format!("{:.2}", self.value)))
+                    .push(Text::new(format!(
+                        "Gesture rotation: {:.1}°",
+                        self.rotation
+                    ))),
+            )
+            .into()
+    }
+
+    fn on_event(&self, event: Event) -> Option<Message> {
+        match event {
+            Event::Gesture(GestureEvent::Rotate { delta, phase, .. }) => {
+                Some(Message::Rotated(delta, phase))
+            }
+            _ => None,
+        }
+    }
+}
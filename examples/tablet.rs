@@ -0,0 +1,126 @@
+use cocoa::appkit::{
+    NSApp, NSApplication, NSApplicationActivationPolicyRegular, NSBackingStoreBuffered, NSWindow,
+    NSWindowStyleMask,
+};
+use cocoa::base::{id, nil, NO};
+use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize};
+
+use iced_nsview::canvas::{self, Cursor, Frame, Geometry, Path};
+use iced_nsview::event::{Event, TabletEvent};
+use iced_nsview::{
+    Application, Canvas, Color, Command, Element, IcedView, Length, Point, Rectangle, Settings,
+    Size, Viewport,
+};
+
+fn main() {
+    let size = Size::new(800, 600);
+    let app = unsafe { init_app() };
+    let window = unsafe { init_window(&size) };
+    let scale_factor = unsafe { window.backingScaleFactor() };
+
+    let drawing = Drawing::new();
+    let physical_size = Size::new(
+        (f64::from(size.width) * scale_factor).round() as u32,
+        (f64::from(size.height) * scale_factor).round() as u32,
+    );
+    let viewport = Viewport::with_physical_size(physical_size, scale_factor);
+    let view = IcedView::new(drawing, viewport, Settings::default());
+
+    unsafe {
+        NSWindow::setContentView_(window, view.raw_object());
+        app.run();
+    }
+}
+
+unsafe fn init_app() -> id {
+    let _pool = NSAutoreleasePool::new(nil);
+    let app = NSApp();
+    NSApplication::setActivationPolicy_(app, NSApplicationActivationPolicyRegular);
+
+    app
+}
+
+unsafe fn init_window(size: &Size<u32>) -> id {
+    let window = NSWindow::alloc(nil)
+        .initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(size.width as f64, size.height as f64),
+            ),
+            NSWindowStyleMask::NSTitledWindowMask
+                | NSWindowStyleMask::NSClosableWindowMask
+                | NSWindowStyleMask::NSResizableWindowMask,
+            NSBackingStoreBuffered,
+            NO,
+        )
+        .autorelease();
+    window.makeKeyAndOrderFront_(nil);
+    window
+}
+
+struct Drawing {
+    stroke: Stroke,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    PenMoved(Point, f32),
+}
+
+impl Drawing {
+    fn new() -> Drawing {
+        Drawing {
+            stroke: Stroke::default(),
+        }
+    }
+}
+
+impl Application for Drawing {
+    type Message = Message;
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        let Message::PenMoved(position, pressure) = message;
+        self.stroke.points.push((position, pressure));
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<Message> {
+        Canvas::new(&mut self.stroke)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn on_event(&self, event: Event) -> Option<Message> {
+        match event {
+            Event::Tablet(TabletEvent::Point {
+                position, pressure, ..
+            }) if pressure > 0.0 => Some(Message::PenMoved(position, pressure)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Stroke {
+    points: Vec<(Point, f32)>,
+}
+
+impl Stroke {
+    const MAX_WIDTH: f32 = 12.0;
+}
+
+impl canvas::Program<Message> for Stroke {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+
+        // The width of the stroke follows the pressure of the pen.
+        self.points.iter().for_each(|(position, pressure)| {
+            let dot = Path::circle(*position, pressure * Self::MAX_WIDTH / 2.0);
+            frame.fill(&dot, Color::BLACK);
+        });
+
+        vec![frame.into_geometry()]
+    }
+}
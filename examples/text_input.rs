@@ -0,0 +1,125 @@
+use cocoa::appkit::{
+    NSApp, NSApplication, NSApplicationActivationPolicyRegular, NSBackingStoreBuffered, NSWindow,
+    NSWindowStyleMask,
+};
+use cocoa::base::{id, nil, NO};
+use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize};
+
+use iced_nsview::event::Event;
+use iced_nsview::{
+    text_input, Align, Application, Column, Command, Element, IcedView, Length, Settings, Size,
+    Text, TextInput, Viewport,
+};
+
+fn main() {
+    let size = Size::new(800, 600);
+    let app = unsafe { init_app() };
+    let window = unsafe { init_window(&size) };
+    let scale_factor = unsafe { window.backingScaleFactor() };
+
+    let notes = Notes::new();
+    let physical_size = Size::new(
+        (f64::from(size.width) * scale_factor).round() as u32,
+        (f64::from(size.height) * scale_factor).round() as u32,
+    );
+    let viewport = Viewport::with_physical_size(physical_size, scale_factor);
+    let view = IcedView::new(notes, viewport, Settings::default());
+
+    unsafe {
+        NSWindow::setContentView_(window, view.raw_object());
+        app.run();
+    }
+}
+
+unsafe fn init_app() -> id {
+    let _pool = NSAutoreleasePool::new(nil);
+    let app = NSApp();
+    NSApplication::setActivationPolicy_(app, NSApplicationActivationPolicyRegular);
+
+    app
+}
+
+unsafe fn init_window(size: &Size<u32>) -> id {
+    let window = NSWindow::alloc(nil)
+        .initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(size.width as f64, size.height as f64),
+            ),
+            NSWindowStyleMask::NSTitledWindowMask
+                | NSWindowStyleMask::NSClosableWindowMask
+                | NSWindowStyleMask::NSResizableWindowMask,
+            NSBackingStoreBuffered,
+            NO,
+        )
+        .autorelease();
+    window.makeKeyAndOrderFront_(nil);
+    window
+}
+
+struct Notes {
+    value: String,
+    marked_text: String,
+    input: text_input::State,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    InputChanged(String),
+    MarkedTextChanged(String),
+}
+
+impl Notes {
+    fn new() -> Notes {
+        Notes {
+            value: String::new(),
+            marked_text: String::new(),
+            input: text_input::State::focused(),
+        }
+    }
+}
+
+impl Application for Notes {
+    type Message = Message;
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::InputChanged(value) => self.value = value,
+            Message::MarkedTextChanged(text) => self.marked_text = text,
+        }
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<Message> {
+        let input = TextInput::new(
+            &mut self.input,
+            "Type with an input method or insert an emoji with Ctrl+Cmd+Space",
+            &self.value,
+            Message::InputChanged,
+        )
+        .padding(10)
+        .size(30);
+
+        Column::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_items(Align::Center)
+            .padding(20)
+            .spacing(10)
+            .push(input)
+            .push(Text::new(format!("Composing: {}", self.marked_text)))
+            .push(Text::new(format!(
+                "Characters: {:?}",
+                self.value.chars().collect::<Vec<_>>()
+            )))
+            .into()
+    }
+
+    fn on_event(&self, event: Event) -> Option<Message> {
+        match event {
+            Event::MarkedText(text) => Some(Message::MarkedTextChanged(text)),
+            _ => None,
+        }
+    }
+}
@@ -18,7 +18,11 @@ pub fn main() {
     let scale_factor = unsafe { window.backingScaleFactor() };
 
     let tour = Tour::new();
-    let viewport = Viewport::with_physical_size(size, scale_factor);
+    let physical_size = Size::new(
+        (f64::from(size.width) * scale_factor).round() as u32,
+        (f64::from(size.height) * scale_factor).round() as u32,
+    );
+    let viewport = Viewport::with_physical_size(physical_size, scale_factor);
     let view = IcedView::new(tour, viewport, Settings::default());
 
     unsafe {
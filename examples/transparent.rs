@@ -0,0 +1,135 @@
+use cocoa::appkit::{
+    NSApp, NSApplication, NSApplicationActivationPolicyRegular, NSBackingStoreBuffered, NSColor,
+    NSWindow, NSWindowStyleMask,
+};
+use cocoa::base::{id, nil, NO, YES};
+use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize};
+
+use iced_nsview::{
+    button, container, Align, Application, Button, Color, Column, Command, Container, Element,
+    IcedView, Length, Settings, Size, Text, Viewport,
+};
+
+// The window is a square, so the container with the half of its side as the radius is a circle.
+const SIDE: u32 = 300;
+
+fn main() {
+    let size = Size::new(SIDE, SIDE);
+    let app = unsafe { init_app() };
+    let window = unsafe { init_window(&size) };
+    let scale_factor = unsafe { window.backingScaleFactor() };
+
+    let dial = Dial::new();
+    let physical_size = Size::new(
+        (f64::from(size.width) * scale_factor).round() as u32,
+        (f64::from(size.height) * scale_factor).round() as u32,
+    );
+    let viewport = Viewport::with_physical_size(physical_size, scale_factor);
+    let view = IcedView::new(dial, viewport, Settings::default());
+
+    unsafe {
+        NSWindow::setContentView_(window, view.raw_object());
+        app.run();
+    }
+}
+
+unsafe fn init_app() -> id {
+    let _pool = NSAutoreleasePool::new(nil);
+    let app = NSApp();
+    NSApplication::setActivationPolicy_(app, NSApplicationActivationPolicyRegular);
+
+    app
+}
+
+// A borderless window without a background, so only the circle drawn by the view is visible.
+unsafe fn init_window(size: &Size<u32>) -> id {
+    let window = NSWindow::alloc(nil)
+        .initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(size.width as f64, size.height as f64),
+            ),
+            NSWindowStyleMask::NSBorderlessWindowMask,
+            NSBackingStoreBuffered,
+            NO,
+        )
+        .autorelease();
+    window.setOpaque_(NO);
+    window.setBackgroundColor_(NSColor::clearColor(nil));
+    window.setMovableByWindowBackground_(YES);
+    window.center();
+    window.makeKeyAndOrderFront_(nil);
+    window
+}
+
+struct Dial {
+    count: u32,
+    increment: button::State,
+    quit: button::State,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Increment,
+    Quit,
+}
+
+impl Dial {
+    fn new() -> Dial {
+        Dial {
+            count: 0,
+            increment: Default::default(),
+            quit: Default::default(),
+        }
+    }
+}
+
+impl Application for Dial {
+    type Message = Message;
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        match message {
+            Message::Increment => self.count += 1,
+            Message::Quit => std::process::exit(0),
+        }
+
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<Message> {
+        let content = Column::new()
+            .spacing(20)
+            .align_items(Align::Center)
+            .push(Text::new(self.count.to_string()).size(50))
+            .push(
+                Button::new(&mut self.increment, Text::new("Increment"))
+                    .on_press(Message::Increment),
+            )
+            .push(Button::new(&mut self.quit, Text::new("Quit")).on_press(Message::Quit));
+
+        Container::new(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .style(Circle)
+            .into()
+    }
+
+    fn background_color(&self) -> Color {
+        Color::TRANSPARENT
+    }
+}
+
+struct Circle;
+
+impl container::StyleSheet for Circle {
+    fn style(&self) -> container::Style {
+        container::Style {
+            text_color: Some(Color::WHITE),
+            background: Some(Color::from_rgba(0.1, 0.1, 0.15, 0.85).into()),
+            border_radius: (SIDE / 2) as u16,
+            ..container::Style::default()
+        }
+    }
+}
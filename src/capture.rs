@@ -0,0 +1,112 @@
+//! Reading the frames back, e.g. for the regression tests of an interface or for a screenshot.
+
+use std::fmt;
+
+use iced_wgpu::wgpu;
+
+use crate::gpu::GpuContext;
+use crate::Size;
+
+/// The alignment of the rows of a texture copied into a buffer.
+const BYTES_PER_ROW_ALIGNMENT: u32 = 256;
+
+/// The pixels of a frame, in rows from the top left corner.
+///
+/// The colors are premultiplied by their alpha, and encoded in sRGB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    /// The width, in pixels.
+    pub width: u32,
+    /// The height, in pixels.
+    pub height: u32,
+    /// The red, green, blue and alpha bytes of each pixel.
+    pub pixels: Vec<u8>,
+}
+
+/// An error of the capture of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureError {
+    /// The viewport has no pixel to capture.
+    EmptyViewport,
+    /// The buffer the frame has been copied into couldn't be mapped.
+    ReadbackFailed,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::EmptyViewport => write!(f, "The viewport is empty."),
+            CaptureError::ReadbackFailed => write!(f, "The frame couldn't be read back."),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+/// The length of the rows of the BGRA pixels of the width in a buffer, padded to the alignment
+/// of the copies.
+pub(crate) fn padded_bytes_per_row(width: u32) -> u32 {
+    let bytes_per_row = width * 4;
+    let padding = (BYTES_PER_ROW_ALIGNMENT - bytes_per_row % BYTES_PER_ROW_ALIGNMENT)
+        % BYTES_PER_ROW_ALIGNMENT;
+    bytes_per_row + padding
+}
+
+/// Waits for the buffer the BGRA pixels have been copied into, and reads them without the
+/// padding of the rows.
+pub(crate) fn read_pixels(
+    gpu: &GpuContext,
+    buffer: &wgpu::Buffer,
+    size: Size<u32>,
+    bytes_per_row: u32,
+) -> Result<RgbaImage, CaptureError> {
+    let mapping = buffer.map_read(0, u64::from(bytes_per_row) * u64::from(size.height));
+    gpu.device().poll(wgpu::Maintain::Wait);
+    let mapping =
+        crate::futures::executor::block_on(mapping).map_err(|_| CaptureError::ReadbackFailed)?;
+
+    Ok(RgbaImage {
+        width: size.width,
+        height: size.height,
+        pixels: rgba_from_padded_bgra(mapping.as_slice(), size.width, bytes_per_row),
+    })
+}
+
+/// Converts the rows of BGRA pixels of the width, padded to the length, into RGBA pixels.
+fn rgba_from_padded_bgra(data: &[u8], width: u32, bytes_per_row: u32) -> Vec<u8> {
+    let row_len = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_len * data.len() / bytes_per_row as usize);
+    data.chunks(bytes_per_row as usize).for_each(|row| {
+        row[..row_len].chunks(4).for_each(|bgra| {
+            pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+        })
+    });
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_padded_to_the_alignment() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+        assert_eq!(padded_bytes_per_row(0), 0);
+    }
+
+    #[test]
+    fn padding_is_stripped_and_bgra_swizzled() {
+        let bytes_per_row = padded_bytes_per_row(2);
+        let mut data = vec![0xee; bytes_per_row as usize * 2];
+        data[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let second_row = bytes_per_row as usize;
+        data[second_row..second_row + 8].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+
+        assert_eq!(
+            rgba_from_padded_bgra(&data, 2, bytes_per_row),
+            vec![3, 2, 1, 4, 7, 6, 5, 8, 11, 10, 9, 12, 15, 14, 13, 16]
+        );
+    }
+}
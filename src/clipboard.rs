@@ -0,0 +1,513 @@
+//! The clipboard of the system, i.e. `NSPasteboard`.
+
+use std::cell::{Cell, RefCell};
+use std::ffi::CStr;
+use std::fmt;
+
+use cocoa::appkit::{NSPasteboard, NSPasteboardTypeString};
+use cocoa::base::{id, nil, NO, YES};
+use cocoa::foundation::{NSArray, NSInteger, NSPoint, NSRect, NSSize, NSString, NSUInteger};
+
+use iced_native::Clipboard;
+
+use objc::{class, msg_send, sel, sel_impl};
+
+use crate::{Object, Size};
+
+/// The storage of the clipboard, which is the system pasteboard by default.
+///
+/// Another one can be given with [`Settings::with_clipboard`], e.g. [`MemoryClipboard`] for the
+/// tests, so they don't clobber the clipboard of the user.
+///
+/// [`Settings::with_clipboard`]: ../struct.Settings.html#method.with_clipboard
+/// [`MemoryClipboard`]: struct.MemoryClipboard.html
+pub trait ClipboardBackend: fmt::Debug {
+    /// Reads the text.
+    fn read(&self) -> Option<String>;
+
+    /// Replaces the contents with the text, returning whether it's been written.
+    fn write(&self, text: &str) -> bool;
+
+    /// Reads the bytes of a custom type, e.g. `com.example.synth.patch`.
+    fn read_data(&self, uti: &str) -> Option<Vec<u8>>;
+
+    /// Replaces the contents with several representations of the same thing, e.g. a custom
+    /// type along with a text for the other applications, returning whether all of them have
+    /// been written.
+    fn write_representations(&self, representations: &[Representation<'_>]) -> bool;
+
+    /// The types of the data, e.g. `public.utf8-plain-text`.
+    fn available_types(&self) -> Vec<String>;
+
+    /// The number of times the contents have changed, which tells whether something has been
+    /// copied since it's been checked.
+    fn change_count(&self) -> i64;
+
+    /// Replaces the contents with the bytes of a custom type, returning whether they've been
+    /// written.
+    fn write_data(&self, uti: &str, bytes: &[u8]) -> bool {
+        self.write_representations(&[Representation::Data { uti, bytes }])
+    }
+
+    /// Reads the image as RGBA pixels, whose colors aren't premultiplied by their alpha, along
+    /// with its size in pixels.
+    ///
+    /// By default, it returns `None`.
+    fn read_image(&self) -> Option<(Vec<u8>, Size<u32>)> {
+        None
+    }
+
+    /// Reads the RTF text, converted to plain text.
+    ///
+    /// By default, it returns `None`.
+    fn read_rtf(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A system pasteboard.
+///
+/// Any image format supported by `NSImage`, like PNG or TIFF, is decoded by `read_image`.
+#[derive(Debug)]
+pub struct Pasteboard {
+    object: id,
+}
+
+impl Pasteboard {
+    /// The general pasteboard, which is the clipboard of the system.
+    pub fn general() -> Self {
+        let object = unsafe { NSPasteboard::generalPasteboard(nil) };
+
+        Self { object }
+    }
+
+    /// The pasteboard with the name, which is created if needed.
+    ///
+    /// It's useful for the tests, which shouldn't clobber the clipboard of the user.
+    pub fn named(name: &str) -> Self {
+        let object = unsafe {
+            let name = NSString::alloc(nil).init_str(name);
+            let object = NSPasteboard::pasteboardWithName(nil, name);
+            let () = msg_send![name, release];
+            object
+        };
+
+        Self { object }
+    }
+}
+
+impl ClipboardBackend for Pasteboard {
+    fn read(&self) -> Option<String> {
+        unsafe {
+            let class = class!(NSString);
+            let class_ref: *mut Object = msg_send![class, self];
+            let classes = NSArray::arrayWithObject(nil, class_ref);
+            let objects = self.object.readObjectsForClasses_options(classes, nil);
+            if objects.is_null() || objects.count() == 0 {
+                return None;
+            }
+            string_from_nsstring(objects.objectAtIndex(0))
+        }
+    }
+
+    fn write(&self, text: &str) -> bool {
+        unsafe {
+            let string = NSString::alloc(nil).init_str(text);
+            self.object.clearContents();
+            let is_written = self
+                .object
+                .setString_forType(string, NSPasteboardTypeString);
+            let () = msg_send![string, release];
+            is_written != NO
+        }
+    }
+
+    fn read_data(&self, uti: &str) -> Option<Vec<u8>> {
+        unsafe { data_from_pasteboard(self.object, uti) }
+    }
+
+    fn write_representations(&self, representations: &[Representation<'_>]) -> bool {
+        unsafe {
+            let types: Vec<id> = representations
+                .iter()
+                .map(|representation| NSString::alloc(nil).init_str(representation.uti()))
+                .collect();
+            self.object
+                .declareTypes_owner(NSArray::arrayWithObjects(nil, &types), nil);
+
+            let is_written = representations
+                .iter()
+                .zip(&types)
+                .all(|(representation, uti)| match representation {
+                    Representation::Text(text) => {
+                        let string = NSString::alloc(nil).init_str(text);
+                        let is_written = self.object.setString_forType(string, *uti);
+                        let () = msg_send![string, release];
+                        is_written != NO
+                    }
+                    Representation::Data { bytes, .. } => {
+                        let length = bytes.len() as NSUInteger;
+                        let data: id = msg_send![
+                            class!(NSData),
+                            dataWithBytes: bytes.as_ptr()
+                            length: length
+                        ];
+                        self.object.setData_forType(data, *uti) != NO
+                    }
+                });
+
+            types.into_iter().for_each(|uti| {
+                let () = msg_send![uti, release];
+            });
+            is_written
+        }
+    }
+
+    fn read_image(&self) -> Option<(Vec<u8>, Size<u32>)> {
+        unsafe { image_from_pasteboard(self.object) }
+    }
+
+    fn read_rtf(&self) -> Option<String> {
+        unsafe {
+            let rtf_type = NSString::alloc(nil).init_str(RTF_TYPE);
+            let data = self.object.dataForType(rtf_type);
+            let () = msg_send![rtf_type, release];
+            if data == nil {
+                return None;
+            }
+
+            let alloc: id = msg_send![class!(NSAttributedString), alloc];
+            let attributed: id = msg_send![alloc, initWithRTF: data documentAttributes: nil];
+            if attributed == nil {
+                return None;
+            }
+            let text = string_from_nsstring(msg_send![attributed, string]);
+            let () = msg_send![attributed, release];
+            text
+        }
+    }
+
+    fn available_types(&self) -> Vec<String> {
+        unsafe {
+            let types = self.object.types();
+            if types == nil {
+                return Vec::new();
+            }
+            (0..types.count())
+                .filter_map(|n| string_from_nsstring(types.objectAtIndex(n)))
+                .collect()
+        }
+    }
+
+    fn change_count(&self) -> i64 {
+        unsafe { self.object.changeCount() as i64 }
+    }
+}
+
+/// A representation of the contents written to the pasteboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation<'a> {
+    /// A plain text.
+    Text(&'a str),
+    /// The bytes of a custom type.
+    Data {
+        /// The uniform type identifier of the data, e.g. `com.example.synth.patch`.
+        uti: &'a str,
+        /// The bytes.
+        bytes: &'a [u8],
+    },
+}
+
+impl Representation<'_> {
+    fn uti(&self) -> &str {
+        match self {
+            Representation::Text(_) => TEXT_TYPE,
+            Representation::Data { uti, .. } => uti,
+        }
+    }
+}
+
+/// A clipboard kept in memory, which is only shared by the views it's given to.
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    contents: RefCell<Vec<(String, Vec<u8>)>>,
+    change_count: Cell<i64>,
+}
+
+impl MemoryClipboard {
+    /// Creates an empty clipboard.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn read(&self) -> Option<String> {
+        self.read_data(TEXT_TYPE)
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+    }
+
+    fn write(&self, text: &str) -> bool {
+        self.write_representations(&[Representation::Text(text)])
+    }
+
+    fn read_data(&self, uti: &str) -> Option<Vec<u8>> {
+        self.contents
+            .borrow()
+            .iter()
+            .find(|(contents_uti, _)| contents_uti == uti)
+            .map(|(_, bytes)| bytes.clone())
+    }
+
+    fn write_representations(&self, representations: &[Representation<'_>]) -> bool {
+        *self.contents.borrow_mut() = representations
+            .iter()
+            .map(|representation| {
+                let bytes = match representation {
+                    Representation::Text(text) => text.as_bytes(),
+                    Representation::Data { bytes, .. } => bytes,
+                };
+                (representation.uti().to_string(), bytes.to_vec())
+            })
+            .collect();
+        self.change_count.set(self.change_count.get() + 1);
+        true
+    }
+
+    fn available_types(&self) -> Vec<String> {
+        self.contents
+            .borrow()
+            .iter()
+            .map(|(uti, _)| uti.clone())
+            .collect()
+    }
+
+    fn change_count(&self) -> i64 {
+        self.change_count.get()
+    }
+}
+
+/// Lends a clipboard to the widgets.
+pub(crate) struct WidgetClipboard<'a>(pub(crate) &'a dyn ClipboardBackend);
+
+impl Clipboard for WidgetClipboard<'_> {
+    fn content(&self) -> Option<String> {
+        self.0.read()
+    }
+}
+
+/// The uniform type identifier of the plain text, i.e. `NSPasteboardTypeString`.
+const TEXT_TYPE: &str = "public.utf8-plain-text";
+
+/// The uniform type identifier of RTF.
+const RTF_TYPE: &str = "public.rtf";
+
+/// Reads the bytes of a custom type from any pasteboard, e.g. the one of a drag.
+pub(crate) unsafe fn data_from_pasteboard(pasteboard: id, uti: &str) -> Option<Vec<u8>> {
+    let uti = NSString::alloc(nil).init_str(uti);
+    let data = pasteboard.dataForType(uti);
+    let () = msg_send![uti, release];
+    if data == nil {
+        return None;
+    }
+
+    let bytes: *const u8 = msg_send![data, bytes];
+    let length: NSUInteger = msg_send![data, length];
+    if length == 0 {
+        return Some(Vec::new());
+    }
+    Some(std::slice::from_raw_parts(bytes, length as usize).to_vec())
+}
+
+/// Reads the image from any pasteboard as RGBA pixels.
+pub(crate) unsafe fn image_from_pasteboard(pasteboard: id) -> Option<(Vec<u8>, Size<u32>)> {
+    let image = undecoded_image_from_pasteboard(pasteboard);
+    if image == nil {
+        return None;
+    }
+
+    let pixels = rgba_from_image(image);
+    let () = msg_send![image, release];
+    pixels
+}
+
+/// Reads the image from any pasteboard, e.g. the one of a drag, without decoding its pixels,
+/// so it can be decoded on another thread.
+///
+/// It returns a retained `NSImage`, or `nil` if there's no image.
+pub(crate) unsafe fn undecoded_image_from_pasteboard(pasteboard: id) -> id {
+    let alloc: id = msg_send![class!(NSImage), alloc];
+    msg_send![alloc, initWithPasteboard: pasteboard]
+}
+
+/// Draws the image into a bitmap of RGBA pixels, as the representations of the image can have
+/// any layout.
+///
+/// The bitmap premultiplies the colors by their alpha, so they're divided back. It returns
+/// `None` if the bitmap couldn't be created.
+///
+/// It can be called on any thread, as the image is drawn into a context of its own.
+pub(crate) unsafe fn rgba_from_image(image: id) -> Option<(Vec<u8>, Size<u32>)> {
+    let representations: id = msg_send![image, representations];
+    if representations == nil || representations.count() == 0 {
+        return None;
+    }
+    // The size of the image is in points, while the representations know their pixels.
+    let representation = representations.objectAtIndex(0);
+    let width: NSInteger = msg_send![representation, pixelsWide];
+    let height: NSInteger = msg_send![representation, pixelsHigh];
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+
+    let planes: *mut *mut u8 = std::ptr::null_mut();
+    let bits_per_sample: NSInteger = 8;
+    let samples_per_pixel: NSInteger = 4;
+    let color_space = NSString::alloc(nil).init_str("NSDeviceRGBColorSpace");
+    let alloc: id = msg_send![class!(NSBitmapImageRep), alloc];
+    let bitmap: id = msg_send![
+        alloc,
+        initWithBitmapDataPlanes: planes
+        pixelsWide: width
+        pixelsHigh: height
+        bitsPerSample: bits_per_sample
+        samplesPerPixel: samples_per_pixel
+        hasAlpha: YES
+        isPlanar: NO
+        colorSpaceName: color_space
+        bytesPerRow: width * samples_per_pixel
+        bitsPerPixel: bits_per_sample * samples_per_pixel
+    ];
+    let () = msg_send![color_space, release];
+    // The bitmap isn't allocated for the sizes beyond the memory.
+    if bitmap == nil {
+        return None;
+    }
+
+    let context: id =
+        msg_send![class!(NSGraphicsContext), graphicsContextWithBitmapImageRep: bitmap];
+    if context == nil {
+        let () = msg_send![bitmap, release];
+        return None;
+    }
+    let () = msg_send![class!(NSGraphicsContext), saveGraphicsState];
+    let () = msg_send![class!(NSGraphicsContext), setCurrentContext: context];
+    let rect = NSRect::new(
+        NSPoint::new(0.0, 0.0),
+        NSSize::new(width as f64, height as f64),
+    );
+    let () = msg_send![image, drawInRect: rect];
+    let () = msg_send![class!(NSGraphicsContext), restoreGraphicsState];
+
+    let data: *const u8 = msg_send![bitmap, bitmapData];
+    let pixels = if data.is_null() {
+        None
+    } else {
+        let len = (width * height * samples_per_pixel) as usize;
+        let mut pixels = std::slice::from_raw_parts(data, len).to_vec();
+        unpremultiply(&mut pixels);
+        Some(pixels)
+    };
+    let () = msg_send![bitmap, release];
+
+    pixels.map(|pixels| (pixels, Size::new(width as u32, height as u32)))
+}
+
+/// Divides the colors of the RGBA pixels by their alpha, rounding to the nearest.
+fn unpremultiply(pixels: &mut [u8]) {
+    pixels.chunks_mut(4).for_each(|pixel| {
+        let alpha = u16::from(pixel[3]);
+        if alpha == 0 || alpha == 255 {
+            return;
+        }
+        pixel[..3].iter_mut().for_each(|color| {
+            let color_alpha = u16::from(*color).min(alpha);
+            *color = ((color_alpha * 255 + alpha / 2) / alpha) as u8;
+        });
+    });
+}
+
+unsafe fn string_from_nsstring(string: id) -> Option<String> {
+    if string == nil {
+        return None;
+    }
+
+    let ptr = NSString::UTF8String(string);
+    if ptr.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(ptr).to_string_lossy().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pasteboard of its own for each test, as they run in parallel.
+    fn pasteboard(test: &str) -> Pasteboard {
+        Pasteboard::named(&format!("com.github.tonikasoft.iced-nsview.tests.{}", test))
+    }
+
+    fn text_round_trips(clipboard: &dyn ClipboardBackend) {
+        let change_count = clipboard.change_count();
+        ["Copied", "Grüße, ✓ 日本語 🎹"].iter().for_each(|text| {
+            assert!(clipboard.write(text));
+            assert_eq!(clipboard.read().as_deref(), Some(*text));
+        });
+        assert!(clipboard.change_count() > change_count);
+        assert!(clipboard
+            .available_types()
+            .iter()
+            .any(|uti| uti == TEXT_TYPE));
+    }
+
+    #[test]
+    fn text_round_trips_through_a_pasteboard() {
+        text_round_trips(&pasteboard("text"));
+    }
+
+    #[test]
+    fn text_round_trips_through_the_memory() {
+        text_round_trips(&MemoryClipboard::new());
+    }
+
+    const PATCH_TYPE: &str = "com.example.synth.patch";
+
+    fn patches_round_trip(clipboard: &dyn ClipboardBackend) {
+        let patch = [0, 1, 2, 0xff, 0];
+        assert!(clipboard.write_representations(&[
+            Representation::Data {
+                uti: PATCH_TYPE,
+                bytes: &patch,
+            },
+            Representation::Text("Init patch"),
+        ]));
+
+        assert_eq!(clipboard.read_data(PATCH_TYPE), Some(patch.to_vec()));
+        assert_eq!(clipboard.read().as_deref(), Some("Init patch"));
+        let types = clipboard.available_types();
+        assert!(types.iter().any(|uti| uti == PATCH_TYPE));
+
+        // A text copied later replaces all the representations.
+        assert!(clipboard.write("Another text"));
+        assert_eq!(clipboard.read_data(PATCH_TYPE), None);
+    }
+
+    #[test]
+    fn custom_types_round_trip_through_a_pasteboard() {
+        patches_round_trip(&pasteboard("patch"));
+    }
+
+    #[test]
+    fn custom_types_round_trip_through_the_memory() {
+        patches_round_trip(&MemoryClipboard::new());
+    }
+
+    #[test]
+    fn unpremultiply_divides_the_colors_by_alpha() {
+        let mut pixels = vec![64, 32, 0, 128, 10, 20, 30, 255, 0, 0, 0, 0];
+        unpremultiply(&mut pixels);
+        assert_eq!(pixels, vec![128, 64, 0, 128, 10, 20, 30, 255, 0, 0, 0, 0]);
+    }
+}
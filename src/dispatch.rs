@@ -0,0 +1,59 @@
+//! Running the slow work, like decoding the dropped images, off the main thread.
+
+use std::ffi::c_void;
+
+use cocoa::base::nil;
+use cocoa::foundation::NSAutoreleasePool;
+
+/// `QOS_CLASS_USER_INITIATED`, as the user waits for the result.
+const QOS_CLASS_USER_INITIATED: isize = 0x19;
+
+// libdispatch is part of libSystem, so it's always linked.
+#[allow(non_upper_case_globals)]
+extern "C" {
+    static _dispatch_main_q: c_void;
+    fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut c_void;
+    fn dispatch_async_f(queue: *mut c_void, context: *mut c_void, work: extern "C" fn(*mut c_void));
+}
+
+type Work = Box<dyn FnOnce() + Send>;
+
+/// Runs the work on a background queue, then hands its result to the continuation on the main
+/// queue, where the view can be messaged.
+pub(crate) fn run_in_background<T, W, C>(work: W, continuation: C)
+where
+    T: Send + 'static,
+    W: FnOnce() -> T + Send + 'static,
+    C: FnOnce(T) + Send + 'static,
+{
+    unsafe {
+        let queue = dispatch_get_global_queue(QOS_CLASS_USER_INITIATED, 0);
+        dispatch(queue, move || {
+            let result = work();
+            run_on_main(move || continuation(result));
+        });
+    }
+}
+
+/// Runs the closure on the main queue, at the next turn of the run loop.
+pub(crate) fn run_on_main<F: FnOnce() + Send + 'static>(f: F) {
+    unsafe {
+        let queue: *const c_void = &_dispatch_main_q;
+        dispatch(queue as *mut c_void, f);
+    }
+}
+
+unsafe fn dispatch<F: FnOnce() + Send + 'static>(queue: *mut c_void, f: F) {
+    // The trait object is boxed again, so the context is a thin pointer.
+    let work: Box<Work> = Box::new(Box::new(f));
+    dispatch_async_f(queue, Box::into_raw(work).cast(), perform);
+}
+
+extern "C" fn perform(context: *mut c_void) {
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+        let work = Box::from_raw(context as *mut Work);
+        work();
+        pool.drain();
+    }
+}
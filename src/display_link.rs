@@ -0,0 +1,139 @@
+//! The display link pacing the redraws of an animating view with the refresh of its display.
+
+use std::ffi::c_void;
+use std::ptr;
+
+use cocoa::base::{id, nil, NO};
+use cocoa::foundation::NSAutoreleasePool;
+
+use objc::{class, msg_send, sel, sel_impl};
+
+type CVDisplayLinkRef = *mut c_void;
+type CVReturn = i32;
+
+/// `kCVReturnSuccess`
+const SUCCESS: CVReturn = 0;
+
+// Only a few fields are read, but the whole layout of CoreVideo is declared.
+#[allow(dead_code)]
+#[repr(C)]
+struct CVSMPTETime {
+    subframes: i16,
+    subframe_divisor: i16,
+    counter: u32,
+    smpte_type: u32,
+    flags: u32,
+    hours: i16,
+    minutes: i16,
+    seconds: i16,
+    frames: i16,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+struct CVTimeStamp {
+    version: u32,
+    video_time_scale: i32,
+    video_time: i64,
+    host_time: u64,
+    rate_scalar: f64,
+    video_refresh_period: i64,
+    smpte_time: CVSMPTETime,
+    flags: u64,
+    reserved: u64,
+}
+
+type CVDisplayLinkOutputCallback = extern "C" fn(
+    CVDisplayLinkRef,
+    *const CVTimeStamp,
+    *const CVTimeStamp,
+    u64,
+    *mut u64,
+    *mut c_void,
+) -> CVReturn;
+
+#[link(name = "CoreVideo", kind = "framework")]
+extern "C" {
+    fn CVDisplayLinkCreateWithActiveCGDisplays(link: *mut CVDisplayLinkRef) -> CVReturn;
+    fn CVDisplayLinkSetOutputCallback(
+        link: CVDisplayLinkRef,
+        callback: CVDisplayLinkOutputCallback,
+        context: *mut c_void,
+    ) -> CVReturn;
+    fn CVDisplayLinkStart(link: CVDisplayLinkRef) -> CVReturn;
+    fn CVDisplayLinkStop(link: CVDisplayLinkRef) -> CVReturn;
+    fn CVDisplayLinkIsRunning(link: CVDisplayLinkRef) -> u8;
+    fn CVDisplayLinkRelease(link: CVDisplayLinkRef);
+}
+
+/// Sends `displayLinkFired:` to the view on the main thread at each refresh of the display, with
+/// the time the frame will be shown at, in seconds, as an `NSNumber`.
+pub(crate) struct DisplayLink {
+    link: CVDisplayLinkRef,
+}
+
+impl DisplayLink {
+    /// Creates a stopped link.
+    ///
+    /// The view isn't retained, so the link has to be dropped before it.
+    pub(crate) fn new(view: id) -> Option<Self> {
+        unsafe {
+            let mut link = ptr::null_mut();
+            if CVDisplayLinkCreateWithActiveCGDisplays(&mut link) != SUCCESS {
+                return None;
+            }
+            CVDisplayLinkSetOutputCallback(link, output, view as *mut c_void);
+
+            Some(Self { link })
+        }
+    }
+
+    pub(crate) fn set_running(&mut self, is_running: bool) {
+        unsafe {
+            let was_running = CVDisplayLinkIsRunning(self.link) != 0;
+            if is_running && !was_running {
+                CVDisplayLinkStart(self.link);
+            } else if !is_running && was_running {
+                CVDisplayLinkStop(self.link);
+            }
+        }
+    }
+}
+
+impl Drop for DisplayLink {
+    fn drop(&mut self) {
+        unsafe {
+            // Stopping waits for the callback in progress, so the view isn't used afterwards.
+            CVDisplayLinkStop(self.link);
+            CVDisplayLinkRelease(self.link);
+        }
+    }
+}
+
+// It's called on a thread of CoreVideo, so the view is only messaged on the main thread.
+extern "C" fn output(
+    _link: CVDisplayLinkRef,
+    _now: *const CVTimeStamp,
+    output_time: *const CVTimeStamp,
+    _flags_in: u64,
+    _flags_out: *mut u64,
+    context: *mut c_void,
+) -> CVReturn {
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+        let output_time = &*output_time;
+        let time_scale = f64::from(output_time.video_time_scale.max(1));
+        let seconds = output_time.video_time as f64 / time_scale;
+        let timestamp: id = msg_send![class!(NSNumber), numberWithDouble: seconds];
+        let view = context as id;
+        let () = msg_send![
+            view,
+            performSelectorOnMainThread: sel!(displayLinkFired:)
+            withObject: timestamp
+            waitUntilDone: NO
+        ];
+        pool.drain();
+    }
+
+    SUCCESS
+}
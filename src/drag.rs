@@ -0,0 +1,493 @@
+//! Dragging from the view to other views and applications, e.g. a preset onto the track of the
+//! host or onto the Finder.
+//!
+//! The drag is described with a [`DragSession`] and started by [`IcedView::begin_drag`] while a
+//! mouse button is held on the view.
+//!
+//! [`DragSession`]: struct.DragSession.html
+//! [`IcedView::begin_drag`]: ../struct.IcedView.html#method.begin_drag
+
+use std::ffi::{c_void, CStr};
+use std::fmt;
+use std::io;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+
+use block::Block;
+
+use cocoa::base::{id, nil};
+use cocoa::foundation::{NSInteger, NSPoint, NSRect, NSSize, NSString, NSUInteger};
+
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel};
+use objc::{class, msg_send, sel, sel_impl};
+
+use crate::event::DragOperation;
+use crate::{image_from_rgba, rgba_len, Point, Size};
+
+/// The description of a drag started from the view.
+pub struct DragSession<Message> {
+    pub(crate) items: Vec<(DragItem, Option<DragImage>)>,
+    pub(crate) image: Option<DragImage>,
+    pub(crate) formation: DragFormation,
+    pub(crate) allows_move: bool,
+    pub(crate) on_end: Option<Box<dyn Fn(DragOutcome) -> Message>>,
+}
+
+impl<Message> DragSession<Message> {
+    /// Creates a drag without any item.
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            image: None,
+            formation: DragFormation::default(),
+            allows_move: false,
+            on_end: None,
+        }
+    }
+
+    /// Adds an item to the drag, which shows the image of the session.
+    pub fn push(mut self, item: DragItem) -> Self {
+        self.items.push((item, None));
+        self
+    }
+
+    /// Adds an item to the drag, which shows its own image.
+    pub fn push_with_image(mut self, item: DragItem, image: DragImage) -> Self {
+        self.items.push((item, Some(image)));
+        self
+    }
+
+    /// Sets the image of the items without their own image, e.g. a thumbnail of the preset.
+    ///
+    /// Without any image, a file shows its icon and the other items show nothing.
+    pub fn image(mut self, image: DragImage) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Sets how the images of the items are arranged during the drag.
+    pub fn formation(mut self, formation: DragFormation) -> Self {
+        self.formation = formation;
+        self
+    }
+
+    /// Sets whether the destination may move the items, e.g. the Finder moving a file within
+    /// its volume, rather than only copy or reference them.
+    pub fn allow_move(mut self, allows_move: bool) -> Self {
+        self.allows_move = allows_move;
+        self
+    }
+
+    /// The `NSDragOperation`s among which the destination chooses.
+    pub(crate) fn operation_mask(&self) -> NSUInteger {
+        // NSDragOperationCopy | NSDragOperationLink | NSDragOperationGeneric
+        let mask = 1 | 2 | 4;
+        if self.allows_move {
+            mask | DragOperation::Move.to_ns_drag_operation()
+        } else {
+            mask
+        }
+    }
+
+    /// Sets the function producing the message when the drag ends, whether the items have been
+    /// dropped or not.
+    pub fn on_end<F>(mut self, on_end: F) -> Self
+    where
+        F: 'static + Fn(DragOutcome) -> Message,
+    {
+        self.on_end = Some(Box::new(on_end));
+        self
+    }
+}
+
+impl<Message> Default for DragSession<Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Message> fmt::Debug for DragSession<Message> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DragSession")
+            .field("items", &self.items)
+            .field("image", &self.image)
+            .field("formation", &self.formation)
+            .field("allows_move", &self.allows_move)
+            .finish()
+    }
+}
+
+/// An item of a drag.
+pub enum DragItem {
+    /// An existing file.
+    File(PathBuf),
+    /// A file which is written only when it's dropped, e.g. onto the Finder.
+    FilePromise {
+        /// The uniform type identifier of the file, e.g. `public.data`.
+        file_type: String,
+        /// The name of the file, with its extension.
+        file_name: String,
+        /// Writes the file to the path, which has the `file_name` in the chosen directory.
+        write: Box<dyn Fn(&Path) -> io::Result<()>>,
+    },
+    /// A text.
+    Text(String),
+    /// The data of a custom type.
+    Data {
+        /// The uniform type identifier of the data, e.g. `com.example.preset`.
+        uti: String,
+        /// The data.
+        bytes: Vec<u8>,
+    },
+}
+
+impl DragItem {
+    /// Creates a file promise.
+    pub fn file_promise<F>(
+        file_type: impl Into<String>,
+        file_name: impl Into<String>,
+        write: F,
+    ) -> Self
+    where
+        F: 'static + Fn(&Path) -> io::Result<()>,
+    {
+        DragItem::FilePromise {
+            file_type: file_type.into(),
+            file_name: file_name.into(),
+            write: Box::new(write),
+        }
+    }
+}
+
+impl fmt::Debug for DragItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DragItem::File(path) => f.debug_tuple("File").field(path).finish(),
+            DragItem::FilePromise {
+                file_type,
+                file_name,
+                ..
+            } => f
+                .debug_struct("FilePromise")
+                .field("file_type", file_type)
+                .field("file_name", file_name)
+                .finish(),
+            DragItem::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            DragItem::Data { uti, bytes } => f
+                .debug_struct("Data")
+                .field("uti", uti)
+                .field("len", &bytes.len())
+                .finish(),
+        }
+    }
+}
+
+/// The image shown for an item during a drag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DragImage {
+    rgba: Vec<u8>,
+    size: Size<u32>,
+    anchor: Point,
+}
+
+impl DragImage {
+    /// Creates an image of the RGBA pixels.
+    ///
+    /// It panics if the pixels don't match the size. An empty image shows the default image of
+    /// the item instead.
+    pub fn new(rgba: Vec<u8>, size: Size<u32>, anchor: Point) -> Self {
+        assert_eq!(
+            Some(rgba.len()),
+            rgba_len(size),
+            "The pixels don't match the size of the image."
+        );
+        Self { rgba, size, anchor }
+    }
+
+    /// The RGBA pixels of the image.
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    /// The size of the image in pixels, which is shown at the scale factor of the view.
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    /// The point of the image under the cursor, in pixels from the top-left corner.
+    pub fn anchor(&self) -> Point {
+        self.anchor
+    }
+}
+
+/// How the images of the items are arranged during a drag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragFormation {
+    /// The arrangement chosen by the system.
+    Default,
+    /// The images are stacked on top of each other.
+    Stack,
+    /// The images are laid out in a vertical list.
+    List,
+}
+
+impl DragFormation {
+    /// The matching `NSDraggingFormation`.
+    pub(crate) fn to_ns_dragging_formation(self) -> NSInteger {
+        match self {
+            DragFormation::Default => 0,
+            DragFormation::Stack => 4,
+            DragFormation::List => 3,
+        }
+    }
+}
+
+impl Default for DragFormation {
+    fn default() -> Self {
+        DragFormation::Default
+    }
+}
+
+/// How a drag started from the view has ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DragOutcome {
+    /// What the destination has done with the items, which is `DragOperation::None` if they
+    /// haven't been dropped.
+    pub operation: DragOperation,
+    /// Where the drag has ended in the screen coordinates, whose origin is at the bottom-left
+    /// corner of the main screen.
+    pub screen_position: Point,
+}
+
+/// Creates the `NSDraggingItem`s of the session, whose frames are placed at the `position` in
+/// the view coordinates.
+///
+/// The items whose pasteboard writers couldn't be created are left out.
+pub(crate) unsafe fn make_dragging_items<Message>(
+    session: &mut DragSession<Message>,
+    position: Point,
+    scale_factor: f64,
+) -> Vec<id> {
+    let session_image = &session.image;
+    session
+        .items
+        .drain(..)
+        .filter_map(|(item, item_image)| {
+            let (writer, icon) = make_writer(item)?;
+            let alloc: id = msg_send![class!(NSDraggingItem), alloc];
+            let dragging_item: id = msg_send![alloc, initWithPasteboardWriter: writer];
+            let () = msg_send![writer, release];
+
+            let image = item_image.as_ref().or_else(|| session_image.as_ref());
+            match image.and_then(|image| Some((image, image_from_rgba(&image.rgba, image.size)?))) {
+                Some((image, contents)) => {
+                    let size = NSSize::new(
+                        f64::from(image.size.width) / scale_factor,
+                        f64::from(image.size.height) / scale_factor,
+                    );
+                    let () = msg_send![contents, setSize: size];
+                    // The view is flipped, so the origin is the top-left corner.
+                    let origin = NSPoint::new(
+                        f64::from(position.x) - f64::from(image.anchor.x) / scale_factor,
+                        f64::from(position.y) - f64::from(image.anchor.y) / scale_factor,
+                    );
+                    let () = msg_send![
+                        dragging_item,
+                        setDraggingFrame: NSRect::new(origin, size)
+                        contents: contents
+                    ];
+                    // The dragging item keeps the image until the session is done with it.
+                    let () = msg_send![contents, release];
+                }
+                None => {
+                    let size = NSSize::new(ICON_SIZE, ICON_SIZE);
+                    let origin = NSPoint::new(
+                        f64::from(position.x) - ICON_SIZE / 2.0,
+                        f64::from(position.y) - ICON_SIZE / 2.0,
+                    );
+                    let () = msg_send![
+                        dragging_item,
+                        setDraggingFrame: NSRect::new(origin, size)
+                        contents: icon
+                    ];
+                }
+            }
+            Some(dragging_item)
+        })
+        .collect()
+}
+
+const ICON_SIZE: f64 = 32.0;
+
+/// Creates the pasteboard writer of the item, and the icon shown during the drag.
+///
+/// It returns `None` if the class of the delegates of the file promises couldn't be declared.
+unsafe fn make_writer(item: DragItem) -> Option<(id, id)> {
+    let writer = match item {
+        DragItem::File(path) => {
+            let path = NSString::alloc(nil).init_str(&path.to_string_lossy());
+            let url: id = msg_send![class!(NSURL), alloc];
+            let url: id = msg_send![url, initFileURLWithPath: path];
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let icon: id = msg_send![workspace, iconForFile: path];
+            let () = msg_send![path, release];
+            (url, icon)
+        }
+        DragItem::FilePromise {
+            file_type,
+            file_name,
+            write,
+        } => {
+            let delegate_class = match file_promise_delegate_class() {
+                Some(class) => class,
+                None => {
+                    log::error!(
+                        "The class of the delegates of the file promises couldn't be declared."
+                    );
+                    return None;
+                }
+            };
+            let file_type = NSString::alloc(nil).init_str(&file_type);
+            let delegate: id = msg_send![delegate_class, new];
+            let file_name = NSString::alloc(nil).init_str(&file_name);
+            (*delegate).set_ivar::<id>(FILE_NAME_IVAR, file_name);
+            let write: Box<Writer> = Box::new(write);
+            (*delegate).set_ivar::<*mut c_void>(WRITER_IVAR, Box::into_raw(write).cast());
+
+            let alloc: id = msg_send![class!(NSFilePromiseProvider), alloc];
+            let provider: id = msg_send![alloc, initWithFileType: file_type delegate: delegate];
+            // The delegate is weak, so the provider keeps it as its user info.
+            let () = msg_send![provider, setUserInfo: delegate];
+            let () = msg_send![delegate, release];
+            let () = msg_send![file_type, release];
+            (provider, nil)
+        }
+        DragItem::Text(text) => (NSString::alloc(nil).init_str(&text), nil),
+        DragItem::Data { uti, bytes } => {
+            let item: id = msg_send![class!(NSPasteboardItem), new];
+            let data: id = msg_send![
+                class!(NSData),
+                dataWithBytes: bytes.as_ptr()
+                length: bytes.len()
+            ];
+            let uti = NSString::alloc(nil).init_str(&uti);
+            let () = msg_send![item, setData: data forType: uti];
+            let () = msg_send![uti, release];
+            (item, nil)
+        }
+    };
+    Some(writer)
+}
+
+type Writer = Box<dyn Fn(&Path) -> io::Result<()>>;
+
+const FILE_NAME_IVAR: &str = "_file_name";
+const WRITER_IVAR: &str = "_writer";
+
+/// The class of the delegates of the file promises, which write the files when they're dropped,
+/// declared on the first call.
+///
+/// Its name is unique to the binary, like the one of the view, so the copies of the crate in
+/// the plug-ins loaded by the same host don't clash.
+fn file_promise_delegate_class() -> Option<&'static Class> {
+    let name = format!(
+        "IcedFilePromiseDelegate_{:x}",
+        file_promise_delegate_class as usize
+    );
+    if let Some(class) = Class::get(&name) {
+        return Some(class);
+    }
+
+    let mut decl = ClassDecl::new(&name, class!(NSObject))?;
+    decl.add_ivar::<id>(FILE_NAME_IVAR);
+    decl.add_ivar::<*mut c_void>(WRITER_IVAR);
+
+    extern "C" fn file_name(this: &Object, _cmd: Sel, _provider: id, _file_type: id) -> id {
+        unsafe { *this.get_ivar::<id>(FILE_NAME_IVAR) }
+    }
+
+    // The promise is written on the main queue, which is the default of the provider.
+    extern "C" fn write_promise(
+        this: &Object,
+        _cmd: Sel,
+        _provider: id,
+        url: id,
+        completion_handler: *mut c_void,
+    ) {
+        unsafe {
+            let write = *this.get_ivar::<*mut c_void>(WRITER_IVAR) as *const Writer;
+            let path: id = msg_send![url, path];
+            let path: *const c_char = msg_send![path, UTF8String];
+            let path = PathBuf::from(CStr::from_ptr(path).to_string_lossy().into_owned());
+            let error = match (*write)(&path) {
+                Ok(()) => nil,
+                Err(error) => make_error(&error.to_string()),
+            };
+            let completion_handler = completion_handler as *const Block<(id,), ()>;
+            (*completion_handler).call((error,));
+        }
+    }
+
+    extern "C" fn dealloc(this: &mut Object, _cmd: Sel) {
+        unsafe {
+            let write = *this.get_ivar::<*mut c_void>(WRITER_IVAR) as *mut Writer;
+            if !write.is_null() {
+                drop(Box::from_raw(write));
+            }
+            let file_name = *this.get_ivar::<id>(FILE_NAME_IVAR);
+            let () = msg_send![file_name, release];
+            let () = msg_send![super(this, class!(NSObject)), dealloc];
+        }
+    }
+
+    unsafe {
+        let file_name: extern "C" fn(&Object, Sel, id, id) -> id = file_name;
+        decl.add_method(sel!(filePromiseProvider:fileNameForType:), file_name);
+        let write_promise: extern "C" fn(&Object, Sel, id, id, *mut c_void) = write_promise;
+        decl.add_method(
+            sel!(filePromiseProvider:writePromiseToURL:completionHandler:),
+            write_promise,
+        );
+        let dealloc: extern "C" fn(&mut Object, Sel) = dealloc;
+        decl.add_method(sel!(dealloc), dealloc);
+    }
+
+    Some(decl.register())
+}
+
+/// Creates an autoreleased `NSError` with the description.
+unsafe fn make_error(description: &str) -> id {
+    let domain = NSString::alloc(nil).init_str("iced-nsview");
+    let description = NSString::alloc(nil).init_str(description);
+    let key = NSString::alloc(nil).init_str("NSLocalizedDescription");
+    let user_info: id =
+        msg_send![class!(NSDictionary), dictionaryWithObject: description forKey: key];
+    let code: NSInteger = 0;
+    let error: id =
+        msg_send![class!(NSError), errorWithDomain: domain code: code userInfo: user_info];
+    let () = msg_send![domain, release];
+    let () = msg_send![description, release];
+    let () = msg_send![key, release];
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_sessions_allowing_it_can_be_moved() {
+        let session = DragSession::<()>::new();
+        assert_eq!(
+            DragOperation::from_ns_drag_operation(session.operation_mask()),
+            DragOperation::Copy
+        );
+
+        let session = session.allow_move(true);
+        assert_eq!(
+            DragOperation::from_ns_drag_operation(session.operation_mask()),
+            DragOperation::Move
+        );
+    }
+}
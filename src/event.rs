@@ -0,0 +1,620 @@
+//! Events produced by the view that have no counterpart in iced.
+//!
+//! These events are delivered to [`Application::on_event`], which can turn them into messages.
+//!
+//! [`Application::on_event`]: ../trait.Application.html#method.on_event
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use cocoa::appkit::{NSEvent, NSEventPhase, NSEventType};
+use cocoa::base::id;
+
+use cocoa::foundation::{NSInteger, NSTimeInterval, NSUInteger};
+
+use objc::{msg_send, sel, sel_impl};
+
+use crate::keys::ModifierFlags;
+use crate::{keyboard, mouse, NSEventT, Object, Point, Size, Vector};
+
+/// An event produced by the view.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The phase of a scroll gesture has changed.
+    ///
+    /// It's queued along with the `WheelScrolled` event which caused it.
+    ScrollPhase(ScrollPhase),
+    /// A mouse event which is not covered by iced.
+    Mouse(MouseEvent),
+    /// A trackpad gesture has been performed.
+    Gesture(GestureEvent),
+    /// The pressure of a click on a Force Touch trackpad has changed.
+    Pressure(PressureEvent),
+    /// A graphics tablet has produced an event.
+    Tablet(TabletEvent),
+    /// The text being composed with an input method has changed.
+    ///
+    /// The composed text isn't committed yet, so it should be only displayed. When the
+    /// composition is finished, the text is empty and the committed characters are delivered as
+    /// `CharacterReceived` events.
+    MarkedText(String),
+    /// The state of the modifiers has changed.
+    ///
+    /// It's tracked from all the native events, not only from the key presses, so it's right
+    /// for the clicks and the drags, see [`IcedView::modifiers`].
+    ///
+    /// [`IcedView::modifiers`]: ../struct.IcedView.html#method.modifiers
+    Modifiers(keyboard::ModifiersState),
+    /// The state of the modifiers not covered by iced has changed.
+    ExtraModifiers(ExtraModifiers),
+    /// The Fn (Globe) key has been pressed or released, which has no iced key code.
+    ///
+    /// It's delivered after the `ExtraModifiers` event of the change.
+    FunctionKey {
+        /// Whether the key has been pressed rather than released.
+        is_pressed: bool,
+    },
+    /// The state of the modifiers has changed while files are dragged over the view, e.g. to
+    /// add the dropped files instead of replacing the current ones while Option is held.
+    ///
+    /// It's delivered before the `FileHovered` and `FileDropped` events. The state is `None`
+    /// once the drag has ended.
+    DragModifiers(Option<keyboard::ModifiersState>),
+    /// The view has gained or lost the keyboard focus.
+    ///
+    /// The view has the focus while it's the first responder of the key window.
+    FocusChanged(bool),
+    /// A drag and drop event which is not covered by iced.
+    ///
+    /// The accepted kinds are chosen with [`Settings::accepted_drop_types`].
+    ///
+    /// [`Settings::accepted_drop_types`]: ../struct.Settings.html#structfield.accepted_drop_types
+    DragDrop(DragDropEvent),
+    /// The cursor has moved during an internal drag started by [`IcedView::begin_internal_drag`].
+    ///
+    /// [`IcedView::begin_internal_drag`]: ../struct.IcedView.html#method.begin_internal_drag
+    InternalDragMoved {
+        /// The position of the cursor in the view coordinates.
+        position: Point,
+    },
+    /// The left mouse button has been released during an internal drag, which ends it.
+    ///
+    /// It's delivered before the `ButtonReleased` event, and the message produced by
+    /// [`Application::on_internal_drop`] is handled before the messages of the release.
+    ///
+    /// [`Application::on_internal_drop`]: ../trait.Application.html#method.on_internal_drop
+    InternalDragDropped {
+        /// The position of the drop in the view coordinates.
+        position: Point,
+    },
+    /// Something has been copied to the clipboard, which has the data of these types now.
+    ///
+    /// It's checked when the view is redrawn and when its window becomes key, e.g. to enable a
+    /// paste button.
+    ClipboardChanged(Vec<String>),
+    /// The display is about to refresh while the view is animating, which is followed by a
+    /// redraw.
+    ///
+    /// See [`IcedView::set_animating`].
+    ///
+    /// [`IcedView::set_animating`]: ../struct.IcedView.html#method.set_animating
+    AnimationFrame {
+        /// When the frame will be shown, on the clock of the display.
+        timestamp: Duration,
+    },
+    /// The window of the view has been fully covered or minimized, if true, or has become
+    /// visible again.
+    ///
+    /// Nothing is drawn meanwhile, so the application can pause its expensive computations too.
+    Occluded(bool),
+    /// The device has been lost and requested again, along with the resources of the view.
+    ///
+    /// The resources created in `Application::setup_gpu` belong to the lost device, so the
+    /// application creates them again with the new one, e.g. in its next custom drawing.
+    GpuRecreated,
+    /// A media key has been pressed or released.
+    ///
+    /// The volume keys are delivered as regular key events instead.
+    MediaKey(MediaKeyEvent),
+}
+
+/// The state of the modifiers not covered by [`keyboard::ModifiersState`].
+///
+/// [`keyboard::ModifiersState`]: ../keyboard/struct.ModifiersState.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExtraModifiers {
+    /// Whether Caps Lock is engaged.
+    pub caps_lock: bool,
+    /// Whether the Fn (Globe) key is held.
+    pub function: bool,
+}
+
+/// An iced event with the time it has been produced at.
+///
+/// The recent events are kept by the view, see [`IcedView::recent_events`].
+///
+/// [`IcedView::recent_events`]: ../struct.IcedView.html#method.recent_events
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedEvent {
+    /// The time of the native event the iced event has been converted from.
+    pub time: Instant,
+    /// The event.
+    pub event: iced_native::Event,
+}
+
+/// A native event received by the view, before it's converted.
+///
+/// It's given to [`Application::on_native_event`] for the details iced doesn't cover. The event
+/// is only valid during that call, so neither the reference nor the [`raw`] pointer may be kept.
+///
+/// [`Application::on_native_event`]: ../trait.Application.html#method.on_native_event
+/// [`raw`]: #method.raw
+pub struct NativeEventRef<'a> {
+    event: NSEventT<id>,
+    _phantom: PhantomData<&'a Object>,
+}
+
+impl<'a> NativeEventRef<'a> {
+    pub(crate) fn new(raw_event: id, view: *mut Object) -> Self {
+        Self {
+            event: NSEventT { raw_event, view },
+            _phantom: PhantomData,
+        }
+    }
+
+    /// The type of the event.
+    pub fn event_type(&self) -> NSEventType {
+        unsafe { NSEvent::eventType(self.event.raw_event) }
+    }
+
+    /// The subtype of the event, e.g. telling the tablet events apart.
+    pub fn subtype(&self) -> i16 {
+        unsafe { msg_send![self.event.raw_event, subtype] }
+    }
+
+    /// The location of the event in the logical view coordinates.
+    ///
+    /// It's meaningful only for the mouse events.
+    pub fn location(&self) -> Point {
+        unsafe { self.event.location() }
+    }
+
+    /// The state of the modifiers when the event has occurred.
+    pub fn modifiers(&self) -> keyboard::ModifiersState {
+        unsafe { ModifierFlags(NSEvent::modifierFlags(self.event.raw_event)).into() }
+    }
+
+    /// The time of the event in seconds since the system startup.
+    pub fn timestamp(&self) -> NSTimeInterval {
+        unsafe { NSEvent::timestamp(self.event.raw_event) }
+    }
+
+    /// The `NSEvent` itself, for the details not covered by the other methods.
+    ///
+    /// The pointer is valid only during [`Application::on_native_event`].
+    ///
+    /// [`Application::on_native_event`]: ../trait.Application.html#method.on_native_event
+    pub fn raw(&self) -> *mut Object {
+        self.event.raw_event
+    }
+}
+
+impl fmt::Debug for NativeEventRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativeEventRef")
+            .field("event_type", &self.event_type())
+            .field("subtype", &self.subtype())
+            .finish()
+    }
+}
+
+/// What the view does with a native event after [`Application::on_native_event`].
+///
+/// [`Application::on_native_event`]: ../trait.Application.html#method.on_native_event
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeEventDisposition<Message> {
+    /// The event is converted and delivered as usual.
+    Pass,
+    /// The event is dropped, so it's neither converted nor delivered.
+    Consume,
+    /// The event is converted and delivered as usual, and the message is produced as well.
+    Produce(Message),
+}
+
+impl<Message> Default for NativeEventDisposition<Message> {
+    fn default() -> Self {
+        NativeEventDisposition::Pass
+    }
+}
+
+/// What a drop onto the view would do, as told by [`Application::drag_operation`].
+///
+/// The cursor shows the operation during the drag.
+///
+/// [`Application::drag_operation`]: ../trait.Application.html#method.drag_operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragOperation {
+    /// The dragged items are copied.
+    Copy,
+    /// The dragged items are referenced.
+    Link,
+    /// The dragged items are moved.
+    Move,
+    /// The drag is rejected, so it isn't reported and nothing can be dropped.
+    None,
+}
+
+impl DragOperation {
+    /// The matching `NSDragOperation`.
+    pub(crate) fn to_ns_drag_operation(self) -> NSUInteger {
+        match self {
+            DragOperation::Copy => 1,
+            DragOperation::Link => 2,
+            DragOperation::Move => 16,
+            DragOperation::None => 0,
+        }
+    }
+
+    /// The operation performed by the destination of a drag, out of the `NSDragOperation` mask.
+    pub(crate) fn from_ns_drag_operation(operation: NSUInteger) -> Self {
+        if operation == 0 {
+            DragOperation::None
+        } else if operation & 16 != 0 {
+            DragOperation::Move
+        } else if operation & 1 == 0 && operation & 2 != 0 {
+            DragOperation::Link
+        } else {
+            DragOperation::Copy
+        }
+    }
+}
+
+impl Default for DragOperation {
+    fn default() -> Self {
+        DragOperation::Copy
+    }
+}
+
+/// A drag and drop event which is not covered by iced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DragDropEvent {
+    /// A file has been dropped. It's delivered along with `window::Event::FileDropped`.
+    ///
+    /// The files dropped together share the position. The promised files, e.g. from Mail, are
+    /// in a temporary directory which is removed after the update, so they're read or copied
+    /// meanwhile.
+    FileDroppedAt {
+        /// The path of the dropped file.
+        path: PathBuf,
+        /// The position of the drop in the view coordinates.
+        position: Point,
+    },
+    /// A text has been dropped.
+    TextDropped {
+        /// The dropped text.
+        text: String,
+        /// The position of the drop in the view coordinates.
+        position: Point,
+    },
+    /// An image has been dropped, e.g. from Photos or a browser.
+    ImageDropped {
+        /// The RGBA pixels of the image, whose colors aren't premultiplied by their alpha.
+        rgba: Vec<u8>,
+        /// The size of the image in pixels.
+        size: Size<u32>,
+        /// The position of the drop in the view coordinates.
+        position: Point,
+    },
+    /// The data of a custom type has been dropped.
+    DataDropped {
+        /// The uniform type identifier of the data.
+        uti: String,
+        /// The dropped data.
+        bytes: Vec<u8>,
+        /// The position of the drop in the view coordinates.
+        position: Point,
+    },
+    /// A promised file, e.g. an attachment dragged from Mail, couldn't be received.
+    ///
+    /// The received files are delivered as `window::Event::FileDropped` after the drop.
+    FilePromiseFailed {
+        /// The description of the error given by the system.
+        description: String,
+    },
+}
+
+/// A mouse event which is not covered by iced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseEvent {
+    /// A button has been pressed several times in a row.
+    ///
+    /// It's queued after the corresponding `ButtonPressed` event.
+    MultiClick {
+        /// The pressed button.
+        button: mouse::Button,
+        /// The number of clicks, which is 2 for a double-click and 3 for a triple-click.
+        count: u32,
+        /// The position of the cursor in the view coordinates.
+        position: Point,
+    },
+    /// The mouse has moved while the pointer is locked.
+    ///
+    /// It's delivered instead of `CursorMoved`, since the cursor stays still, see
+    /// [`IcedView::begin_pointer_lock`].
+    ///
+    /// [`IcedView::begin_pointer_lock`]: ../struct.IcedView.html#method.begin_pointer_lock
+    RelativeMotion {
+        /// The horizontal motion in logical pixels.
+        dx: f32,
+        /// The vertical motion in logical pixels, which is positive downwards.
+        dy: f32,
+    },
+}
+
+/// A trackpad gesture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GestureEvent {
+    /// The user is pinching to zoom.
+    Magnify {
+        /// The change of the magnification since the previous event. A positive value means
+        /// zooming in.
+        delta: f32,
+        /// The position of the cursor in the view coordinates.
+        position: Point,
+        /// The phase of the gesture.
+        phase: Phase,
+    },
+    /// The user is rotating two fingers.
+    Rotate {
+        /// The rotation in degrees since the previous event. A positive value means
+        /// counterclockwise rotation.
+        delta: f32,
+        /// The position of the cursor in the view coordinates.
+        position: Point,
+        /// The phase of the gesture.
+        phase: Phase,
+    },
+    /// The user has double-tapped with two fingers, which usually means "zoom to fit".
+    SmartMagnify {
+        /// The position of the cursor in the view coordinates.
+        position: Point,
+    },
+    /// The user has swiped with several fingers.
+    Swipe {
+        /// The direction of the swipe.
+        direction: SwipeDirection,
+        /// The state of the modifier keys during the swipe.
+        modifiers: keyboard::ModifiersState,
+    },
+}
+
+/// The direction of a swipe gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    /// The fingers moved to the left.
+    Left,
+    /// The fingers moved to the right.
+    Right,
+    /// The fingers moved up.
+    Up,
+    /// The fingers moved down.
+    Down,
+}
+
+/// A change of the click pressure on a Force Touch trackpad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PressureEvent {
+    /// The pressure has changed within a stage.
+    Changed {
+        /// The current stage. It's 1 for a normal click and 2 for a force click.
+        stage: u8,
+        /// The pressure within the stage, between 0 and 1.
+        pressure: f32,
+        /// The position of the cursor in the view coordinates.
+        position: Point,
+    },
+    /// The click has transitioned to another stage.
+    ///
+    /// It's produced once per transition, so a transition to the stage 2 is a force click.
+    StageChanged {
+        /// The new stage.
+        stage: u8,
+        /// The position of the cursor in the view coordinates.
+        position: Point,
+    },
+}
+
+/// An event of a graphics tablet.
+///
+/// The tablet also moves the cursor, so the regular mouse events are delivered as well.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TabletEvent {
+    /// The pointing device has moved or its pressure has changed.
+    Point {
+        /// The position of the pointing device in the view coordinates.
+        position: Point,
+        /// The pressure, between 0 and 1.
+        pressure: f32,
+        /// The tilt along both axes, between -1 and 1.
+        tilt: Vector,
+    },
+    /// The pointing device has entered or left the proximity of the tablet.
+    Proximity {
+        /// Whether the device is entering the proximity.
+        is_entering: bool,
+        /// The kind of the pointing device.
+        device: PointingDevice,
+    },
+}
+
+/// An event of a media key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKeyEvent {
+    /// The key has been pressed. It's repeated while the key is held.
+    Pressed {
+        /// The pressed key.
+        key: MediaKey,
+        /// Whether the press has been repeated by holding the key.
+        is_repeat: bool,
+    },
+    /// The key has been released.
+    Released(MediaKey),
+}
+
+/// A media key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKey {
+    /// The play/pause key.
+    PlayPause,
+    /// The fast-forward key.
+    FastForward,
+    /// The rewind key.
+    Rewind,
+    /// The next track key.
+    NextTrack,
+    /// The previous track key.
+    PreviousTrack,
+}
+
+impl MediaKey {
+    // The NX_KEYTYPE_* constants of IOKit.
+    pub(crate) fn from_key_type(key_type: NSInteger) -> Option<Self> {
+        match key_type {
+            16 => Some(MediaKey::PlayPause),
+            17 => Some(MediaKey::NextTrack),
+            18 => Some(MediaKey::PreviousTrack),
+            19 => Some(MediaKey::FastForward),
+            20 => Some(MediaKey::Rewind),
+            _ => None,
+        }
+    }
+}
+
+/// The kind of a tablet pointing device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointingDevice {
+    /// The device is unknown.
+    Unknown,
+    /// The tip of a pen.
+    Pen,
+    /// A cursor, also known as a puck.
+    Cursor,
+    /// The eraser end of a pen.
+    Eraser,
+}
+
+impl PointingDevice {
+    pub(crate) fn from_device_type(device_type: NSUInteger) -> Self {
+        match device_type {
+            1 => PointingDevice::Pen,
+            2 => PointingDevice::Cursor,
+            3 => PointingDevice::Eraser,
+            _ => PointingDevice::Unknown,
+        }
+    }
+}
+
+/// A phase of a continuous gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// The gesture has started.
+    Began,
+    /// The gesture is in progress.
+    Changed,
+    /// The gesture has finished.
+    Ended,
+    /// The gesture has been cancelled by the system.
+    Cancelled,
+}
+
+impl From<NSEventPhase> for Phase {
+    fn from(phase: NSEventPhase) -> Self {
+        if phase.contains(NSEventPhase::NSEventPhaseBegan) {
+            Phase::Began
+        } else if phase.contains(NSEventPhase::NSEventPhaseEnded) {
+            Phase::Ended
+        } else if phase.contains(NSEventPhase::NSEventPhaseCancelled) {
+            Phase::Cancelled
+        } else {
+            Phase::Changed
+        }
+    }
+}
+
+/// A phase of a scroll gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollPhase {
+    /// The user has started scrolling.
+    Began,
+    /// The user has finished scrolling.
+    Ended,
+    /// The scroll has been cancelled by the system.
+    Cancelled,
+    /// The inertial scrolling has started after the user lifted the fingers.
+    MomentumBegan,
+    /// The inertial scrolling has finished.
+    MomentumEnded,
+}
+
+impl ScrollPhase {
+    pub(crate) fn from_phases(phase: NSEventPhase, momentum_phase: NSEventPhase) -> Option<Self> {
+        if momentum_phase.contains(NSEventPhase::NSEventPhaseBegan) {
+            Some(ScrollPhase::MomentumBegan)
+        } else if momentum_phase
+            .intersects(NSEventPhase::NSEventPhaseEnded | NSEventPhase::NSEventPhaseCancelled)
+        {
+            Some(ScrollPhase::MomentumEnded)
+        } else if phase.contains(NSEventPhase::NSEventPhaseBegan) {
+            Some(ScrollPhase::Began)
+        } else if phase.contains(NSEventPhase::NSEventPhaseEnded) {
+            Some(ScrollPhase::Ended)
+        } else if phase.contains(NSEventPhase::NSEventPhaseCancelled) {
+            Some(ScrollPhase::Cancelled)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_operations_round_trip() {
+        vec![
+            DragOperation::Copy,
+            DragOperation::Link,
+            DragOperation::Move,
+            DragOperation::None,
+        ]
+        .into_iter()
+        .for_each(|operation| {
+            assert_eq!(
+                DragOperation::from_ns_drag_operation(operation.to_ns_drag_operation()),
+                operation
+            )
+        });
+    }
+
+    #[test]
+    fn drag_operations_prefer_move_then_copy() {
+        // NSDragOperationCopy | NSDragOperationLink | NSDragOperationGeneric
+        assert_eq!(
+            DragOperation::from_ns_drag_operation(1 | 2 | 4),
+            DragOperation::Copy
+        );
+        // NSDragOperationEvery
+        assert_eq!(
+            DragOperation::from_ns_drag_operation(NSUInteger::max_value()),
+            DragOperation::Move
+        );
+        // NSDragOperationGeneric alone is copied.
+        assert_eq!(
+            DragOperation::from_ns_drag_operation(4),
+            DragOperation::Copy
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! The GPU device of the views.
+//!
+//! Each view draws with a device, which several views can share through a [`GpuContext`], e.g.
+//! the editors of several instances of a plugin.
+//!
+//! The device shared by default is the one of the thread. As AppKit only lets the views live on
+//! the main thread, it's the one of the whole process.
+//!
+//! [`GpuContext`]: struct.GpuContext.html
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+
+use iced_wgpu::wgpu;
+
+use crate::{BackendBit, Error, Extensions, Limits, PowerPreference, Settings};
+
+/// The most bind groups wgpu can give a device, which is `MAX_BIND_GROUPS` of `wgpu-core`.
+///
+/// wgpu doesn't report the limits of the adapters, nor re-export the constant, so it's repeated
+/// here to check the limits before the device is requested.
+const MAX_BIND_GROUPS: u32 = 4;
+
+thread_local! {
+    /// The context shared by the views by default, which lives while any of them does.
+    static SHARED: RefCell<Weak<Gpu>> = RefCell::new(Weak::new());
+}
+
+/// A GPU device and its queue, which the views created with it share.
+///
+/// The device is released when the last view using it is dropped.
+#[derive(Clone)]
+pub struct GpuContext {
+    gpu: Arc<Gpu>,
+}
+
+struct Gpu {
+    device: Mutex<wgpu::Device>,
+    queue: wgpu::Queue,
+    extensions: Extensions,
+    limits: Limits,
+    /// The name of the adapter of the device, if it isn't the one of the host.
+    adapter_name: Option<String>,
+    /// What the device has been requested with, if it isn't the one of the host.
+    request: Option<Request>,
+    /// The device requested again after this one has been lost, shared by all its views.
+    replacement: Mutex<Weak<Gpu>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Request {
+    power_preference: PowerPreference,
+    backends: BackendBit,
+}
+
+impl GpuContext {
+    /// Requests a device with the power preference, the backends, the extensions and the limits
+    /// of the settings.
+    ///
+    /// The device can present to any view, as all the adapters of Metal can.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the device can't be requested, which [`try_new`] reports instead.
+    ///
+    /// [`try_new`]: #method.try_new
+    pub fn new(settings: &Settings) -> Self {
+        Self::try_new(settings).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Requests a device with the settings, returning the error if the limits are beyond what
+    /// wgpu can give or if there's no adapter.
+    pub fn try_new(settings: &Settings) -> Result<Self, Error> {
+        let request = Request {
+            power_preference: settings.power_preference,
+            backends: settings.backends,
+        };
+        Self::request(
+            request,
+            settings.extensions.clone(),
+            settings.limits.clone(),
+        )
+    }
+
+    fn request(request: Request, extensions: Extensions, limits: Limits) -> Result<Self, Error> {
+        check_limits(&limits)?;
+
+        let adapter = crate::futures::executor::block_on(wgpu::Adapter::request(
+            &wgpu::RequestAdapterOptions {
+                power_preference: request.power_preference,
+                compatible_surface: None,
+            },
+            request.backends,
+        ))
+        .ok_or(Error::AdapterNotFound)?;
+        let info = adapter.get_info();
+        log::info!("Using the {} adapter with {:?}.", info.name, info.backend);
+
+        let (device, queue) =
+            crate::futures::executor::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+                extensions: extensions.clone(),
+                limits: limits.clone(),
+            }));
+
+        Ok(Self {
+            gpu: Arc::new(Gpu {
+                device: Mutex::new(device),
+                queue,
+                extensions,
+                limits,
+                adapter_name: Some(info.name),
+                request: Some(request),
+                replacement: Mutex::new(Weak::new()),
+            }),
+        })
+    }
+
+    /// Wraps the device of the host, which it has been requested with the extensions and the
+    /// limits.
+    ///
+    /// The host draws with it through the context afterwards.
+    pub fn from_device(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        extensions: Extensions,
+        limits: Limits,
+    ) -> Self {
+        Self {
+            gpu: Arc::new(Gpu {
+                device: Mutex::new(device),
+                queue,
+                extensions,
+                limits,
+                adapter_name: None,
+                request: None,
+                replacement: Mutex::new(Weak::new()),
+            }),
+        }
+    }
+
+    /// Returns the context shared by the views of the thread, requesting the device with the
+    /// settings if none of them is alive.
+    ///
+    /// The settings of the device of a later view are ignored, with a warning if they differ.
+    pub(crate) fn shared(settings: &Settings) -> Result<Self, Error> {
+        SHARED.with(|shared| {
+            if let Some(gpu) = shared.borrow().upgrade() {
+                let request = Request {
+                    power_preference: settings.power_preference,
+                    backends: settings.backends,
+                };
+                if gpu.request != Some(request)
+                    || gpu.extensions != settings.extensions
+                    || gpu.limits != settings.limits
+                {
+                    log::warn!(
+                        "The settings of the device differ from the ones of the shared device, \
+                         which is used with its own."
+                    );
+                }
+                return Ok(Self { gpu });
+            }
+
+            let context = Self::try_new(settings)?;
+            *shared.borrow_mut() = Arc::downgrade(&context.gpu);
+            Ok(context)
+        })
+    }
+
+    /// Requests the device again, as it's been lost, returning `None` if it's the one of the
+    /// host, which can't be requested by the views.
+    ///
+    /// The views of the context get the same new device, which replaces the shared one.
+    pub(crate) fn recreate(&self) -> Option<Result<Self, Error>> {
+        let request = self.gpu.request?;
+        let mut replacement = self
+            .gpu
+            .replacement
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(gpu) = replacement.upgrade() {
+            return Some(Ok(Self { gpu }));
+        }
+
+        let context = match Self::request(
+            request,
+            self.gpu.extensions.clone(),
+            self.gpu.limits.clone(),
+        ) {
+            Ok(context) => context,
+            Err(error) => return Some(Err(error)),
+        };
+        *replacement = Arc::downgrade(&context.gpu);
+        SHARED.with(|shared| {
+            let is_shared = shared
+                .borrow()
+                .upgrade()
+                .map_or(false, |gpu| Arc::ptr_eq(&gpu, &self.gpu));
+            if is_shared {
+                *shared.borrow_mut() = Arc::downgrade(&context.gpu);
+            }
+        });
+        Some(Ok(context))
+    }
+
+    /// The optional features of the device.
+    pub fn extensions(&self) -> &Extensions {
+        &self.gpu.extensions
+    }
+
+    /// The limits of the device.
+    pub fn limits(&self) -> &Limits {
+        &self.gpu.limits
+    }
+
+    /// The name of the adapter the device has been requested from, which is unknown for the
+    /// device of the host.
+    pub(crate) fn adapter_name(&self) -> Option<&str> {
+        self.gpu.adapter_name.as_deref()
+    }
+
+    /// Locks the device, e.g. for the host to draw with it.
+    ///
+    /// The views and the renderers using the context can't draw until it's unlocked.
+    pub fn device(&self) -> MutexGuard<'_, wgpu::Device> {
+        // The device stays usable after a panic of another view.
+        self.gpu
+            .device
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The queue of the device.
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.gpu.queue
+    }
+}
+
+impl fmt::Debug for GpuContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GpuContext")
+            .field("extensions", &self.gpu.extensions)
+            .field("limits", &self.gpu.limits)
+            .finish()
+    }
+}
+
+/// Checks the limits against what wgpu can give, as requesting more would panic.
+fn check_limits(limits: &Limits) -> Result<(), Error> {
+    if limits.max_bind_groups > MAX_BIND_GROUPS {
+        return Err(Error::TooManyBindGroups {
+            requested: limits.max_bind_groups,
+            max: MAX_BIND_GROUPS,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_beyond_wgpu_are_an_error() {
+        assert_eq!(check_limits(&Limits::default()), Ok(()));
+        assert_eq!(
+            check_limits(&Limits {
+                max_bind_groups: MAX_BIND_GROUPS + 1,
+            }),
+            Err(Error::TooManyBindGroups {
+                requested: MAX_BIND_GROUPS + 1,
+                max: MAX_BIND_GROUPS,
+            })
+        );
+    }
+}
@@ -0,0 +1,433 @@
+//! Rendering into the textures of the host.
+//!
+//! A host which already renders with wgpu can composite the interface into its own frames with a
+//! [`HeadlessRenderer`], instead of the layer of an [`IcedView`], and drive the presentation
+//! itself, with the device wrapped with [`GpuContext::from_device`]. The view draws with one
+//! too, around its swap chain.
+//!
+//! Without any view nor host, a renderer created with [`HeadlessRenderer::with_settings`] runs
+//! the interface on a machine without a display, e.g. for its tests, and draws it with
+//! [`HeadlessRenderer::capture`].
+//!
+//! [`HeadlessRenderer`]: struct.HeadlessRenderer.html
+//! [`IcedView`]: ../struct.IcedView.html
+//! [`GpuContext::from_device`]: ../gpu/struct.GpuContext.html#method.from_device
+//! [`HeadlessRenderer::with_settings`]: struct.HeadlessRenderer.html#method.with_settings
+//! [`HeadlessRenderer::capture`]: struct.HeadlessRenderer.html#method.capture
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Write};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use iced_native::{program, Clipboard, Debug, Event};
+use iced_wgpu::{wgpu, Backend, Primitive, Renderer, Settings as RendererSettings};
+
+use crate::capture::{self, CaptureError, RgbaImage};
+use crate::clipboard::{ClipboardBackend, WidgetClipboard};
+use crate::gpu::GpuContext;
+use crate::{
+    mouse, supported_antialiasing, Application, ColorSpace, Error, Program, Settings, Viewport,
+};
+
+/// What the custom drawing of the application is recorded with.
+pub struct CustomDrawContext<'a> {
+    /// The device.
+    pub device: &'a wgpu::Device,
+    /// The encoder recording the frame.
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    /// The texture the frame is drawn into.
+    pub target: &'a wgpu::TextureView,
+    /// The viewport of the frame.
+    pub viewport: &'a Viewport,
+    /// The time since the previous frame, which is zero for the first one.
+    pub frame_time: Duration,
+}
+
+/// The state and the renderer of an application, without any Cocoa object.
+pub struct HeadlessRenderer<A: 'static + Application> {
+    state: program::State<Program<A>>,
+    renderer: Renderer,
+    debug: Debug,
+    gpu: GpuContext,
+    format: wgpu::TextureFormat,
+    /// The color space the background color is converted into.
+    color_space: ColorSpace,
+    /// What the renderer is created with again when the device changes.
+    renderer_settings: RendererSettings,
+    shows_debug_overlay: bool,
+    /// When the previous frame has been drawn.
+    last_frame_at: Option<Instant>,
+    /// The hash of what the last update has produced to draw.
+    appearance_hash: u64,
+}
+
+impl<A: 'static + Application> HeadlessRenderer<A> {
+    /// Creates the renderer drawing into the textures of the format with the device of the
+    /// context.
+    ///
+    /// The settings of the rendering are used, while the ones of the events and the device are
+    /// ignored.
+    pub fn new(
+        mut application: A,
+        viewport: &Viewport,
+        settings: &Settings,
+        gpu: GpuContext,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        application.setup_gpu(&gpu.device());
+        let renderer_settings = RendererSettings {
+            format,
+            antialiasing: settings
+                .antialiasing
+                .map(|antialiasing| supported_antialiasing(antialiasing, gpu.adapter_name())),
+            ..settings.into()
+        };
+        let mut renderer = Renderer::new(Backend::new(&mut gpu.device(), renderer_settings));
+        let mut debug = Debug::new();
+        let state = program::State::new(
+            Program::new(application),
+            viewport.logical_size(),
+            &mut renderer,
+            &mut debug,
+        );
+
+        Self {
+            state,
+            renderer,
+            debug,
+            gpu,
+            format,
+            color_space: settings.color_space,
+            renderer_settings,
+            shows_debug_overlay: settings.debug_overlay,
+            last_frame_at: None,
+            appearance_hash: 0,
+        }
+    }
+
+    /// Creates the renderer with a device of its own, requested with the settings without any
+    /// surface, so no Cocoa object is needed.
+    ///
+    /// It draws in the texture format of the settings, which is sRGB by default.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the device couldn't be requested, which [`try_with_settings`] returns
+    /// instead.
+    ///
+    /// [`try_with_settings`]: #method.try_with_settings
+    pub fn with_settings(application: A, viewport: &Viewport, settings: &Settings) -> Self {
+        Self::try_with_settings(application, viewport, settings)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates the renderer like [`with_settings`], or returns why the device couldn't be
+    /// requested, e.g. on a machine without any GPU.
+    ///
+    /// [`with_settings`]: #method.with_settings
+    pub fn try_with_settings(
+        application: A,
+        viewport: &Viewport,
+        settings: &Settings,
+    ) -> Result<Self, Error> {
+        let gpu = GpuContext::try_new(settings)?;
+        let format = settings.texture_format.unwrap_or_default().to_wgpu();
+        Ok(Self::new(application, viewport, settings, gpu, format))
+    }
+
+    /// The application.
+    pub fn application(&self) -> &A {
+        &self.state.program().application
+    }
+
+    /// The context of the device it draws with.
+    pub fn gpu_context(&self) -> &GpuContext {
+        &self.gpu
+    }
+
+    /// Draws with the device of the context from now on, e.g. after the previous one has been
+    /// lost, creating the resources of the renderer again.
+    ///
+    /// The state of the application is kept, while its own resources of the previous device
+    /// have to be created again.
+    pub fn set_gpu_context(&mut self, gpu: GpuContext) {
+        // The resources of the renderer are released before their device.
+        self.renderer = Renderer::new(Backend::new(&mut gpu.device(), self.renderer_settings));
+        self.gpu = gpu;
+        self.last_frame_at = None;
+    }
+
+    /// Whether the timings of the updates and of the rendering are drawn over the interface.
+    pub fn is_debug_overlay_shown(&self) -> bool {
+        self.shows_debug_overlay
+    }
+
+    /// Shows or hides the timings of the updates and of the rendering over the interface.
+    pub fn set_debug_overlay(&mut self, is_shown: bool) {
+        self.shows_debug_overlay = is_shown;
+    }
+
+    /// Queues an event for the next update.
+    pub fn queue_event(&mut self, event: Event) {
+        self.state.queue_event(event);
+    }
+
+    /// Queues a message for the next update.
+    pub fn queue_message(&mut self, message: A::Message) {
+        self.state.queue_message(message);
+    }
+
+    /// Whether no event nor message waits for the next update.
+    pub(crate) fn is_queue_empty(&self) -> bool {
+        self.state.is_queue_empty()
+    }
+
+    /// Handles the queued events and messages, laying the interface out in the viewport, and
+    /// returns whether the interface looks different.
+    ///
+    /// The widgets paste from the clipboard, if any.
+    pub fn update(
+        &mut self,
+        viewport: &Viewport,
+        clipboard: Option<&dyn ClipboardBackend>,
+    ) -> bool {
+        if self.state.is_queue_empty() {
+            return false;
+        }
+
+        let clipboard = clipboard.map(WidgetClipboard);
+        let clipboard: Option<&dyn Clipboard> = match &clipboard {
+            Some(clipboard) => Some(clipboard),
+            None => None,
+        };
+        self.state.update(
+            clipboard,
+            viewport.logical_size(),
+            &mut self.renderer,
+            &mut self.debug,
+        );
+
+        let appearance_hash = self.appearance_hash();
+        let is_changed = appearance_hash != self.appearance_hash;
+        self.appearance_hash = appearance_hash;
+        is_changed
+    }
+
+    /// Hashes the primitives, the interaction and the background, which is cheaper than
+    /// drawing them again.
+    fn appearance_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let (primitive, mouse_interaction) = self.state.primitive();
+        hash_primitive(primitive, &mut hasher);
+        let _ = write!(
+            HashWriter(&mut hasher),
+            "{:?}{:?}",
+            mouse_interaction,
+            self.application().background_color()
+        );
+        hasher.finish()
+    }
+
+    /// Records the drawing of the interface over the content of the target, which is drawn by
+    /// the application first, returning the interaction under the cursor.
+    ///
+    /// The target is loaded rather than cleared, so a host can draw below the interface. Call
+    /// [`clear`] before to fill it with the background color instead.
+    ///
+    /// [`clear`]: #method.clear
+    ///
+    /// The device of the context must not be locked meanwhile.
+    pub fn render_to(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        viewport: &Viewport,
+    ) -> mouse::Interaction {
+        self.debug.render_started();
+
+        let now = Instant::now();
+        let frame_time = self.last_frame_at.map_or_else(
+            || Duration::from_secs(0),
+            |last_frame_at| now - last_frame_at,
+        );
+        self.last_frame_at = Some(now);
+        self.application().custom_draw(&mut CustomDrawContext {
+            device: &self.gpu.device(),
+            encoder: &mut *encoder,
+            target,
+            viewport,
+            frame_time,
+        });
+
+        let overlay = if self.shows_debug_overlay {
+            self.debug.overlay()
+        } else {
+            Vec::new()
+        };
+        let mouse_interaction = self.renderer.backend_mut().draw(
+            &mut self.gpu.device(),
+            encoder,
+            target,
+            viewport,
+            self.state.primitive(),
+            &overlay,
+        );
+        self.debug.render_finished();
+
+        mouse_interaction
+    }
+
+    /// Draws the interface into a texture of the size of the viewport and reads its pixels
+    /// back, waiting for the device.
+    ///
+    /// The device of the context must not be locked meanwhile.
+    pub fn capture(&mut self, viewport: &Viewport) -> Result<RgbaImage, CaptureError> {
+        let size = viewport.physical_size();
+        if size.width == 0 || size.height == 0 {
+            return Err(CaptureError::EmptyViewport);
+        }
+
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth: 1,
+        };
+        let bytes_per_row = capture::padded_bytes_per_row(size.width);
+        let (texture, buffer, mut encoder) = {
+            let device = self.gpu.device();
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: extent,
+                array_layer_count: 1,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT | wgpu::TextureUsage::COPY_SRC,
+            });
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: u64::from(bytes_per_row) * u64::from(size.height),
+                usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            });
+            let encoder =
+                device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            (texture, buffer, encoder)
+        };
+
+        let view = texture.create_default_view();
+        self.clear(&mut encoder, &view);
+        let _ = self.render_to(&mut encoder, &view, viewport);
+        encoder.copy_texture_to_buffer(
+            wgpu::TextureCopyView {
+                texture: &texture,
+                mip_level: 0,
+                array_layer: 0,
+                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+            },
+            wgpu::BufferCopyView {
+                buffer: &buffer,
+                offset: 0,
+                bytes_per_row,
+                rows_per_image: size.height,
+            },
+            extent,
+        );
+        self.gpu.queue().submit(&[encoder.finish()]);
+
+        capture::read_pixels(&self.gpu, &buffer, size, bytes_per_row)
+    }
+
+    /// Records the clearing of the target with the background color of the application,
+    /// converted into the color space of the settings.
+    pub fn clear(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+        let background_color = self
+            .color_space
+            .convert_from_srgb(self.application().background_color());
+        // The sRGB formats encode the written colors, so the color is given in linear space.
+        let [r, g, b, a] = match self.format {
+            wgpu::TextureFormat::Bgra8UnormSrgb => background_color.into_linear(),
+            _ => [
+                background_color.r,
+                background_color.g,
+                background_color.b,
+                background_color.a,
+            ],
+        };
+        // The target is composited with the premultiplied alpha.
+        let alpha = a as f64;
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: target,
+                resolve_target: None,
+                load_op: wgpu::LoadOp::Clear,
+                store_op: wgpu::StoreOp::Store,
+                clear_color: wgpu::Color {
+                    r: r as f64 * alpha,
+                    g: g as f64 * alpha,
+                    b: b as f64 * alpha,
+                    a: alpha,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+    }
+}
+
+/// Hashes the primitive by walking its tree, so the meshes of the canvases are hashed as bits
+/// rather than formatted.
+fn hash_primitive(primitive: &Primitive, hasher: &mut DefaultHasher) {
+    std::mem::discriminant(primitive).hash(hasher);
+    match primitive {
+        Primitive::Group { primitives } => {
+            primitives.len().hash(hasher);
+            primitives
+                .iter()
+                .for_each(|primitive| hash_primitive(primitive, hasher));
+        }
+        Primitive::Clip {
+            bounds,
+            offset,
+            content,
+        } => {
+            let _ = write!(HashWriter(hasher), "{:?}{:?}", bounds, offset);
+            hash_primitive(content, hasher);
+        }
+        Primitive::Translate {
+            translation,
+            content,
+        } => {
+            let _ = write!(HashWriter(hasher), "{:?}", translation);
+            hash_primitive(content, hasher);
+        }
+        Primitive::Cached { cache } => hash_primitive(cache, hasher),
+        Primitive::Mesh2D { buffers, size } => {
+            let _ = write!(HashWriter(hasher), "{:?}", size);
+            buffers.vertices.len().hash(hasher);
+            buffers.vertices.iter().for_each(|vertex| {
+                vertex
+                    .position
+                    .iter()
+                    .chain(vertex.color.iter())
+                    .for_each(|component| hasher.write_u32(component.to_bits()));
+            });
+            buffers.indices.hash(hasher);
+        }
+        // The other primitives are small, so their formatting is cheap.
+        primitive => {
+            let _ = write!(HashWriter(hasher), "{:?}", primitive);
+        }
+    }
+}
+
+/// Feeds the formatted text into a hasher, without allocating it.
+struct HashWriter<'a>(&'a mut DefaultHasher);
+
+impl Write for HashWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
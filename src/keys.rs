@@ -0,0 +1,1031 @@
+//! The pipeline converting the raw key events of AppKit into iced events.
+//!
+//! It doesn't touch the Cocoa objects, so the behavior for any keyboard layout can be reproduced
+//! by feeding it the key codes, the modifier flags and the text produced by the input context.
+
+use std::collections::{HashMap, HashSet};
+
+use cocoa::appkit::NSEventModifierFlags;
+
+use iced_native::{keyboard, Event};
+
+use crate::event::ExtraModifiers;
+use crate::KeymapMode;
+
+/// The state of the keyboard needed to convert the key events.
+#[derive(Debug)]
+pub(crate) struct KeyPipeline {
+    ignore_repeat: bool,
+    keymap_mode: KeymapMode,
+    physical_layout: PhysicalLayout,
+    modifier_flags: NSEventModifierFlags,
+    is_function_pressed: bool,
+    /// The pressed keys with the key codes reported for them, so a key is released with the
+    /// same code even if the layout has been switched while it was held.
+    pressed_keys: HashMap<u16, Option<keyboard::KeyCode>>,
+    /// The pressed keys which have been pressed while Command was held, whose key-ups AppKit
+    /// doesn't deliver.
+    command_keys: HashSet<u16>,
+    marked_text: String,
+    is_composing_key: bool,
+}
+
+impl KeyPipeline {
+    pub(crate) fn new(
+        ignore_repeat: bool,
+        keymap_mode: KeymapMode,
+        physical_layout: PhysicalLayout,
+    ) -> Self {
+        Self {
+            ignore_repeat,
+            keymap_mode,
+            physical_layout,
+            modifier_flags: NSEventModifierFlags::empty(),
+            is_function_pressed: false,
+            pressed_keys: HashMap::new(),
+            command_keys: HashSet::new(),
+            marked_text: String::new(),
+            is_composing_key: false,
+        }
+    }
+
+    pub(crate) fn set_keymap_mode(&mut self, keymap_mode: KeymapMode) {
+        self.keymap_mode = keymap_mode;
+    }
+
+    /// Sets the physical layout of the keyboard producing the next events.
+    pub(crate) fn set_physical_layout(&mut self, physical_layout: PhysicalLayout) {
+        self.physical_layout = physical_layout;
+    }
+
+    /// Returns the key code of a key on a US keyboard.
+    fn physical_key_code(&self, key_code: u16) -> Option<keyboard::KeyCode> {
+        match (self.physical_layout, key_code) {
+            // The ISO keyboards report the key left of 1 as the key next to the left Shift and
+            // vice versa.
+            (PhysicalLayout::Iso, 10) => Some(keyboard::KeyCode::Grave),
+            (PhysicalLayout::Iso, 50) => Some(keyboard::KeyCode::OEM102),
+            (PhysicalLayout::Jis, 93) => Some(keyboard::KeyCode::Yen),
+            (PhysicalLayout::Jis, 94) => Some(keyboard::KeyCode::Underline),
+            (PhysicalLayout::Jis, 95) => Some(keyboard::KeyCode::NumpadComma),
+            (PhysicalLayout::Jis, 104) => Some(keyboard::KeyCode::Kana),
+            // 102, Eisu, switches the input method of the JIS keyboards to the alphanumeric
+            // characters, and has no counterpart.
+            _ => NSKeyCode(key_code).into(),
+        }
+    }
+
+    /// Returns the key code reported for a key, given the characters it produces without the
+    /// modifiers.
+    ///
+    /// The characters are computed by AppKit with the current layout for each event, so
+    /// switching the layout takes effect with the next key press.
+    pub(crate) fn key_code(&self, key_code: u16, characters: &str) -> Option<keyboard::KeyCode> {
+        let physical = self.physical_key_code(key_code);
+
+        match (self.keymap_mode, physical) {
+            (KeymapMode::Logical, Some(physical)) if is_layout_dependent(physical) => {
+                let mut chars = characters.chars().flat_map(char::to_lowercase);
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => logical_key_code(c).or(Some(physical)),
+                    _ => Some(physical),
+                }
+            }
+            _ => physical,
+        }
+    }
+
+    /// The text being composed with an input method.
+    pub(crate) fn marked_text(&self) -> &str {
+        &self.marked_text
+    }
+
+    /// The state of the modifiers not covered by iced, as of the last modifier change.
+    pub(crate) fn extra_modifiers(&self) -> ExtraModifiers {
+        ExtraModifiers {
+            caps_lock: self
+                .modifier_flags
+                .contains(NSEventModifierFlags::NSAlphaShiftKeyMask),
+            function: self.is_function_pressed,
+        }
+    }
+
+    /// Replaces the marked text, returning whether it has changed.
+    pub(crate) fn set_marked_text(&mut self, text: &str) -> bool {
+        if text == self.marked_text {
+            false
+        } else {
+            self.marked_text = text.to_string();
+            true
+        }
+    }
+
+    /// Marks the next key press as consumed by the input method, if it has been composing the
+    /// text before or after the key has been interpreted.
+    pub(crate) fn set_composing_key(&mut self, had_marked_text: bool) {
+        self.is_composing_key = had_marked_text || !self.marked_text.is_empty();
+    }
+
+    /// Converts a key press into the key events.
+    ///
+    /// The characters aren't produced here, they're received from the input context with
+    /// [`text_events`].
+    ///
+    /// [`text_events`]: fn.text_events.html
+    pub(crate) fn key_down(
+        &mut self,
+        key_code: u16,
+        characters: &str,
+        flags: NSEventModifierFlags,
+        is_repeat: bool,
+    ) -> Vec<Event> {
+        // The keys composing the text are delivered through the marked text only.
+        if std::mem::take(&mut self.is_composing_key) {
+            return vec![];
+        }
+
+        let modifiers = keyboard::ModifiersState::from(ModifierFlags(flags));
+        let mut events = vec![];
+
+        let reported = if is_repeat {
+            self.pressed_keys
+                .get(&key_code)
+                .copied()
+                .unwrap_or_else(|| self.key_code(key_code, characters))
+        } else {
+            let reported = self.key_code(key_code, characters);
+
+            // A new press of a key which is still pressed means its key-up was lost.
+            if let Some(lost) = self.pressed_keys.insert(key_code, reported) {
+                events.extend(key_released(lost, modifiers));
+            }
+            if flags.contains(NSEventModifierFlags::NSCommandKeyMask) {
+                self.command_keys.insert(key_code);
+            } else {
+                self.command_keys.remove(&key_code);
+            }
+
+            reported
+        };
+
+        if !(self.ignore_repeat && is_repeat) {
+            events.extend(reported.map(|key_code| {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                })
+            }));
+        }
+
+        events
+    }
+
+    /// Converts a key release into the key events.
+    pub(crate) fn key_up(&mut self, key_code: u16, flags: NSEventModifierFlags) -> Vec<Event> {
+        self.command_keys.remove(&key_code);
+        // The key has been already released if its key-up was synthesized.
+        match self.pressed_keys.remove(&key_code) {
+            Some(reported) => key_released(
+                reported,
+                keyboard::ModifiersState::from(ModifierFlags(flags)),
+            )
+            .into_iter()
+            .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Synthesizes key presses and releases for the modifier keys by diffing the new flags
+    /// against the previously seen ones. Several modifiers can toggle within a single event, so
+    /// each of them is checked separately.
+    pub(crate) fn flags_changed(
+        &mut self,
+        key_code: u16,
+        flags: NSEventModifierFlags,
+    ) -> Vec<Event> {
+        let previous = self.modifier_flags;
+        let modifiers = keyboard::ModifiersState::from(ModifierFlags(flags));
+        let event_key_code = Option::<keyboard::KeyCode>::from(NSKeyCode(key_code));
+
+        let mut events: Vec<Event> = MODIFIER_KEYS
+            .iter()
+            .filter(|(mask, _, _)| previous.contains(*mask) != flags.contains(*mask))
+            .map(|(mask, left, right)| {
+                // The key code of the event tells which side has been toggled, but if several
+                // modifiers changed at once it only describes one of them.
+                let key_code = match event_key_code {
+                    Some(key_code) if key_code == *left || key_code == *right => key_code,
+                    _ => *left,
+                };
+
+                if flags.contains(*mask) {
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key_code,
+                        modifiers,
+                    })
+                } else {
+                    Event::Keyboard(keyboard::Event::KeyReleased {
+                        key_code,
+                        modifiers,
+                    })
+                }
+            })
+            .collect();
+
+        // The arrows and the function keys set the Fn flag too, so only the Fn key itself may
+        // change its state.
+        if key_code == FUNCTION_KEY_CODE {
+            self.is_function_pressed = flags.contains(NSEventModifierFlags::NSFunctionKeyMask);
+        }
+
+        // Caps Lock only reports its state, so each toggle is delivered as a full key stroke.
+        if previous.contains(NSEventModifierFlags::NSAlphaShiftKeyMask)
+            != flags.contains(NSEventModifierFlags::NSAlphaShiftKeyMask)
+        {
+            events.push(Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Capital,
+                modifiers,
+            }));
+            events.push(Event::Keyboard(keyboard::Event::KeyReleased {
+                key_code: keyboard::KeyCode::Capital,
+                modifiers,
+            }));
+        }
+
+        // AppKit doesn't deliver the key-ups of the keys pressed while Command is held, so they
+        // are released along with Command. The keys pressed before keep their own key-ups.
+        if previous.contains(NSEventModifierFlags::NSCommandKeyMask)
+            && !flags.contains(NSEventModifierFlags::NSCommandKeyMask)
+        {
+            let pressed_keys = &mut self.pressed_keys;
+            events.extend(
+                self.command_keys
+                    .drain()
+                    .filter_map(|key_code| pressed_keys.remove(&key_code))
+                    .filter_map(|key_code| key_released(key_code, modifiers)),
+            );
+        }
+
+        self.modifier_flags = flags;
+        events
+    }
+}
+
+pub(crate) fn text_events(text: &str) -> Vec<Event> {
+    text.chars()
+        .filter(|c| is_text_char(*c))
+        .map(|c| Event::Keyboard(keyboard::Event::CharacterReceived(c)))
+        .collect()
+}
+
+pub(crate) fn backspace_events(count: usize) -> Vec<Event> {
+    (0..count)
+        .flat_map(|_| {
+            key_stroke_events(
+                keyboard::KeyCode::Backspace,
+                keyboard::ModifiersState::default(),
+            )
+        })
+        .collect()
+}
+
+/// Synthesizes the press and the release of a key, e.g. to perform the Edit actions of the menu.
+pub(crate) fn key_stroke_events(
+    key_code: keyboard::KeyCode,
+    modifiers: keyboard::ModifiersState,
+) -> Vec<Event> {
+    vec![
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers,
+        }),
+        Event::Keyboard(keyboard::Event::KeyReleased {
+            key_code,
+            modifiers,
+        }),
+    ]
+}
+
+fn key_released(
+    key_code: Option<keyboard::KeyCode>,
+    modifiers: keyboard::ModifiersState,
+) -> Option<Event> {
+    key_code.map(|key_code| {
+        Event::Keyboard(keyboard::Event::KeyReleased {
+            key_code,
+            modifiers,
+        })
+    })
+}
+
+/// Returns whether a key pressed with the modifiers types text.
+///
+/// The keys pressed with Command or Control aren't text input. The characters produced with
+/// Option are legitimate text, though.
+pub(crate) fn is_text_input(flags: NSEventModifierFlags) -> bool {
+    !flags
+        .intersects(NSEventModifierFlags::NSCommandKeyMask | NSEventModifierFlags::NSControlKeyMask)
+}
+
+/// Returns whether the character produced by a key is text.
+///
+/// AppKit reports the function keys (arrows, F-keys, Home, End, etc.) as characters from the
+/// private use area, which aren't text, as well as the control characters.
+fn is_text_char(c: char) -> bool {
+    match c {
+        '\n' | '\t' => true,
+        '\u{F700}'..='\u{F8FF}' => false,
+        c => !c.is_control(),
+    }
+}
+
+/// Returns whether the character printed on a key depends on the layout.
+///
+/// The rest of the keys, like the arrows or the numpad, are at the same place on every layout.
+fn is_layout_dependent(key_code: keyboard::KeyCode) -> bool {
+    use keyboard::KeyCode::*;
+
+    matches!(
+        key_code,
+        Key0 | Key1
+            | Key2
+            | Key3
+            | Key4
+            | Key5
+            | Key6
+            | Key7
+            | Key8
+            | Key9
+            | A
+            | B
+            | C
+            | D
+            | E
+            | F
+            | G
+            | H
+            | I
+            | J
+            | K
+            | L
+            | M
+            | N
+            | O
+            | P
+            | Q
+            | R
+            | S
+            | T
+            | U
+            | V
+            | W
+            | X
+            | Y
+            | Z
+            | Grave
+            | Minus
+            | Equals
+            | LBracket
+            | RBracket
+            | Semicolon
+            | Apostrophe
+            | Comma
+            | Period
+            | Slash
+            | Backslash
+    )
+}
+
+/// Returns the key code of the US layout for a character typed on a key.
+fn logical_key_code(c: char) -> Option<keyboard::KeyCode> {
+    use keyboard::KeyCode::*;
+
+    let key_code = match c {
+        '0' => Key0,
+        '1' => Key1,
+        '2' => Key2,
+        '3' => Key3,
+        '4' => Key4,
+        '5' => Key5,
+        '6' => Key6,
+        '7' => Key7,
+        '8' => Key8,
+        '9' => Key9,
+        'a' => A,
+        'b' => B,
+        'c' => C,
+        'd' => D,
+        'e' => E,
+        'f' => F,
+        'g' => G,
+        'h' => H,
+        'i' => I,
+        'j' => J,
+        'k' => K,
+        'l' => L,
+        'm' => M,
+        'n' => N,
+        'o' => O,
+        'p' => P,
+        'q' => Q,
+        'r' => R,
+        's' => S,
+        't' => T,
+        'u' => U,
+        'v' => V,
+        'w' => W,
+        'x' => X,
+        'y' => Y,
+        'z' => Z,
+        '`' => Grave,
+        '-' => Minus,
+        '=' => Equals,
+        '[' => LBracket,
+        ']' => RBracket,
+        ';' => Semicolon,
+        '\'' => Apostrophe,
+        ',' => Comma,
+        '.' => Period,
+        '/' => Slash,
+        '\\' => Backslash,
+        _ => return None,
+    };
+
+    Some(key_code)
+}
+
+/// The physical layout of a keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PhysicalLayout {
+    Ansi,
+    Iso,
+    Jis,
+}
+
+impl PhysicalLayout {
+    /// Returns the layout of the keyboard used last.
+    pub(crate) fn current() -> Self {
+        const KEYBOARD_ISO: u32 = u32::from_be_bytes(*b"ISO ");
+        const KEYBOARD_JIS: u32 = u32::from_be_bytes(*b"JIS ");
+
+        match unsafe { KBGetLayoutType(LMGetKbdType() as i16) } {
+            KEYBOARD_ISO => PhysicalLayout::Iso,
+            KEYBOARD_JIS => PhysicalLayout::Jis,
+            _ => PhysicalLayout::Ansi,
+        }
+    }
+}
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    fn LMGetKbdType() -> u8;
+    fn KBGetLayoutType(keyboard_type: i16) -> u32;
+}
+
+/// The key codes of AppKit, which are the `kVK_*` constants of Carbon.
+///
+/// They're named after the keys of the US keyboard, whatever layout is used. The keys specific
+/// to the ISO and JIS keyboards are told apart by `PhysicalLayout`.
+struct NSKeyCode(u16);
+
+impl From<NSKeyCode> for Option<keyboard::KeyCode> {
+    fn from(key_code: NSKeyCode) -> Self {
+        match key_code.0 {
+            29 => Some(keyboard::KeyCode::Key0),
+            18 => Some(keyboard::KeyCode::Key1),
+            19 => Some(keyboard::KeyCode::Key2),
+            20 => Some(keyboard::KeyCode::Key3),
+            21 => Some(keyboard::KeyCode::Key4),
+            23 => Some(keyboard::KeyCode::Key5),
+            22 => Some(keyboard::KeyCode::Key6),
+            26 => Some(keyboard::KeyCode::Key7),
+            28 => Some(keyboard::KeyCode::Key8),
+            25 => Some(keyboard::KeyCode::Key9),
+            0 => Some(keyboard::KeyCode::A),
+            11 => Some(keyboard::KeyCode::B),
+            8 => Some(keyboard::KeyCode::C),
+            2 => Some(keyboard::KeyCode::D),
+            14 => Some(keyboard::KeyCode::E),
+            3 => Some(keyboard::KeyCode::F),
+            5 => Some(keyboard::KeyCode::G),
+            4 => Some(keyboard::KeyCode::H),
+            34 => Some(keyboard::KeyCode::I),
+            38 => Some(keyboard::KeyCode::J),
+            40 => Some(keyboard::KeyCode::K),
+            37 => Some(keyboard::KeyCode::L),
+            46 => Some(keyboard::KeyCode::M),
+            45 => Some(keyboard::KeyCode::N),
+            31 => Some(keyboard::KeyCode::O),
+            35 => Some(keyboard::KeyCode::P),
+            12 => Some(keyboard::KeyCode::Q),
+            15 => Some(keyboard::KeyCode::R),
+            1 => Some(keyboard::KeyCode::S),
+            17 => Some(keyboard::KeyCode::T),
+            32 => Some(keyboard::KeyCode::U),
+            9 => Some(keyboard::KeyCode::V),
+            13 => Some(keyboard::KeyCode::W),
+            7 => Some(keyboard::KeyCode::X),
+            16 => Some(keyboard::KeyCode::Y),
+            6 => Some(keyboard::KeyCode::Z),
+            // The key next to the left Shift on ISO keyboards. It's swapped with 50 by the ISO
+            // keyboards, see `PhysicalLayout`.
+            10 => Some(keyboard::KeyCode::OEM102),
+            50 => Some(keyboard::KeyCode::Grave),
+            27 => Some(keyboard::KeyCode::Minus),
+            24 => Some(keyboard::KeyCode::Equals),
+            33 => Some(keyboard::KeyCode::LBracket),
+            30 => Some(keyboard::KeyCode::RBracket),
+            41 => Some(keyboard::KeyCode::Semicolon),
+            39 => Some(keyboard::KeyCode::Apostrophe),
+            43 => Some(keyboard::KeyCode::Comma),
+            47 => Some(keyboard::KeyCode::Period),
+            44 => Some(keyboard::KeyCode::Slash),
+            42 => Some(keyboard::KeyCode::Backslash),
+            82 => Some(keyboard::KeyCode::Numpad0),
+            83 => Some(keyboard::KeyCode::Numpad1),
+            84 => Some(keyboard::KeyCode::Numpad2),
+            85 => Some(keyboard::KeyCode::Numpad3),
+            86 => Some(keyboard::KeyCode::Numpad4),
+            87 => Some(keyboard::KeyCode::Numpad5),
+            88 => Some(keyboard::KeyCode::Numpad6),
+            89 => Some(keyboard::KeyCode::Numpad7),
+            91 => Some(keyboard::KeyCode::Numpad8),
+            92 => Some(keyboard::KeyCode::Numpad9),
+            65 => Some(keyboard::KeyCode::Decimal),
+            67 => Some(keyboard::KeyCode::Multiply),
+            69 => Some(keyboard::KeyCode::Add),
+            75 => Some(keyboard::KeyCode::Divide),
+            78 => Some(keyboard::KeyCode::Subtract),
+            81 => Some(keyboard::KeyCode::NumpadEquals),
+            // Clear is placed where Num Lock is on PC keyboards.
+            71 => Some(keyboard::KeyCode::Numlock),
+            76 => Some(keyboard::KeyCode::NumpadEnter),
+            49 => Some(keyboard::KeyCode::Space),
+            36 => Some(keyboard::KeyCode::Enter),
+            48 => Some(keyboard::KeyCode::Tab),
+            51 => Some(keyboard::KeyCode::Backspace),
+            117 => Some(keyboard::KeyCode::Delete),
+            // 52 has no Carbon constant, it used to be the Enter key of some PowerBooks.
+            52 => Some(keyboard::KeyCode::NumpadEnter),
+            53 => Some(keyboard::KeyCode::Escape),
+            54 => Some(keyboard::KeyCode::RWin),
+            55 => Some(keyboard::KeyCode::LWin),
+            56 => Some(keyboard::KeyCode::LShift),
+            57 => Some(keyboard::KeyCode::Capital),
+            58 => Some(keyboard::KeyCode::LAlt),
+            59 => Some(keyboard::KeyCode::LControl),
+            60 => Some(keyboard::KeyCode::RShift),
+            61 => Some(keyboard::KeyCode::RAlt),
+            62 => Some(keyboard::KeyCode::RControl),
+            // 63, the Fn key, has no counterpart and is reported with the view events.
+            122 => Some(keyboard::KeyCode::F1),
+            120 => Some(keyboard::KeyCode::F2),
+            99 => Some(keyboard::KeyCode::F3),
+            118 => Some(keyboard::KeyCode::F4),
+            96 => Some(keyboard::KeyCode::F5),
+            97 => Some(keyboard::KeyCode::F6),
+            98 => Some(keyboard::KeyCode::F7),
+            100 => Some(keyboard::KeyCode::F8),
+            101 => Some(keyboard::KeyCode::F9),
+            109 => Some(keyboard::KeyCode::F10),
+            103 => Some(keyboard::KeyCode::F11),
+            111 => Some(keyboard::KeyCode::F12),
+            105 => Some(keyboard::KeyCode::F13),
+            107 => Some(keyboard::KeyCode::F14),
+            113 => Some(keyboard::KeyCode::F15),
+            106 => Some(keyboard::KeyCode::F16),
+            64 => Some(keyboard::KeyCode::F17),
+            79 => Some(keyboard::KeyCode::F18),
+            80 => Some(keyboard::KeyCode::F19),
+            90 => Some(keyboard::KeyCode::F20),
+            72 => Some(keyboard::KeyCode::VolumeUp),
+            73 => Some(keyboard::KeyCode::VolumeDown),
+            74 => Some(keyboard::KeyCode::Mute),
+            // Help is placed where Insert is on PC keyboards.
+            114 => Some(keyboard::KeyCode::Insert),
+            115 => Some(keyboard::KeyCode::Home),
+            119 => Some(keyboard::KeyCode::End),
+            116 => Some(keyboard::KeyCode::PageUp),
+            121 => Some(keyboard::KeyCode::PageDown),
+            110 => Some(keyboard::KeyCode::Apps),
+            123 => Some(keyboard::KeyCode::Left),
+            124 => Some(keyboard::KeyCode::Right),
+            125 => Some(keyboard::KeyCode::Down),
+            126 => Some(keyboard::KeyCode::Up),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) struct ModifierFlags(pub(crate) NSEventModifierFlags);
+
+impl From<ModifierFlags> for keyboard::ModifiersState {
+    fn from(flags: ModifierFlags) -> Self {
+        Self {
+            shift: flags.0.contains(NSEventModifierFlags::NSShiftKeyMask),
+            control: flags.0.contains(NSEventModifierFlags::NSControlKeyMask),
+            alt: flags.0.contains(NSEventModifierFlags::NSAlternateKeyMask),
+            logo: flags.0.contains(NSEventModifierFlags::NSCommandKeyMask),
+        }
+    }
+}
+
+/// The key code of the Fn (Globe) key.
+const FUNCTION_KEY_CODE: u16 = 63;
+
+/// Modifier flags with the left and right key codes of the corresponding keys.
+const MODIFIER_KEYS: [(NSEventModifierFlags, keyboard::KeyCode, keyboard::KeyCode); 4] = [
+    (
+        NSEventModifierFlags::NSShiftKeyMask,
+        keyboard::KeyCode::LShift,
+        keyboard::KeyCode::RShift,
+    ),
+    (
+        NSEventModifierFlags::NSControlKeyMask,
+        keyboard::KeyCode::LControl,
+        keyboard::KeyCode::RControl,
+    ),
+    (
+        NSEventModifierFlags::NSAlternateKeyMask,
+        keyboard::KeyCode::LAlt,
+        keyboard::KeyCode::RAlt,
+    ),
+    (
+        NSEventModifierFlags::NSCommandKeyMask,
+        keyboard::KeyCode::LWin,
+        keyboard::KeyCode::RWin,
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: u16 = 0;
+    const C: u16 = 8;
+    const COMMAND: u16 = 55;
+
+    fn pipeline() -> KeyPipeline {
+        KeyPipeline::new(false, KeymapMode::Physical, PhysicalLayout::Ansi)
+    }
+
+    fn released(events: &[Event]) -> Vec<keyboard::KeyCode> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Keyboard(keyboard::Event::KeyReleased { key_code, .. }) => Some(*key_code),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn characters(events: &[Event]) -> String {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Keyboard(keyboard::Event::CharacterReceived(c)) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn function_keys_produce_no_characters() {
+        // NSUpArrowFunctionKey, NSF1FunctionKey, NSHomeFunctionKey and the last one of the
+        // private use area.
+        ['\u{F700}', '\u{F704}', '\u{F729}', '\u{F8FF}']
+            .iter()
+            .for_each(|c| assert!(!is_text_char(*c), "{:?}", c));
+        // Escape and Delete
+        assert!(text_events("\u{1B}\u{7F}").is_empty());
+    }
+
+    #[test]
+    fn text_keeps_the_new_lines_and_the_tabs() {
+        assert_eq!(characters(&text_events("a\tb\n\u{F702}ô")), "a\tb\nô");
+        // The characters right outside the private use area of AppKit are text.
+        assert!(is_text_char('\u{F6FF}'));
+        assert!(is_text_char('\u{F900}'));
+    }
+
+    #[test]
+    fn caps_lock_toggles_are_full_key_strokes() {
+        const CAPS_LOCK: u16 = 57;
+        let caps_lock = NSEventModifierFlags::NSAlphaShiftKeyMask;
+        let capital = |pressed| {
+            let modifiers = keyboard::ModifiersState::default();
+            let key_code = keyboard::KeyCode::Capital;
+            if pressed {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                })
+            } else {
+                Event::Keyboard(keyboard::Event::KeyReleased {
+                    key_code,
+                    modifiers,
+                })
+            }
+        };
+        let mut keys = pipeline();
+
+        assert_eq!(
+            keys.flags_changed(CAPS_LOCK, caps_lock),
+            vec![capital(true), capital(false)]
+        );
+        assert!(keys.extra_modifiers().caps_lock);
+
+        assert_eq!(
+            keys.flags_changed(CAPS_LOCK, NSEventModifierFlags::empty()),
+            vec![capital(true), capital(false)]
+        );
+        assert!(!keys.extra_modifiers().caps_lock);
+    }
+
+    #[test]
+    fn option_and_command_are_alt_and_logo() {
+        let modifiers =
+            keyboard::ModifiersState::from(ModifierFlags(NSEventModifierFlags::NSAlternateKeyMask));
+        assert_eq!(
+            modifiers,
+            keyboard::ModifiersState {
+                alt: true,
+                ..Default::default()
+            }
+        );
+
+        // The device-dependent bits of the flags are ignored.
+        let flags = NSEventModifierFlags::NSCommandKeyMask
+            | NSEventModifierFlags::NSAlternateKeyMask
+            | NSEventModifierFlags::from_bits_truncate(0x08);
+        assert_eq!(
+            keyboard::ModifiersState::from(ModifierFlags(flags)),
+            keyboard::ModifiersState {
+                alt: true,
+                logo: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn caps_lock_is_not_shift() {
+        let modifiers = keyboard::ModifiersState::from(ModifierFlags(
+            NSEventModifierFlags::NSAlphaShiftKeyMask,
+        ));
+        assert_eq!(modifiers, keyboard::ModifiersState::default());
+    }
+
+    fn physical_key_code(layout: PhysicalLayout, key_code: u16) -> Option<keyboard::KeyCode> {
+        KeyPipeline::new(false, KeymapMode::Physical, layout).physical_key_code(key_code)
+    }
+
+    #[test]
+    fn every_carbon_key_code_is_mapped_or_documented() {
+        use keyboard::KeyCode as K;
+
+        // The `kVK_*` constants of Carbon, with the key codes of an ANSI keyboard.
+        let ansi = [
+            (0, K::A),
+            (1, K::S),
+            (2, K::D),
+            (3, K::F),
+            (4, K::H),
+            (5, K::G),
+            (6, K::Z),
+            (7, K::X),
+            (8, K::C),
+            (9, K::V),
+            (10, K::OEM102),
+            (11, K::B),
+            (12, K::Q),
+            (13, K::W),
+            (14, K::E),
+            (15, K::R),
+            (16, K::Y),
+            (17, K::T),
+            (18, K::Key1),
+            (19, K::Key2),
+            (20, K::Key3),
+            (21, K::Key4),
+            (22, K::Key6),
+            (23, K::Key5),
+            (24, K::Equals),
+            (25, K::Key9),
+            (26, K::Key7),
+            (27, K::Minus),
+            (28, K::Key8),
+            (29, K::Key0),
+            (30, K::RBracket),
+            (31, K::O),
+            (32, K::U),
+            (33, K::LBracket),
+            (34, K::I),
+            (35, K::P),
+            (36, K::Enter),
+            (37, K::L),
+            (38, K::J),
+            (39, K::Apostrophe),
+            (40, K::K),
+            (41, K::Semicolon),
+            (42, K::Backslash),
+            (43, K::Comma),
+            (44, K::Slash),
+            (45, K::N),
+            (46, K::M),
+            (47, K::Period),
+            (48, K::Tab),
+            (49, K::Space),
+            (50, K::Grave),
+            (51, K::Backspace),
+            (52, K::NumpadEnter),
+            (53, K::Escape),
+            (54, K::RWin),
+            (55, K::LWin),
+            (56, K::LShift),
+            (57, K::Capital),
+            (58, K::LAlt),
+            (59, K::LControl),
+            (60, K::RShift),
+            (61, K::RAlt),
+            (62, K::RControl),
+            (64, K::F17),
+            (65, K::Decimal),
+            (67, K::Multiply),
+            (69, K::Add),
+            (71, K::Numlock),
+            (72, K::VolumeUp),
+            (73, K::VolumeDown),
+            (74, K::Mute),
+            (75, K::Divide),
+            (76, K::NumpadEnter),
+            (78, K::Subtract),
+            (79, K::F18),
+            (80, K::F19),
+            (81, K::NumpadEquals),
+            (82, K::Numpad0),
+            (83, K::Numpad1),
+            (84, K::Numpad2),
+            (85, K::Numpad3),
+            (86, K::Numpad4),
+            (87, K::Numpad5),
+            (88, K::Numpad6),
+            (89, K::Numpad7),
+            (90, K::F20),
+            (91, K::Numpad8),
+            (92, K::Numpad9),
+            (96, K::F5),
+            (97, K::F6),
+            (98, K::F7),
+            (99, K::F3),
+            (100, K::F8),
+            (101, K::F9),
+            (103, K::F11),
+            (105, K::F13),
+            (106, K::F16),
+            (107, K::F14),
+            (109, K::F10),
+            (110, K::Apps),
+            (111, K::F12),
+            (113, K::F15),
+            (114, K::Insert),
+            (115, K::Home),
+            (116, K::PageUp),
+            (117, K::Delete),
+            (118, K::F4),
+            (119, K::End),
+            (120, K::F2),
+            (121, K::PageDown),
+            (122, K::F1),
+            (123, K::Left),
+            (124, K::Right),
+            (125, K::Down),
+            (126, K::Up),
+        ];
+        ansi.iter().for_each(|(key_code, expected)| {
+            assert_eq!(
+                physical_key_code(PhysicalLayout::Ansi, *key_code),
+                Some(*expected),
+                "{}",
+                key_code
+            )
+        });
+
+        // The section sign and the grave accent are swapped on ISO keyboards.
+        assert_eq!(physical_key_code(PhysicalLayout::Iso, 10), Some(K::Grave));
+        assert_eq!(physical_key_code(PhysicalLayout::Iso, 50), Some(K::OEM102));
+
+        // The keys of the JIS keyboards only exist there.
+        let jis = [
+            (93, K::Yen),
+            (94, K::Underline),
+            (95, K::NumpadComma),
+            (104, K::Kana),
+        ];
+        jis.iter().for_each(|(key_code, expected)| {
+            assert_eq!(
+                physical_key_code(PhysicalLayout::Jis, *key_code),
+                Some(*expected)
+            );
+            assert_eq!(physical_key_code(PhysicalLayout::Ansi, *key_code), None);
+        });
+
+        // Fn is reported with the view events, and Eisu has no counterpart.
+        [63, 102].iter().for_each(|key_code| {
+            [
+                PhysicalLayout::Ansi,
+                PhysicalLayout::Iso,
+                PhysicalLayout::Jis,
+            ]
+            .iter()
+            .for_each(|layout| assert_eq!(physical_key_code(*layout, *key_code), None))
+        });
+    }
+
+    #[test]
+    fn only_the_fn_key_presses_and_releases_it() {
+        const FUNCTION: u16 = 63;
+        const UP_ARROW: u16 = 126;
+        let mut keys = pipeline();
+
+        // The arrows set the Fn flag too.
+        keys.flags_changed(UP_ARROW, NSEventModifierFlags::NSFunctionKeyMask);
+        assert!(!keys.extra_modifiers().function);
+
+        assert!(keys
+            .flags_changed(FUNCTION, NSEventModifierFlags::NSFunctionKeyMask)
+            .is_empty());
+        assert!(keys.extra_modifiers().function);
+        assert!(keys
+            .flags_changed(FUNCTION, NSEventModifierFlags::empty())
+            .is_empty());
+        assert!(!keys.extra_modifiers().function);
+    }
+
+    #[test]
+    fn command_and_control_keys_are_not_text_input() {
+        assert!(!is_text_input(NSEventModifierFlags::NSCommandKeyMask));
+        assert!(!is_text_input(NSEventModifierFlags::NSControlKeyMask));
+        assert!(!is_text_input(
+            NSEventModifierFlags::NSCommandKeyMask | NSEventModifierFlags::NSShiftKeyMask
+        ));
+        // Option+E starts the composition of an acute accent.
+        assert!(is_text_input(NSEventModifierFlags::NSAlternateKeyMask));
+        assert!(is_text_input(NSEventModifierFlags::NSShiftKeyMask));
+        assert!(is_text_input(NSEventModifierFlags::empty()));
+    }
+
+    #[test]
+    fn dead_keys_compose_without_a_bogus_character() {
+        // On the French layout, the key of [ on a US keyboard is the circumflex dead key.
+        const CIRCUMFLEX: u16 = 33;
+        const O: u16 = 31;
+        let mut keys = pipeline();
+
+        // The input method marks the accent, then replaces it with the composed character.
+        assert!(keys.set_marked_text("^"));
+        keys.set_composing_key(false);
+        assert!(keys
+            .key_down(CIRCUMFLEX, "^", NSEventModifierFlags::empty(), false)
+            .is_empty());
+        assert!(keys
+            .key_up(CIRCUMFLEX, NSEventModifierFlags::empty())
+            .is_empty());
+
+        assert!(keys.set_marked_text(""));
+        keys.set_composing_key(true);
+        assert!(keys
+            .key_down(O, "o", NSEventModifierFlags::empty(), false)
+            .is_empty());
+        assert_eq!(characters(&text_events("ô")), "ô");
+
+        // The next key is delivered again.
+        keys.set_composing_key(false);
+        assert_eq!(
+            keys.key_down(O, "o", NSEventModifierFlags::empty(), false),
+            vec![Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::O,
+                modifiers: keyboard::ModifiersState::default(),
+            })]
+        );
+    }
+
+    #[test]
+    fn command_release_releases_the_keys_pressed_with_it() {
+        let mut keys = pipeline();
+        let command = NSEventModifierFlags::NSCommandKeyMask;
+        keys.key_down(A, "a", NSEventModifierFlags::empty(), false);
+        keys.flags_changed(COMMAND, command);
+        keys.key_down(C, "c", command, false);
+
+        let events = keys.flags_changed(COMMAND, NSEventModifierFlags::empty());
+        assert_eq!(
+            released(&events),
+            vec![keyboard::KeyCode::LWin, keyboard::KeyCode::C]
+        );
+
+        // The key pressed before Command is released with its own key-up.
+        let events = keys.key_up(A, NSEventModifierFlags::empty());
+        assert_eq!(released(&events), vec![keyboard::KeyCode::A]);
+    }
+}
@@ -22,27 +22,62 @@
     unreachable_pub
 )]
 
+pub mod capture;
+pub mod clipboard;
+mod dispatch;
+mod display_link;
+pub mod drag;
+pub mod event;
+pub mod gpu;
+pub mod headless;
+mod keys;
+pub mod menu;
+pub mod shortcuts;
+#[cfg(feature = "touch-bar")]
+pub mod touch_bar;
 pub mod widget;
 
+use std::any::Any;
+use std::collections::VecDeque;
 use std::ffi::{c_void, CStr};
 use std::marker::PhantomData;
 use std::os::raw::c_char;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bitflags::bitflags;
+
+use block::ConcreteBlock;
 
 use cocoa::appkit::{
-    NSEvent, NSEventModifierFlags, NSEventType, NSPasteboard, NSURLPboardType, NSView,
+    NSEvent, NSEventMask, NSEventModifierFlags, NSEventPhase, NSEventType, NSPasteboardTypePNG,
+    NSPasteboardTypeString, NSPasteboardTypeTIFF, NSURLPboardType, NSView,
+};
+use cocoa::base::{id, nil, BOOL, NO};
+use cocoa::foundation::{
+    NSArray, NSInteger, NSNotFound, NSPoint, NSRange, NSRect, NSSize, NSString, NSTimeInterval,
+    NSUInteger,
 };
-use cocoa::base::{id, nil, BOOL};
-use cocoa::foundation::{NSArray, NSPoint, NSRect, NSSize, NSString, NSUInteger};
 
 use core_graphics::base::CGFloat;
+use core_graphics::display::CGDisplay;
+use core_graphics::event::CGEvent;
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
 use core_graphics::geometry::{CGPoint, CGRect};
 
-use iced_wgpu::{settings, wgpu, Backend, Renderer, Settings as RendererSettings};
+use iced_wgpu::{Renderer, Settings as RendererSettings};
+
+pub use iced_wgpu::wgpu;
+pub use iced_wgpu::wgpu::{BackendBit, Extensions, Limits, PowerPreference};
+pub use iced_wgpu::{settings::Antialiasing, Viewport};
 
-pub use iced_wgpu::Viewport;
+use keys::{
+    backspace_events, is_text_input, key_stroke_events, text_events, KeyPipeline, ModifierFlags,
+    PhysicalLayout,
+};
 
-use iced_native::{program, window, Clipboard, Debug, Element as NativeElement, Event};
+use iced_native::{program, window, Element as NativeElement, Event};
 
 pub use iced_native::{
     futures, keyboard, mouse, Align, Background, Color, Command, Font, HorizontalAlignment, Length,
@@ -50,8 +85,8 @@ pub use iced_native::{
 };
 
 use objc::declare::ClassDecl;
-use objc::runtime::{Class, Sel, YES};
-use objc::{class, msg_send, sel, sel_impl};
+use objc::runtime::{Class, Protocol, Sel, YES};
+use objc::{class, msg_send, sel, sel_impl, Encode, Encoding};
 
 pub use objc::runtime::Object;
 
@@ -61,6 +96,19 @@ pub use widget::*;
 /// A composition of widgets.
 pub type Element<'a, M> = NativeElement<'a, M, Renderer>;
 
+/// The key code of Tab.
+const TAB_KEY_CODE: u16 = 48;
+
+/// The number of the events kept by `IcedView::recent_events`.
+const RECENT_EVENTS_CAPACITY: usize = 64;
+
+/// The shortest time between two recreations of the swap chain during a live resize.
+const LIVE_RESIZE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The number of redraws in a row without a frame before the error is reported and the view
+/// stops asking for another redraw.
+const MAX_FAILED_FRAMES: u32 = 3;
+
 /// Iced view which is a subclass of `NSView`.
 pub struct IcedView<A: 'static + Application> {
     object: *mut Object,
@@ -69,27 +117,84 @@ pub struct IcedView<A: 'static + Application> {
 
 impl<A: 'static + Application> IcedView<A> {
     const EVENT_HANDLER_IVAR: &'static str = "_event_handler";
-    const DID_EXIT_DRAG: &'static str = "_did_exit_drag";
+    const MEDIA_KEY_MONITOR_IVAR: &'static str = "_media_key_monitor";
+    const TRACKING_AREA_IVAR: &'static str = "_tracking_area";
 
     /// Constructor.
+    ///
+    /// The physical size of the viewport is in pixels, so it's the size of the view in points
+    /// multiplied by the scale factor.
+    ///
+    /// The view shares the device with the other views of the thread if the settings allow it.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the view can't be created, which [`try_new`] reports instead.
+    ///
+    /// [`try_new`]: #method.try_new
     pub fn new(application: A, viewport: Viewport, settings: Settings) -> Self {
-        let object = unsafe { Self::init_nsview(viewport.physical_size()) };
-        let event_handler = EventHandler::new(application, object, viewport, settings);
+        Self::try_new(application, viewport, settings).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Constructor returning the error if the view can't be created, e.g. so that a plugin shows
+    /// a view of its own instead of taking the host down.
+    pub fn try_new(application: A, viewport: Viewport, settings: Settings) -> Result<Self, Error> {
+        let context = if settings.share_gpu {
+            gpu::GpuContext::shared(&settings)?
+        } else {
+            gpu::GpuContext::try_new(&settings)?
+        };
+        Self::try_new_with_context(application, viewport, settings, context)
+    }
+
+    /// Constructor drawing with the device of the context, e.g. the one of the host wrapped with
+    /// `GpuContext::from_device`.
+    ///
+    /// The settings of the device are ignored, as the context already has one.
+    ///
+    /// # Panics
+    ///
+    /// It panics if the view can't be created, which [`try_new_with_context`] reports instead.
+    ///
+    /// [`try_new_with_context`]: #method.try_new_with_context
+    pub fn new_with_context(
+        application: A,
+        viewport: Viewport,
+        settings: Settings,
+        context: gpu::GpuContext,
+    ) -> Self {
+        Self::try_new_with_context(application, viewport, settings, context)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Constructor drawing with the device of the context, returning the error if the view
+    /// can't be created.
+    pub fn try_new_with_context(
+        application: A,
+        viewport: Viewport,
+        settings: Settings,
+        context: gpu::GpuContext,
+    ) -> Result<Self, Error> {
+        let object =
+            unsafe { Self::init_nsview(viewport.logical_size(), &settings.accepted_drop_types)? };
+        let event_handler = EventHandler::new(application, object, viewport, settings, context);
         unsafe {
             (*object).set_ivar(
                 Self::EVENT_HANDLER_IVAR,
                 Box::into_raw(Box::new(event_handler)) as *mut c_void,
             );
+            let monitor = Self::add_media_key_monitor(object);
+            (*object).set_ivar(Self::MEDIA_KEY_MONITOR_IVAR, monitor);
         };
 
-        Self {
+        Ok(Self {
             object,
             _phantom_app: PhantomData,
-        }
+        })
     }
 
-    unsafe fn init_nsview(size: Size<u32>) -> *mut Object {
-        let class = Self::declare_class();
+    unsafe fn init_nsview(size: Size<f32>, drop_types: &[DropType]) -> Result<*mut Object, Error> {
+        let class = Self::declare_class()?;
         let rect = NSRect::new(
             NSPoint::new(0.0, 0.0),
             NSSize::new(size.width.into(), size.height.into()),
@@ -98,29 +203,164 @@ impl<A: 'static + Application> IcedView<A> {
         let object: *mut Object = msg_send![allocation, initWithFrame: rect];
         // NSViewLayerContentsRedrawDuringViewResize
         let () = msg_send![object, setLayerContentsRedrawPolicy: 2];
-        let types = NSArray::arrayWithObject(nil, NSURLPboardType);
+        Self::register_drop_types(object, drop_types);
+
+        Ok(object)
+    }
+
+    // Some hosts misbehave when the view registers for the types it doesn't use, so nothing is
+    // registered without any type.
+    unsafe fn register_drop_types(object: *mut Object, drop_types: &[DropType]) {
+        let () = msg_send![object, unregisterDraggedTypes];
+        if drop_types.is_empty() {
+            return;
+        }
+
+        let mut types = Vec::new();
+        let mut custom_types = Vec::new();
+        drop_types.iter().for_each(|drop_type| match drop_type {
+            DropType::Files => {
+                types.push(NSURLPboardType);
+                let promise_types: id =
+                    msg_send![class!(NSFilePromiseReceiver), readableDraggedTypes];
+                types.extend((0..promise_types.count()).map(|n| promise_types.objectAtIndex(n)));
+            }
+            DropType::Text => types.push(NSPasteboardTypeString),
+            DropType::Image => {
+                types.push(NSPasteboardTypeTIFF);
+                types.push(NSPasteboardTypePNG);
+            }
+            DropType::Custom(uti) => custom_types.push(NSString::alloc(nil).init_str(uti)),
+        });
+        types.extend(&custom_types);
+        let types = NSArray::arrayWithObjects(nil, &types);
         let () = msg_send![object, registerForDraggedTypes: types];
+        custom_types.into_iter().for_each(|uti| {
+            let () = msg_send![uti, release];
+        });
+    }
+
+    // The media keys produce system-defined events, which are sent to the application rather
+    // than to the views, so they're picked up by a monitor. The events are passed on, so the
+    // host keeps receiving them.
+    unsafe fn add_media_key_monitor(object: *mut Object) -> id {
+        let handler = ConcreteBlock::new(move |event: id| -> id {
+            unsafe {
+                let window: id = msg_send![object, window];
+                let is_key_window: BOOL = msg_send![window, isKeyWindow];
+                if window != nil && is_key_window == YES {
+                    Self::handle_event(&mut *object, sel!(mediaKey:), event);
+                }
+            }
+
+            event
+        })
+        .copy();
 
-        object
+        msg_send![
+            class!(NSEvent),
+            addLocalMonitorForEventsMatchingMask: NSEventMask::NSSystemDefinedMask.bits()
+            handler: &*handler
+        ]
+    }
+
+    /// The name of the class of the views of the application, which is registered once per type
+    /// of application and per binary, e.g. per plugin loaded by the host.
+    fn class_name() -> String {
+        let type_name: String = std::any::type_name::<A>()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        // The address of the function tells the binaries bundling the crate apart.
+        format!("IcedView_{}_{:x}", type_name, Self::declare_class as usize)
     }
 
-    unsafe fn declare_class() -> &'static Class {
+    unsafe fn declare_class() -> Result<&'static Class, Error> {
+        let name = Self::class_name();
+        if let Some(class) = Class::get(&name) {
+            return Ok(class);
+        }
+
         let superclass = class!(NSView);
-        let mut decl =
-            ClassDecl::new("IcedView", superclass).expect("Can't declare IcedView class.");
+        let mut decl = ClassDecl::new(&name, superclass).ok_or(Error::ClassRegistrationFailed)?;
         decl.add_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
-        decl.add_ivar::<bool>(Self::DID_EXIT_DRAG);
+        decl.add_ivar::<id>(Self::MEDIA_KEY_MONITOR_IVAR);
+        decl.add_ivar::<id>(Self::TRACKING_AREA_IVAR);
 
         let accepts_first_responder: extern "C" fn(&Object, Sel) -> BOOL =
             Self::accepts_first_responder;
         decl.add_method(sel!(acceptsFirstResponder), accepts_first_responder);
+        let become_first_responder: extern "C" fn(&mut Object, Sel) -> BOOL =
+            Self::become_first_responder;
+        decl.add_method(sel!(becomeFirstResponder), become_first_responder);
+        let resign_first_responder: extern "C" fn(&mut Object, Sel) -> BOOL =
+            Self::resign_first_responder;
+        decl.add_method(sel!(resignFirstResponder), resign_first_responder);
+        let view_will_move_to_window: extern "C" fn(&mut Object, Sel, id) =
+            Self::view_will_move_to_window;
+        decl.add_method(sel!(viewWillMoveToWindow:), view_will_move_to_window);
+        let view_did_move_to_window: extern "C" fn(&mut Object, Sel) =
+            Self::view_did_move_to_window;
+        decl.add_method(sel!(viewDidMoveToWindow), view_did_move_to_window);
+
+        let view_did_change_backing_properties: extern "C" fn(&mut Object, Sel) =
+            Self::view_did_change_backing_properties;
+        decl.add_method(
+            sel!(viewDidChangeBackingProperties),
+            view_did_change_backing_properties,
+        );
+        let window_did_change_backing_properties: extern "C" fn(&mut Object, Sel, id) =
+            Self::window_did_change_backing_properties;
+        decl.add_method(
+            sel!(windowDidChangeBackingProperties:),
+            window_did_change_backing_properties,
+        );
+
+        let view_did_change_visibility: extern "C" fn(&mut Object, Sel) =
+            Self::view_did_change_visibility;
+        decl.add_method(sel!(viewDidHide), view_did_change_visibility);
+        decl.add_method(sel!(viewDidUnhide), view_did_change_visibility);
+
+        let display_link_fired: extern "C" fn(&mut Object, Sel, id) = Self::display_link_fired;
+        decl.add_method(sel!(displayLinkFired:), display_link_fired);
+        let window_did_change_key: extern "C" fn(&mut Object, Sel, id) =
+            Self::window_did_change_key;
+        decl.add_method(sel!(windowDidChangeKey:), window_did_change_key);
+        let window_did_change_occlusion_state: extern "C" fn(&mut Object, Sel, id) =
+            Self::window_did_change_occlusion_state;
+        decl.add_method(
+            sel!(windowDidChangeOcclusionState:),
+            window_did_change_occlusion_state,
+        );
+
+        let accepts_first_mouse: extern "C" fn(&mut Object, Sel, *mut Object) -> BOOL =
+            Self::accepts_first_mouse;
+        decl.add_method(sel!(acceptsFirstMouse:), accepts_first_mouse);
 
         let is_flipped: extern "C" fn(&Object, Sel) -> BOOL = Self::is_flipped;
         decl.add_method(sel!(isFlipped), is_flipped);
 
-        let update_tracking_areas: extern "C" fn(&Object, Sel) = Self::update_tracking_areas;
+        let is_opaque: extern "C" fn(&Object, Sel) -> BOOL = Self::is_opaque;
+        decl.add_method(sel!(isOpaque), is_opaque);
+
+        let mouse_down_can_move_window: extern "C" fn(&Object, Sel) -> BOOL =
+            Self::mouse_down_can_move_window;
+        decl.add_method(sel!(mouseDownCanMoveWindow), mouse_down_can_move_window);
+
+        let hit_test: extern "C" fn(&mut Object, Sel, NSPoint) -> id = Self::hit_test;
+        decl.add_method(sel!(hitTest:), hit_test);
+
+        #[cfg(feature = "touch-bar")]
+        Self::declare_touch_bar(&mut decl);
+
+        let update_tracking_areas: extern "C" fn(&mut Object, Sel) = Self::update_tracking_areas;
         decl.add_method(sel!(updateTrackingAreas), update_tracking_areas);
 
+        let cursor_update: extern "C" fn(&mut Object, Sel, *mut Object) = Self::cursor_update;
+        decl.add_method(sel!(cursorUpdate:), cursor_update);
+        let reset_cursor_rects: extern "C" fn(&mut Object, Sel) = Self::reset_cursor_rects;
+        decl.add_method(sel!(resetCursorRects), reset_cursor_rects);
+
         let update_layer: extern "C" fn(&mut Object, Sel) = Self::update_layer;
         decl.add_method(sel!(updateLayer), update_layer);
 
@@ -131,774 +371,6011 @@ impl<A: 'static + Application> IcedView<A> {
         let dragging_entered: extern "C" fn(&mut Object, Sel, *mut Object) -> NSUInteger =
             Self::dragging_entered;
         decl.add_method(sel!(draggingEntered:), dragging_entered);
+        let dragging_updated: extern "C" fn(&mut Object, Sel, *mut Object) -> NSUInteger =
+            Self::dragging_updated;
+        decl.add_method(sel!(draggingUpdated:), dragging_updated);
+        let prepare_for_drag_operation: extern "C" fn(&mut Object, Sel, *mut Object) -> BOOL =
+            Self::prepare_for_drag_operation;
+        decl.add_method(sel!(prepareForDragOperation:), prepare_for_drag_operation);
+        let perform_drag_operation: extern "C" fn(&mut Object, Sel, *mut Object) -> BOOL =
+            Self::perform_drag_operation;
+        decl.add_method(sel!(performDragOperation:), perform_drag_operation);
+        let conclude_drag_operation: extern "C" fn(&mut Object, Sel, *mut Object) =
+            Self::conclude_drag_operation;
+        decl.add_method(sel!(concludeDragOperation:), conclude_drag_operation);
         let dragging_ended: extern "C" fn(&mut Object, Sel, *mut Object) = Self::dragging_ended;
         decl.add_method(sel!(draggingEnded:), dragging_ended);
         let dragging_exited: extern "C" fn(&mut Object, Sel, *mut Object) = Self::dragging_exited;
         decl.add_method(sel!(draggingExited:), dragging_exited);
 
+        let source_operation_mask: extern "C" fn(&Object, Sel, id, NSInteger) -> NSUInteger =
+            Self::source_operation_mask;
+        decl.add_method(
+            sel!(draggingSession:sourceOperationMaskForDraggingContext:),
+            source_operation_mask,
+        );
+        let dragging_session_ended: extern "C" fn(&mut Object, Sel, id, NSPoint, NSUInteger) =
+            Self::dragging_session_ended;
+        decl.add_method(
+            sel!(draggingSession:endedAtPoint:operation:),
+            dragging_session_ended,
+        );
+
         let handle_event: extern "C" fn(&mut Object, Sel, *mut Object) = Self::handle_event;
-        decl.add_method(sel!(mouseDown:), handle_event);
+        let mouse_down: extern "C" fn(&mut Object, Sel, *mut Object) = Self::mouse_down;
+        decl.add_method(sel!(mouseDown:), mouse_down);
         decl.add_method(sel!(mouseUp:), handle_event);
         decl.add_method(sel!(mouseDragged:), handle_event);
+        decl.add_method(sel!(rightMouseDragged:), handle_event);
+        decl.add_method(sel!(otherMouseDragged:), handle_event);
         decl.add_method(sel!(mouseMoved:), handle_event);
         decl.add_method(sel!(mouseEntered:), handle_event);
         decl.add_method(sel!(mouseExited:), handle_event);
-        decl.add_method(sel!(rightMouseDown:), handle_event);
+        let right_mouse_down: extern "C" fn(&mut Object, Sel, *mut Object) = Self::right_mouse_down;
+        decl.add_method(sel!(rightMouseDown:), right_mouse_down);
         decl.add_method(sel!(rightMouseUp:), handle_event);
+        decl.add_method(sel!(otherMouseDown:), handle_event);
+        decl.add_method(sel!(otherMouseUp:), handle_event);
         decl.add_method(sel!(scrollWheel:), handle_event);
-        decl.add_method(sel!(keyDown:), handle_event);
-        decl.add_method(sel!(keyUp:), handle_event);
+        decl.add_method(sel!(magnifyWithEvent:), handle_event);
+        decl.add_method(sel!(rotateWithEvent:), handle_event);
+        decl.add_method(sel!(swipeWithEvent:), handle_event);
+        decl.add_method(sel!(pressureChangeWithEvent:), handle_event);
+        decl.add_method(sel!(tabletPoint:), handle_event);
+        decl.add_method(sel!(tabletProximity:), handle_event);
         decl.add_method(sel!(flagsChanged:), handle_event);
 
-        decl.register()
+        // Force clicks are reported by the pressure stages, so the system Quick Look is disabled.
+        let quick_look: extern "C" fn(&Object, Sel, *mut Object) = Self::quick_look;
+        decl.add_method(sel!(quickLookWithEvent:), quick_look);
+
+        let smart_magnify: extern "C" fn(&mut Object, Sel, *mut Object) = Self::smart_magnify;
+        decl.add_method(sel!(smartMagnifyWithEvent:), smart_magnify);
+
+        let key_event: extern "C" fn(&mut Object, Sel, *mut Object) = Self::key_event;
+        decl.add_method(sel!(keyDown:), key_event);
+        decl.add_method(sel!(keyUp:), key_event);
+
+        Self::declare_text_input_client(&mut decl);
+
+        let perform_key_equivalent: extern "C" fn(&mut Object, Sel, *mut Object) -> BOOL =
+            Self::perform_key_equivalent;
+        decl.add_method(sel!(performKeyEquivalent:), perform_key_equivalent);
+
+        // The Edit menu of the host sends these actions to the first responder.
+        let edit_action: extern "C" fn(&mut Object, Sel, id) = Self::edit_action;
+        decl.add_method(sel!(copy:), edit_action);
+        decl.add_method(sel!(cut:), edit_action);
+        decl.add_method(sel!(paste:), edit_action);
+        decl.add_method(sel!(selectAll:), edit_action);
+        let validate_menu_item: extern "C" fn(&mut Object, Sel, id) -> BOOL =
+            Self::validate_menu_item;
+        decl.add_method(sel!(validateMenuItem:), validate_menu_item);
+
+        Ok(decl.register())
     }
 
-    extern "C" fn accepts_first_responder(_this: &Object, _cmd: Sel) -> BOOL {
-        return YES;
+    unsafe fn declare_text_input_client(decl: &mut ClassDecl) {
+        if let Some(protocol) = Protocol::get("NSTextInputClient") {
+            decl.add_protocol(protocol);
+        }
+
+        let insert_text: extern "C" fn(&mut Object, Sel, id, TextRange) = Self::insert_text;
+        decl.add_method(sel!(insertText:replacementRange:), insert_text);
+
+        let set_marked_text: extern "C" fn(&mut Object, Sel, id, TextRange, TextRange) =
+            Self::set_marked_text;
+        decl.add_method(
+            sel!(setMarkedText:selectedRange:replacementRange:),
+            set_marked_text,
+        );
+
+        let unmark_text: extern "C" fn(&mut Object, Sel) = Self::unmark_text;
+        decl.add_method(sel!(unmarkText), unmark_text);
+
+        let has_marked_text: extern "C" fn(&mut Object, Sel) -> BOOL = Self::has_marked_text;
+        decl.add_method(sel!(hasMarkedText), has_marked_text);
+
+        let marked_range: extern "C" fn(&mut Object, Sel) -> TextRange = Self::marked_range;
+        decl.add_method(sel!(markedRange), marked_range);
+
+        let selected_range: extern "C" fn(&Object, Sel) -> TextRange = Self::selected_range;
+        decl.add_method(sel!(selectedRange), selected_range);
+
+        let valid_attributes_for_marked_text: extern "C" fn(&Object, Sel) -> id =
+            Self::valid_attributes_for_marked_text;
+        decl.add_method(
+            sel!(validAttributesForMarkedText),
+            valid_attributes_for_marked_text,
+        );
+
+        let attributed_substring_for_proposed_range: extern "C" fn(
+            &Object,
+            Sel,
+            TextRange,
+            *mut c_void,
+        ) -> id = Self::attributed_substring_for_proposed_range;
+        decl.add_method(
+            sel!(attributedSubstringForProposedRange:actualRange:),
+            attributed_substring_for_proposed_range,
+        );
+
+        let character_index_for_point: extern "C" fn(&Object, Sel, NSPoint) -> NSUInteger =
+            Self::character_index_for_point;
+        decl.add_method(sel!(characterIndexForPoint:), character_index_for_point);
+
+        let first_rect_for_character_range: extern "C" fn(
+            &mut Object,
+            Sel,
+            TextRange,
+            *mut c_void,
+        ) -> NSRect = Self::first_rect_for_character_range;
+        decl.add_method(
+            sel!(firstRectForCharacterRange:actualRange:),
+            first_rect_for_character_range,
+        );
+
+        let do_command_by_selector: extern "C" fn(&Object, Sel, Sel) = Self::do_command_by_selector;
+        decl.add_method(sel!(doCommandBySelector:), do_command_by_selector);
     }
 
-    extern "C" fn is_flipped(_this: &Object, _cmd: Sel) -> BOOL {
-        return YES;
+    // Without the keyboard events, the view doesn't take the focus, so the keys and the
+    // shortcuts go to the host.
+    extern "C" fn accepts_first_responder(this: &Object, _cmd: Sel) -> BOOL {
+        unsafe {
+            let value = this.get_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if (*event_handler).event_mask.contains(EventMask::KEYBOARD) {
+                YES
+            } else {
+                NO
+            }
+        }
     }
 
-    extern "C" fn update_tracking_areas(this: &Object, _cmd: Sel) {
-        // NSTrackingMouseEnteredAndExited | NSTrackingMouseMoved | NSTrackingCursorUpdate |
-        // NSTrackingActiveInKeyWindow
-        let options = 0x01 | 0x02 | 0x04 | 0x20;
-        let class = class!(NSTrackingArea);
+    extern "C" fn become_first_responder(this: &mut Object, _cmd: Sel) -> BOOL {
         unsafe {
-            let bounds: NSRect = msg_send![this, bounds];
-            let alloc: *mut Object = msg_send![class, alloc];
-            let tracking_area: *mut Object =
-                msg_send![alloc, initWithRect:bounds options:options owner:this userInfo:nil];
-            let () = msg_send![this, addTrackingArea: tracking_area];
+            let superclass = class!(NSView);
+            let accepted: BOOL = msg_send![super(this, superclass), becomeFirstResponder];
+            if accepted == YES {
+                Self::update_focus(this, true);
+            }
+            accepted
         }
     }
 
-    extern "C" fn update_layer(this: &mut Object, cmd: Sel) {
+    extern "C" fn resign_first_responder(this: &mut Object, _cmd: Sel) -> BOOL {
         unsafe {
-            let in_resize: BOOL = msg_send![this, inLiveResize];
-            if in_resize != 0 {
-                Self::resize(this, cmd);
+            let superclass = class!(NSView);
+            let accepted: BOOL = msg_send![super(this, superclass), resignFirstResponder];
+            if accepted == YES {
+                Self::update_focus(this, false);
             }
+            accepted
+        }
+    }
 
+    // The swap chain is dropped while the view isn't in a window, e.g. when the editor of a
+    // plugin is closed by the host without dropping the view.
+    extern "C" fn view_will_move_to_window(this: &mut Object, _cmd: Sel, new_window: id) {
+        unsafe {
             let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            if value.is_null() || new_window != nil {
+                return;
+            }
+
             let event_handler = *value as *mut EventHandler<A>;
-            (*event_handler).redraw();
+            (*event_handler).suspend();
         }
     }
 
-    extern "C" fn resize(this: &mut Object, _cmd: Sel) {
+    // The view keeps being the first responder when its window resigns key, so the window is
+    // observed too, as well as for its occlusion and its backing.
+    extern "C" fn view_did_move_to_window(this: &mut Object, _cmd: Sel) {
         unsafe {
-            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
-            let event_handler = *value as *mut EventHandler<A>;
+            Self::update_backing(this);
+
             let this_ptr: *mut Object = this;
-            let bounds = NSView::bounds(this_ptr);
-            let parent_window: *mut Object = msg_send![this, window];
-            let scale_factor: CGFloat = msg_send![parent_window, backingScaleFactor];
-            (*event_handler).resize(
-                Size::new(bounds.size.width as u32, bounds.size.height as u32),
-                scale_factor,
-            );
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let () = msg_send![center, removeObserver: this_ptr];
+
+            let window: id = msg_send![this, window];
+            if window != nil {
+                [
+                    (
+                        "NSWindowDidBecomeKeyNotification",
+                        sel!(windowDidChangeKey:),
+                    ),
+                    (
+                        "NSWindowDidResignKeyNotification",
+                        sel!(windowDidChangeKey:),
+                    ),
+                    (
+                        "NSWindowDidChangeOcclusionStateNotification",
+                        sel!(windowDidChangeOcclusionState:),
+                    ),
+                    (
+                        "NSWindowDidChangeBackingPropertiesNotification",
+                        sel!(windowDidChangeBackingProperties:),
+                    ),
+                ]
+                .iter()
+                .for_each(|(name, selector)| {
+                    let name = NSString::alloc(nil).init_str(name);
+                    let () = msg_send![
+                        center,
+                        addObserver: this_ptr
+                        selector: *selector
+                        name: name
+                        object: window
+                    ];
+                    let () = msg_send![name, release];
+                });
+            }
+
+            Self::update_focus(this, Self::is_first_responder(this));
+            Self::update_occlusion(this);
         }
     }
 
-    extern "C" fn dragging_entered(
+    // The window may be on a display with another scale factor than the previous one, and the
+    // swap chain is created again if the view has been out of any window.
+    unsafe fn update_backing(this: &mut Object) {
+        let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        if value.is_null() {
+            return;
+        }
+
+        let window: id = msg_send![this, window];
+        if window == nil {
+            return;
+        }
+
+        let event_handler = *value as *mut EventHandler<A>;
+        let (size, scale_factor) = Self::backing_size(this);
+        if !(*event_handler).resume(size, scale_factor) {
+            (*event_handler).resize(size, scale_factor, false);
+        }
+        let () = msg_send![this, setNeedsDisplay: YES];
+    }
+
+    extern "C" fn view_did_change_backing_properties(this: &mut Object, _cmd: Sel) {
+        unsafe {
+            Self::update_backing(this);
+        }
+    }
+
+    // Some hosts move the view between the windows without the callbacks of the view, so the
+    // window is observed too.
+    extern "C" fn window_did_change_backing_properties(
         this: &mut Object,
         _cmd: Sel,
-        sender: *mut Object,
-    ) -> NSUInteger {
+        _notification: id,
+    ) {
         unsafe {
-            this.set_ivar::<bool>(Self::DID_EXIT_DRAG, false);
+            Self::update_backing(this);
+        }
+    }
 
-            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
-            let event_handler = *value as *mut EventHandler<A>;
+    extern "C" fn window_did_change_occlusion_state(
+        this: &mut Object,
+        _cmd: Sel,
+        _notification: id,
+    ) {
+        unsafe {
+            Self::update_occlusion(this);
+        }
+    }
 
-            Self::paths_from_dragged_info(sender)
-                .into_iter()
-                .for_each(|pathbuf| {
-                    (*event_handler).on_window_event(window::Event::FileHovered(pathbuf));
-                });
+    // Nothing is drawn while the window is fully covered or minimized.
+    unsafe fn update_occlusion(this: &mut Object) {
+        let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        if value.is_null() {
+            return;
+        }
+
+        let event_handler = *value as *mut EventHandler<A>;
+        let window: id = msg_send![this, window];
+        let is_occluded = window != nil && {
+            let occlusion_state: NSUInteger = msg_send![window, occlusionState];
+            // NSWindowOcclusionStateVisible
+            occlusion_state & (1 << 1) == 0
+        };
+        if (*event_handler).set_occluded(is_occluded) {
+            let () = msg_send![this, setNeedsDisplay: YES];
         }
-        // NSDragOperationEvery
-        NSUInteger::MAX
+        Self::update_display_link(this);
     }
 
-    extern "C" fn dragging_ended(this: &mut Object, _cmd: Sel, sender: *mut Object) {
+    // The display link is paused while the view is hidden.
+    extern "C" fn view_did_change_visibility(this: &mut Object, _cmd: Sel) {
         unsafe {
-            if *this.get_ivar::<bool>(Self::DID_EXIT_DRAG) {
-                return;
-            }
-            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
-            let event_handler = *value as *mut EventHandler<A>;
-
-            Self::paths_from_dragged_info(sender)
-                .into_iter()
-                .for_each(|pathbuf| {
-                    (*event_handler).on_window_event(window::Event::FileDropped(pathbuf));
-                });
+            Self::update_display_link(this);
         }
     }
 
-    unsafe fn paths_from_dragged_info(info: *mut Object) -> Vec<PathBuf> {
-        let pasteboard: id = msg_send![info, draggingPasteboard];
-        let class = class!(NSURL);
-        let class_ref: *mut Object = msg_send![class, self];
-        let classes = NSArray::arrayWithObject(nil, class_ref);
-        let items: id = msg_send![pasteboard, readObjectsForClasses: classes options: nil];
-        (0..items.count())
-            .into_iter()
-            .map(|n| pathbuf_from_nsurl(items.objectAtIndex(n)))
-            .collect()
+    unsafe fn update_display_link(this: &mut Object) {
+        let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        if value.is_null() {
+            return;
+        }
+
+        let event_handler = *value as *mut EventHandler<A>;
+        let this_ptr: *mut Object = this;
+        (*event_handler).update_display_link(this_ptr);
     }
 
-    extern "C" fn dragging_exited(this: &mut Object, _cmd: Sel, _sender: *mut Object) {
+    extern "C" fn display_link_fired(this: &mut Object, _cmd: Sel, timestamp: id) {
         unsafe {
-            this.set_ivar::<bool>(Self::DID_EXIT_DRAG, true);
             let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
-            let event_handler = *value as *mut EventHandler<A>;
+            // The view may be dropped while the tick is on the way.
+            if value.is_null() {
+                return;
+            }
 
-            (*event_handler).on_window_event(window::Event::FilesHoveredLeft);
+            let event_handler = *value as *mut EventHandler<A>;
+            let seconds: f64 = msg_send![timestamp, doubleValue];
+            if (*event_handler).on_animation_frame(Duration::from_secs_f64(seconds.max(0.0))) {
+                let () = msg_send![this, setNeedsDisplay: YES];
+            }
         }
     }
 
-    extern "C" fn handle_event(this: &mut Object, _cmd: Sel, event: *mut Object) {
+    extern "C" fn window_did_change_key(this: &mut Object, _cmd: Sel, _notification: id) {
         unsafe {
+            Self::update_focus(this, Self::is_first_responder(this));
+
+            // The user has likely copied something in another window.
             let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
             let event_handler = *value as *mut EventHandler<A>;
-            (*event_handler).queue_event(
-                NSEventT {
-                    raw_event: event,
-                    view: this,
-                }
-                .into(),
-            );
-            let () = msg_send![this, setNeedsDisplay: YES];
-        };
+            if (*event_handler).check_clipboard() {
+                let () = msg_send![this, setNeedsDisplay: YES];
+            }
+        }
     }
 
-    /// Get a raw pointer to the Cocoa view.
-    pub fn raw_object(&self) -> *mut Object {
-        self.object
+    unsafe fn is_first_responder(this: &mut Object) -> bool {
+        let this_ptr: *mut Object = this;
+        let window: id = msg_send![this, window];
+        let first_responder: id = msg_send![window, firstResponder];
+        window != nil && first_responder == this_ptr
     }
 
-    /// Make this view a subview of another view.
-    pub unsafe fn make_subview_of(&self, view: *mut c_void) {
-        NSView::addSubview_(view as id, self.object);
+    unsafe fn update_focus(this: &mut Object, is_first_responder: bool) {
+        let window: id = msg_send![this, window];
+        let is_key_window: BOOL = msg_send![window, isKeyWindow];
+        let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        let event_handler = *value as *mut EventHandler<A>;
+        (*event_handler).set_focused(is_first_responder && window != nil && is_key_window == YES);
+        let () = msg_send![this, setNeedsDisplay: YES];
     }
-}
 
-impl<A: 'static + Application> Drop for IcedView<A> {
-    fn drop(&mut self) {
+    extern "C" fn accepts_first_mouse(this: &mut Object, _cmd: Sel, _event: *mut Object) -> BOOL {
         unsafe {
-            let value = self
-                .object
-                .as_mut()
-                .unwrap()
-                .get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
-            let _ = Box::from_raw(*value as *mut EventHandler<A>);
-            let () = msg_send![self.object, release];
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if (*event_handler).accepts_first_mouse {
+                YES
+            } else {
+                NO
+            }
         }
     }
-}
 
-/// Implement this trait for your application then pass it into `IcedView::new`.
-pub trait Application {
-    /// The message your application will produce.
-    type Message: Clone + std::fmt::Debug + Send;
-
-    /// Message processing function.
-    fn update(&mut self, message: Self::Message) -> Command<Self::Message>;
+    extern "C" fn is_flipped(_this: &Object, _cmd: Sel) -> BOOL {
+        return YES;
+    }
 
-    /// Application interface.
-    fn view(&mut self) -> Element<'_, Self::Message>;
+    // The content behind the view shows through while the background of the application is
+    // translucent.
+    extern "C" fn is_opaque(this: &Object, _cmd: Sel) -> BOOL {
+        unsafe {
+            let value = this.get_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *const EventHandler<A>;
+            if !event_handler.is_null() && (*event_handler).is_opaque {
+                YES
+            } else {
+                NO
+            }
+        }
+    }
 
-    /// Returns the background color of the [`Application`].
-    ///
-    /// By default, it returns `Color::WHITE`.
-    fn background_color(&self) -> Color {
-        Color::WHITE
+    // The window is moved explicitly in `mouse_down` for the draggable regions only.
+    extern "C" fn mouse_down_can_move_window(_this: &Object, _cmd: Sel) -> BOOL {
+        return NO;
     }
-}
 
-/// The settings of the view.
-#[derive(Debug)]
-pub struct Settings {
-    /// The bytes of the font that will be used by default.
-    ///
-    /// If `None` is provided, a default system font will be chosen.
-    pub default_font: Option<&'static [u8]>,
-    /// The default size of text.
-    ///
-    /// By default, it will be set to 20.
-    pub default_text_size: u16,
-    /// If set to true, the renderer will try to perform antialiasing for some primitives.
-    ///
-    /// Enabling it can produce a smoother result in some widgets, like the `Canvas`, at a
-    /// performance cost.
-    ///
-    /// By default, it is disabled.
-    pub antialiasing: bool,
-}
+    #[cfg(feature = "touch-bar")]
+    unsafe fn declare_touch_bar(decl: &mut ClassDecl) {
+        let make_touch_bar: extern "C" fn(&mut Object, Sel) -> id = Self::make_touch_bar;
+        decl.add_method(sel!(makeTouchBar), make_touch_bar);
+        let make_touch_bar_item: extern "C" fn(&mut Object, Sel, id, id) -> id =
+            Self::make_touch_bar_item;
+        decl.add_method(sel!(touchBar:makeItemForIdentifier:), make_touch_bar_item);
+        let touch_bar_button_pressed: extern "C" fn(&mut Object, Sel, id) =
+            Self::touch_bar_button_pressed;
+        decl.add_method(sel!(touchBarButtonPressed:), touch_bar_button_pressed);
+        let touch_bar_slider_changed: extern "C" fn(&mut Object, Sel, id) =
+            Self::touch_bar_slider_changed;
+        decl.add_method(sel!(touchBarSliderChanged:), touch_bar_slider_changed);
+    }
 
-impl Default for Settings {
-    fn default() -> Self {
-        Self {
-            default_font: Some(include_bytes!("../fonts/OpenSans-Regular.ttf")),
-            default_text_size: 20,
-            antialiasing: false,
+    #[cfg(feature = "touch-bar")]
+    extern "C" fn make_touch_bar(this: &mut Object, _cmd: Sel) -> id {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            (*event_handler).touch_bar = (*event_handler).iced.application().touch_bar();
+            match &(*event_handler).touch_bar {
+                Some(spec) => touch_bar::make_touch_bar(spec, this),
+                None => nil,
+            }
         }
     }
-}
 
-impl From<Settings> for RendererSettings {
-    fn from(settings: Settings) -> RendererSettings {
-        Self {
-            default_font: settings.default_font,
-            default_text_size: settings.default_text_size,
-            antialiasing: if settings.antialiasing {
-                Some(settings::Antialiasing::MSAAx4)
-            } else {
-                None
-            },
-            ..Default::default()
+    #[cfg(feature = "touch-bar")]
+    extern "C" fn make_touch_bar_item(
+        this: &mut Object,
+        _cmd: Sel,
+        _touch_bar: id,
+        identifier: id,
+    ) -> id {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let item = (*event_handler)
+                .touch_bar
+                .as_ref()
+                .and_then(|spec| spec.item(&string_from_text_input(identifier)));
+            match item {
+                Some(item) => touch_bar::make_item(
+                    item,
+                    identifier,
+                    this,
+                    sel!(touchBarButtonPressed:),
+                    sel!(touchBarSliderChanged:),
+                ),
+                None => nil,
+            }
         }
     }
+
+    #[cfg(feature = "touch-bar")]
+    extern "C" fn touch_bar_button_pressed(this: &mut Object, _cmd: Sel, sender: id) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let identifier: id = msg_send![sender, identifier];
+            let message = (*event_handler)
+                .touch_bar
+                .as_ref()
+                .and_then(|spec| spec.item(&string_from_text_input(identifier)))
+                .and_then(|item| match item {
+                    touch_bar::TouchBarItem::Button { on_press, .. } => Some(on_press.clone()),
+                    _ => None,
+                });
+
+            if let Some(message) = message {
+                (*event_handler).iced.queue_message(message);
+                let () = msg_send![this, setNeedsDisplay: YES];
+            }
+        }
+    }
+
+    #[cfg(feature = "touch-bar")]
+    extern "C" fn touch_bar_slider_changed(this: &mut Object, _cmd: Sel, sender: id) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let identifier: id = msg_send![sender, identifier];
+            let message = (*event_handler)
+                .touch_bar
+                .as_ref()
+                .and_then(|spec| spec.item(&string_from_text_input(identifier)))
+                .and_then(|item| match item {
+                    touch_bar::TouchBarItem::Slider { on_change, .. } => {
+                        Some(on_change(touch_bar::slider_value(sender)))
+                    }
+                    _ => None,
+                });
+
+            if let Some(message) = message {
+                (*event_handler).iced.queue_message(message);
+                let () = msg_send![this, setNeedsDisplay: YES];
+            }
+        }
+    }
+
+    // The bar is recreated when the layout described by the application changes, otherwise only
+    // the values of the sliders are updated.
+    #[cfg(feature = "touch-bar")]
+    unsafe fn update_touch_bar(this: &mut Object) {
+        let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        let event_handler = *value as *mut EventHandler<A>;
+        let spec = (*event_handler).iced.application().touch_bar();
+
+        match (&(*event_handler).touch_bar, spec) {
+            (None, None) => {}
+            (Some(current), Some(spec)) if current.has_same_layout(&spec) => {
+                let touch_bar: id = msg_send![this, touchBar];
+                touch_bar::update_values(&spec, touch_bar);
+                (*event_handler).touch_bar = Some(spec);
+            }
+            _ => {
+                (*event_handler).touch_bar = None;
+                // AppKit calls `makeTouchBar` again when the bar is needed.
+                let () = msg_send![this, setTouchBar: nil];
+            }
+        }
+    }
+
+    // The regions rejected by the application pass the clicks to the views underneath.
+    extern "C" fn hit_test(this: &mut Object, _cmd: Sel, point: NSPoint) -> id {
+        unsafe {
+            let superclass = class!(NSView);
+            let view: id = msg_send![super(this, superclass), hitTest: point];
+            let this_ptr: *mut Object = this;
+            if view != this_ptr {
+                return view;
+            }
+
+            // The point is in the coordinates of the superview.
+            let superview: id = msg_send![this, superview];
+            let location = NSView::convertPoint_fromView_(this_ptr, point, superview);
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if (*event_handler)
+                .iced
+                .application()
+                .hit_test(Point::new(location.x as f32, location.y as f32))
+            {
+                view
+            } else {
+                nil
+            }
+        }
+    }
+
+    extern "C" fn update_tracking_areas(this: &mut Object, _cmd: Sel) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            // NSTrackingMouseEnteredAndExited | NSTrackingMouseMoved | NSTrackingCursorUpdate |
+            // NSTrackingEnabledDuringMouseDrag
+            let options: NSUInteger =
+                0x01 | 0x02 | 0x04 | 0x400 | (*event_handler).mouse_tracking.options();
+
+            let superclass = class!(NSView);
+            let () = msg_send![super(this, superclass), updateTrackingAreas];
+
+            let previous = *this.get_ivar::<id>(Self::TRACKING_AREA_IVAR);
+            let tracking_area = replace_tracking_area(this, previous, options);
+            this.set_ivar::<id>(Self::TRACKING_AREA_IVAR, tracking_area);
+
+            debug_assert_eq!(owned_tracking_areas_count(this), 1);
+        }
+    }
+
+    unsafe fn remove_tracking_area(this: &mut Object) {
+        let tracking_area = *this.get_ivar::<id>(Self::TRACKING_AREA_IVAR);
+        if tracking_area != nil {
+            let () = msg_send![this, removeTrackingArea: tracking_area];
+            let () = msg_send![tracking_area, release];
+            this.set_ivar::<id>(Self::TRACKING_AREA_IVAR, nil);
+        }
+    }
+
+    extern "C" fn update_layer(this: &mut Object, cmd: Sel) {
+        unsafe {
+            let in_resize: BOOL = msg_send![this, inLiveResize];
+            if in_resize != 0 {
+                Self::resize(this, cmd);
+            }
+
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if (*event_handler).redraw() {
+                let () = msg_send![this, setNeedsDisplay: YES];
+            }
+
+            #[cfg(feature = "touch-bar")]
+            Self::update_touch_bar(this);
+
+            // The cursor rects keep the cursor right when the interaction changes under the
+            // motionless cursor.
+            if (*event_handler).cursor.take_changed() {
+                let this_ptr: *mut Object = this;
+                let window: id = msg_send![this, window];
+                let () = msg_send![window, invalidateCursorRectsForView: this_ptr];
+            }
+        }
+    }
+
+    // AppKit asks for the cursor when it enters the tracking area.
+    extern "C" fn cursor_update(this: &mut Object, _cmd: Sel, _event: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            (*event_handler).apply_current_cursor();
+        }
+    }
+
+    extern "C" fn reset_cursor_rects(this: &mut Object, _cmd: Sel) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if let Some(interaction) = (*event_handler).cursor.current() {
+                let cursor = (*event_handler).ns_cursor(interaction);
+                let this_ptr: *mut Object = this;
+                let bounds = NSView::bounds(this_ptr);
+                let () = msg_send![this, addCursorRect: bounds cursor: cursor];
+            }
+        }
+    }
+
+    extern "C" fn resize(this: &mut Object, _cmd: Sel) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let (size, scale_factor) = Self::backing_size(this);
+            let in_live_resize: BOOL = msg_send![this, inLiveResize];
+            (*event_handler).resize(size, scale_factor, in_live_resize == YES);
+        }
+    }
+
+    /// The size of the view in pixels and the scale factor of its window.
+    unsafe fn backing_size(this: &mut Object) -> (Size<u32>, CGFloat) {
+        let this_ptr: *mut Object = this;
+        let bounds = NSView::bounds(this_ptr);
+        let parent_window: *mut Object = msg_send![this, window];
+        let scale_factor: CGFloat = msg_send![parent_window, backingScaleFactor];
+        (physical_size(bounds.size, scale_factor), scale_factor)
+    }
+
+    extern "C" fn dragging_entered(
+        this: &mut Object,
+        _cmd: Sel,
+        sender: *mut Object,
+    ) -> NSUInteger {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if !(*event_handler)
+                .event_mask
+                .contains(EventMask::DRAG_AND_DROP)
+            {
+                // NSDragOperationNone
+                return 0;
+            }
+
+            let position = Self::dragging_location(this, sender);
+            let paths = Self::paths_from_dragged_info(sender, &(*event_handler).drop_types);
+            let operation = (*event_handler).on_file_drag_entered(position, paths);
+            let () = msg_send![this, setNeedsDisplay: YES];
+            operation.to_ns_drag_operation()
+        }
+    }
+
+    // The tracking area doesn't report the moves during the drag, so the cursor is moved here
+    // to let the widgets highlight the drop target.
+    extern "C" fn dragging_updated(
+        this: &mut Object,
+        _cmd: Sel,
+        sender: *mut Object,
+    ) -> NSUInteger {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if !(*event_handler)
+                .event_mask
+                .contains(EventMask::DRAG_AND_DROP)
+            {
+                // NSDragOperationNone
+                return 0;
+            }
+
+            let operation =
+                (*event_handler).on_file_drag_updated(Self::dragging_location(this, sender));
+            let () = msg_send![this, setNeedsDisplay: YES];
+            operation.to_ns_drag_operation()
+        }
+    }
+
+    extern "C" fn prepare_for_drag_operation(
+        this: &mut Object,
+        _cmd: Sel,
+        _sender: *mut Object,
+    ) -> BOOL {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if (*event_handler)
+                .event_mask
+                .contains(EventMask::DRAG_AND_DROP)
+                && (*event_handler).file_drag.is_accepted
+            {
+                YES
+            } else {
+                NO
+            }
+        }
+    }
+
+    // The pasteboard is read here rather than when the drag ends, since some sources only
+    // provide their data once the drop is performed.
+    extern "C" fn perform_drag_operation(
+        this: &mut Object,
+        _cmd: Sel,
+        sender: *mut Object,
+    ) -> BOOL {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let position = Self::dragging_location(this, sender);
+            // Nothing is dropped if the drag has ended outside while the view still shows it.
+            if !(*event_handler)
+                .file_drag
+                .can_drop(Self::is_inside(this, position))
+            {
+                return NO;
+            }
+
+            let drop_types = (*event_handler).drop_types.clone();
+            let paths = Self::paths_from_dragged_info(sender, &drop_types);
+            let pasteboard: id = msg_send![sender, draggingPasteboard];
+            // The files often come with their names as text and with their icons as images.
+            let mut drops = Vec::new();
+            if paths.is_empty() && drop_types.contains(&DropType::Text) {
+                drops.extend(
+                    Self::text_from_dragged_info(sender)
+                        .map(|text| event::DragDropEvent::TextDropped { text, position }),
+                );
+            }
+            if paths.is_empty() && drop_types.contains(&DropType::Image) {
+                let image = clipboard::undecoded_image_from_pasteboard(pasteboard);
+                if image != nil {
+                    Self::decode_dropped_image(this, image, position);
+                }
+            }
+            drop_types.iter().for_each(|drop_type| {
+                if let DropType::Custom(uti) = drop_type {
+                    drops.extend(
+                        clipboard::data_from_pasteboard(pasteboard, uti).map(|bytes| {
+                            event::DragDropEvent::DataDropped {
+                                uti: uti.clone(),
+                                bytes,
+                                position,
+                            }
+                        }),
+                    );
+                }
+            });
+            let receives_promises = paths.is_empty() && drop_types.contains(&DropType::Files);
+            (*event_handler).on_file_drop(position, paths, drops);
+            if receives_promises {
+                Self::receive_file_promises(this, sender, position);
+            }
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+        YES
+    }
+
+    extern "C" fn conclude_drag_operation(this: &mut Object, _cmd: Sel, _sender: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            (*event_handler).clear_drag_modifiers();
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    // The drop has been performed by now, so only the hover state is left to clean up.
+    extern "C" fn dragging_ended(this: &mut Object, _cmd: Sel, sender: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if !(*event_handler)
+                .event_mask
+                .contains(EventMask::DRAG_AND_DROP)
+            {
+                return;
+            }
+
+            let position = Self::dragging_location(this, sender);
+            let is_inside = Self::is_inside(this, position);
+            (*event_handler).on_file_drag_ended(position, is_inside);
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    // Mail and Photos promise their files instead of giving their URLs. The files are written
+    // to a temporary directory, and each one is dropped on the main queue once it's there. The
+    // directory is removed once the readers and the update of the drop are done with it.
+    unsafe fn receive_file_promises(this: &mut Object, info: *mut Object, position: Point) {
+        let pasteboard: id = msg_send![info, draggingPasteboard];
+        let class = class!(NSFilePromiseReceiver);
+        let class_ref: *mut Object = msg_send![class, self];
+        let classes = NSArray::arrayWithObject(nil, class_ref);
+        let receivers: id = msg_send![pasteboard, readObjectsForClasses: classes options: nil];
+        if receivers == nil || receivers.count() == 0 {
+            return;
+        }
+
+        let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        let event_handler = *value as *mut EventHandler<A>;
+        let directory = match promised_files_directory() {
+            Ok(directory) => Arc::new(PromisedFilesDirectory(directory)),
+            Err(error) => {
+                (*event_handler).on_view_event(event::Event::DragDrop(
+                    event::DragDropEvent::FilePromiseFailed {
+                        description: error.to_string(),
+                    },
+                ));
+                return;
+            }
+        };
+
+        let path = NSString::alloc(nil).init_str(&directory.0.to_string_lossy());
+        let destination: id = msg_send![class!(NSURL), fileURLWithPath: path isDirectory: YES];
+        let () = msg_send![path, release];
+        let options: id = msg_send![class!(NSDictionary), dictionary];
+        // The reader waits for the file to be written, which would block the host.
+        let queue: id = msg_send![class!(NSOperationQueue), new];
+        let queue = RetainedObject::from_retained(queue);
+        (0..receivers.count()).for_each(|n| {
+            // The reader may be called after the view is dropped, so it keeps the object alive
+            // and finds out whether the event handler is still there.
+            let object = RetainedObject::new(this);
+            // Each reader keeps the queue alive until it's been called.
+            let queue = RetainedObject::new(queue.0);
+            let directory = Arc::clone(&directory);
+            let reader = ConcreteBlock::new(move |url: id, error: id| unsafe {
+                let _ = &queue;
+                let file = if error != nil {
+                    let description: id = msg_send![error, localizedDescription];
+                    Err(string_from_text_input(description))
+                } else {
+                    Ok(pathbuf_from_nsurl(url))
+                };
+                let object = RetainedObject::new(object.0);
+                let directory = Arc::clone(&directory);
+                dispatch::run_on_main(move || {
+                    Self::on_promised_file(object.0, file, position, directory);
+                });
+            })
+            .copy();
+            let () = msg_send![
+                receivers.objectAtIndex(n),
+                receivePromisedFilesAtDestination: destination
+                options: options
+                operationQueue: queue
+                reader: &*reader
+            ];
+        });
+    }
+
+    unsafe fn on_promised_file(
+        object: *mut Object,
+        file: Result<PathBuf, String>,
+        position: Point,
+        directory: Arc<PromisedFilesDirectory>,
+    ) {
+        let value = (*object).get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        if value.is_null() {
+            return;
+        }
+
+        let event_handler = *value as *mut EventHandler<A>;
+        match file {
+            Ok(path) => {
+                (*event_handler).on_file_dropped(path, position);
+                (*event_handler).promised_files_directories.push(directory);
+            }
+            Err(description) => (*event_handler).on_view_event(event::Event::DragDrop(
+                event::DragDropEvent::FilePromiseFailed { description },
+            )),
+        }
+        let () = msg_send![object, setNeedsDisplay: YES];
+    }
+
+    // The large images take a while to be drawn into pixels, so they're decoded on a
+    // background queue and delivered once they're ready.
+    unsafe fn decode_dropped_image(this: &mut Object, image: id, position: Point) {
+        let image = RetainedObject::from_retained(image);
+        let object = RetainedObject::new(this);
+        dispatch::run_in_background(
+            move || unsafe { clipboard::rgba_from_image(image.0) },
+            move |pixels| unsafe {
+                let value = (*object.0).get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+                if value.is_null() {
+                    return;
+                }
+
+                let event_handler = *value as *mut EventHandler<A>;
+                if let Some((rgba, size)) = pixels {
+                    (*event_handler).on_view_event(event::Event::DragDrop(
+                        event::DragDropEvent::ImageDropped {
+                            rgba,
+                            size,
+                            position,
+                        },
+                    ));
+                    let () = msg_send![object.0, setNeedsDisplay: YES];
+                }
+            },
+        );
+    }
+
+    // The destination chooses among these what a drop from the view does.
+    extern "C" fn source_operation_mask(
+        this: &Object,
+        _cmd: Sel,
+        _session: id,
+        _context: NSInteger,
+    ) -> NSUInteger {
+        unsafe {
+            let value = this.get_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *const EventHandler<A>;
+            (*event_handler).drag_operation_mask
+        }
+    }
+
+    extern "C" fn dragging_session_ended(
+        this: &mut Object,
+        _cmd: Sel,
+        _session: id,
+        screen_point: NSPoint,
+        operation: NSUInteger,
+    ) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            (*event_handler).on_drag_source_ended(drag::DragOutcome {
+                operation: event::DragOperation::from_ns_drag_operation(operation),
+                screen_position: Point::new(screen_point.x as f32, screen_point.y as f32),
+            });
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    unsafe fn is_inside(this: &mut Object, position: Point) -> bool {
+        let this_ptr: *mut Object = this;
+        let bounds = NSView::bounds(this_ptr);
+        position.x >= 0.0
+            && position.y >= 0.0
+            && f64::from(position.x) < bounds.size.width
+            && f64::from(position.y) < bounds.size.height
+    }
+
+    unsafe fn dragging_location(this: &mut Object, info: *mut Object) -> Point {
+        let location_in_window: NSPoint = msg_send![info, draggingLocation];
+        let location = NSView::convertPoint_fromView_(this, location_in_window, nil);
+        Point::new(location.x as f32, location.y as f32)
+    }
+
+    unsafe fn paths_from_dragged_info(info: *mut Object, drop_types: &[DropType]) -> Vec<PathBuf> {
+        if !drop_types.contains(&DropType::Files) {
+            return Vec::new();
+        }
+
+        let pasteboard: id = msg_send![info, draggingPasteboard];
+        let class = class!(NSURL);
+        let class_ref: *mut Object = msg_send![class, self];
+        let classes = NSArray::arrayWithObject(nil, class_ref);
+        let items: id = msg_send![pasteboard, readObjectsForClasses: classes options: nil];
+        (0..items.count())
+            .into_iter()
+            .map(|n| pathbuf_from_nsurl(items.objectAtIndex(n)))
+            .collect()
+    }
+
+    unsafe fn text_from_dragged_info(info: *mut Object) -> Option<String> {
+        let pasteboard: id = msg_send![info, draggingPasteboard];
+        let class = class!(NSString);
+        let class_ref: *mut Object = msg_send![class, self];
+        let classes = NSArray::arrayWithObject(nil, class_ref);
+        let items: id = msg_send![pasteboard, readObjectsForClasses: classes options: nil];
+        if items == nil || items.count() == 0 {
+            None
+        } else {
+            Some(string_from_text_input(items.objectAtIndex(0)))
+        }
+    }
+
+    extern "C" fn dragging_exited(this: &mut Object, _cmd: Sel, _sender: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if !(*event_handler)
+                .event_mask
+                .contains(EventMask::DRAG_AND_DROP)
+            {
+                return;
+            }
+
+            (*event_handler).on_file_drag_exited();
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    extern "C" fn right_mouse_down(this: &mut Object, cmd: Sel, event: *mut Object) {
+        unsafe {
+            if Self::pop_up_context_menu(this, event) {
+                return;
+            }
+        }
+
+        Self::handle_event(this, cmd, event);
+    }
+
+    /// Shows the context menu of the application, if it has one at the location of the event.
+    unsafe fn pop_up_context_menu(this: &mut Object, event: *mut Object) -> bool {
+        let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+        let event_handler = *value as *mut EventHandler<A>;
+        let position = NSEventT {
+            raw_event: event,
+            view: this,
+        }
+        .location();
+
+        let menu = match (*event_handler).iced.application().context_menu(position) {
+            Some(menu) => menu,
+            None => return false,
+        };
+
+        let this_ptr: *mut Object = this;
+        if let Some(message) = menu::pop_up(&menu, event, this_ptr) {
+            (*event_handler).iced.queue_message(message);
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+        true
+    }
+
+    extern "C" fn mouse_down(this: &mut Object, cmd: Sel, event: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            (*event_handler).set_last_mouse_down(event);
+            if (*event_handler).is_right_click_emulated(event)
+                && Self::pop_up_context_menu(this, event)
+            {
+                return;
+            }
+
+            let position = NSEventT {
+                raw_event: event,
+                view: this,
+            }
+            .location();
+
+            if (*event_handler)
+                .iced
+                .application()
+                .is_draggable_region(position)
+            {
+                let window: id = msg_send![this, window];
+                let () = msg_send![window, performWindowDragWithEvent: event];
+                return;
+            }
+        }
+
+        Self::handle_event(this, cmd, event);
+    }
+
+    extern "C" fn key_event(this: &mut Object, cmd: Sel, event: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            // The user may type on several keyboards.
+            (*event_handler)
+                .keys
+                .set_physical_layout(PhysicalLayout::current());
+            let modifiers =
+                keyboard::ModifiersState::from(ModifierFlags(NSEvent::modifierFlags(event)));
+            let key_code = (*event_handler).keys.key_code(
+                NSEvent::keyCode(event),
+                &characters_ignoring_modifiers(event),
+            );
+            let passes_through = !(*event_handler).event_mask.contains(EventMask::KEYBOARD)
+                || key_code
+                    .map(|key_code| {
+                        (*event_handler)
+                            .iced
+                            .application()
+                            .passes_key_through(key_code, modifiers)
+                    })
+                    .unwrap_or(false);
+
+            // Tab moves the focus out of the view once the application has no more widgets to
+            // focus in that direction.
+            if NSEvent::eventType(event) == NSEventType::NSKeyDown
+                && NSEvent::keyCode(event) == TAB_KEY_CODE
+                && !modifiers.control
+                && !modifiers.alt
+                && !modifiers.logo
+                && (*event_handler)
+                    .iced
+                    .application()
+                    .focus_direction_exhausted(!modifiers.shift)
+            {
+                let this_ptr: *mut Object = this;
+                let window: id = msg_send![this, window];
+                if modifiers.shift {
+                    let () = msg_send![window, selectPreviousKeyView: this_ptr];
+                } else {
+                    let () = msg_send![window, selectNextKeyView: this_ptr];
+                }
+                return;
+            }
+
+            if passes_through {
+                let superclass = class!(NSView);
+                if NSEvent::eventType(event) == NSEventType::NSKeyDown {
+                    let () = msg_send![super(this, superclass), keyDown: event];
+                } else {
+                    let () = msg_send![super(this, superclass), keyUp: event];
+                }
+                return;
+            }
+
+            if NSEvent::eventType(event) == NSEventType::NSKeyDown {
+                let had_marked_text = !(*event_handler).keys.marked_text().is_empty();
+
+                // The shortcuts of the application aren't text input.
+                let is_shortcut = key_code.map_or(false, |key_code| {
+                    (*event_handler).is_shortcut(key_code, modifiers)
+                });
+                if !is_shortcut && is_text_input(NSEvent::modifierFlags(event)) {
+                    // The input context calls back the `NSTextInputClient` methods with the text.
+                    let events = NSArray::arrayWithObject(nil, event);
+                    let () = msg_send![this, interpretKeyEvents: events];
+                }
+
+                // The keys composing the text belong to the input method.
+                (*event_handler).keys.set_composing_key(had_marked_text);
+            }
+        }
+
+        Self::handle_event(this, cmd, event);
+    }
+
+    extern "C" fn insert_text(
+        this: &mut Object,
+        _cmd: Sel,
+        string: id,
+        replacement_range: TextRange,
+    ) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            (*event_handler).set_marked_text(String::new());
+
+            // The accent picker of the press-and-hold replaces the character inserted by the
+            // press. The widgets can't be edited directly, so the replaced characters are erased
+            // the same way the user would do it.
+            let replacement_range = replacement_range.0;
+            if replacement_range.location != TextRange::NOT_FOUND.0.location {
+                (*event_handler).queue_event(backspace_events(replacement_range.length as usize));
+            }
+
+            // The text can come without any key press, e.g. from the Character Viewer, and an
+            // emoji can consist of several chars, so each of them is delivered.
+            (*event_handler).queue_event(text_events(&string_from_text_input(string)));
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    extern "C" fn set_marked_text(
+        this: &mut Object,
+        _cmd: Sel,
+        string: id,
+        _selected_range: TextRange,
+        _replacement_range: TextRange,
+    ) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            (*event_handler).set_marked_text(string_from_text_input(string));
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    // The marked text is accepted as if it had been inserted.
+    extern "C" fn unmark_text(this: &mut Object, _cmd: Sel) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let text = (*event_handler).keys.marked_text().to_string();
+            (*event_handler).set_marked_text(String::new());
+            (*event_handler).queue_event(text_events(&text));
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    extern "C" fn has_marked_text(this: &mut Object, _cmd: Sel) -> BOOL {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if (*event_handler).keys.marked_text().is_empty() {
+                NO
+            } else {
+                YES
+            }
+        }
+    }
+
+    extern "C" fn marked_range(this: &mut Object, _cmd: Sel) -> TextRange {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let length = (*event_handler).keys.marked_text().encode_utf16().count();
+            if length == 0 {
+                TextRange::NOT_FOUND
+            } else {
+                TextRange(NSRange::new(0, length as NSUInteger))
+            }
+        }
+    }
+
+    // The view doesn't know about the selection in the widgets.
+    extern "C" fn selected_range(_this: &Object, _cmd: Sel) -> TextRange {
+        TextRange::NOT_FOUND
+    }
+
+    extern "C" fn valid_attributes_for_marked_text(_this: &Object, _cmd: Sel) -> id {
+        unsafe { msg_send![class!(NSArray), array] }
+    }
+
+    extern "C" fn attributed_substring_for_proposed_range(
+        _this: &Object,
+        _cmd: Sel,
+        _range: TextRange,
+        _actual_range: *mut c_void,
+    ) -> id {
+        nil
+    }
+
+    extern "C" fn character_index_for_point(
+        _this: &Object,
+        _cmd: Sel,
+        _point: NSPoint,
+    ) -> NSUInteger {
+        TextRange::NOT_FOUND.0.location
+    }
+
+    // The candidate window of the input method is placed next to this rectangle.
+    extern "C" fn first_rect_for_character_range(
+        this: &mut Object,
+        _cmd: Sel,
+        _range: TextRange,
+        _actual_range: *mut c_void,
+    ) -> NSRect {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let rect = match (*event_handler).iced.application().input_method_area() {
+                Some(area) => NSRect::new(
+                    NSPoint::new(area.x.into(), area.y.into()),
+                    NSSize::new(area.width.into(), area.height.into()),
+                ),
+                None => msg_send![this, bounds],
+            };
+
+            let window: id = msg_send![this, window];
+            if window.is_null() {
+                return NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(0.0, 0.0));
+            }
+            let rect_in_window: NSRect = msg_send![this, convertRect: rect toView: nil];
+            msg_send![window, convertRectToScreen: rect_in_window]
+        }
+    }
+
+    // The commands, like `deleteBackward:`, are handled by the widgets from the key presses.
+    extern "C" fn do_command_by_selector(_this: &Object, _cmd: Sel, _selector: Sel) {}
+
+    extern "C" fn perform_key_equivalent(this: &mut Object, cmd: Sel, event: *mut Object) -> BOOL {
+        unsafe {
+            // The key equivalents are sent to all the views of the window, so only the focused
+            // view may claim them.
+            let this_ptr: *mut Object = this;
+            let window: id = msg_send![this, window];
+            let first_responder: id = msg_send![window, firstResponder];
+            if first_responder != this_ptr || NSEvent::eventType(event) != NSEventType::NSKeyDown {
+                return NO;
+            }
+
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if !(*event_handler).event_mask.contains(EventMask::KEYBOARD) {
+                return NO;
+            }
+
+            (*event_handler)
+                .keys
+                .set_physical_layout(PhysicalLayout::current());
+            let modifiers =
+                keyboard::ModifiersState::from(ModifierFlags(NSEvent::modifierFlags(event)));
+
+            // The Edit shortcuts work even if the host has no Edit menu.
+            let edit_action =
+                EditAction::from_key(&characters_ignoring_modifiers(event), modifiers);
+            if let Some(action) = edit_action {
+                if (*event_handler).perform_edit_action(action) {
+                    let () = msg_send![this, setNeedsDisplay: YES];
+                    return YES;
+                }
+            }
+
+            let is_wanted = (*event_handler)
+                .keys
+                .key_code(
+                    NSEvent::keyCode(event),
+                    &characters_ignoring_modifiers(event),
+                )
+                .map(|key_code| {
+                    let application = (*event_handler).iced.application();
+                    application.wants_key_equivalent(key_code, modifiers)
+                        || application
+                            .shortcuts()
+                            .resolve(
+                                key_code,
+                                modifiers,
+                                application.input_method_area().is_some(),
+                            )
+                            .is_some()
+                })
+                .unwrap_or(false);
+
+            if !is_wanted {
+                return NO;
+            }
+        }
+
+        Self::handle_event(this, cmd, event);
+        YES
+    }
+
+    extern "C" fn edit_action(this: &mut Object, cmd: Sel, _sender: id) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if let Some(action) = EditAction::from_selector(cmd) {
+                if (*event_handler).perform_edit_action(action) {
+                    let () = msg_send![this, setNeedsDisplay: YES];
+                }
+            }
+        }
+    }
+
+    extern "C" fn validate_menu_item(this: &mut Object, _cmd: Sel, item: id) -> BOOL {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            let action: Sel = msg_send![item, action];
+            match EditAction::from_selector(action) {
+                Some(action) if (*event_handler).can_perform_edit_action(action) => YES,
+                Some(_) => NO,
+                // The other items are validated by the superclass, if it validates any, and
+                // enabled otherwise, like AppKit does without any validation.
+                None => {
+                    let superclass = class!(NSView);
+                    let validates: BOOL = msg_send![
+                        superclass,
+                        instancesRespondToSelector: sel!(validateMenuItem:)
+                    ];
+                    if validates == YES {
+                        msg_send![super(this, superclass), validateMenuItem: item]
+                    } else {
+                        YES
+                    }
+                }
+            }
+        }
+    }
+
+    extern "C" fn handle_event(this: &mut Object, _cmd: Sel, event: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            // A drag can't start once the button has been released.
+            if NSEvent::eventType(event) == NSEventType::NSLeftMouseUp {
+                (*event_handler).set_last_mouse_down(nil);
+            }
+            // The filtered events are dropped before anything else, so they cost nothing.
+            if !(*event_handler)
+                .event_mask
+                .contains(EventMask::from_event_type(NSEvent::eventType(event)))
+            {
+                return;
+            }
+
+            let disposition = (*event_handler)
+                .iced
+                .application()
+                .on_native_event(&event::NativeEventRef::new(event, this));
+            match disposition {
+                event::NativeEventDisposition::Pass => {}
+                event::NativeEventDisposition::Consume => return,
+                event::NativeEventDisposition::Produce(message) => {
+                    (*event_handler).iced.queue_message(message);
+                }
+            }
+
+            (*event_handler).on_native_event(NSEventT {
+                raw_event: event,
+                view: this,
+            });
+            // The events which are dropped or produce nothing don't cost an update.
+            if (*event_handler).has_pending_update() {
+                let () = msg_send![this, setNeedsDisplay: YES];
+            }
+        };
+    }
+
+    extern "C" fn quick_look(_this: &Object, _cmd: Sel, _event: *mut Object) {}
+
+    // The type of this event is missing in `NSEventType`, so it can't go through `handle_event`.
+    extern "C" fn smart_magnify(this: &mut Object, _cmd: Sel, event: *mut Object) {
+        unsafe {
+            let value = this.get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let event_handler = *value as *mut EventHandler<A>;
+            if !(*event_handler).event_mask.contains(EventMask::SCROLL) {
+                return;
+            }
+
+            let position = NSEventT {
+                raw_event: event,
+                view: this,
+            }
+            .location();
+            (*event_handler).on_view_event(event::Event::Gesture(
+                event::GestureEvent::SmartMagnify { position },
+            ));
+            let () = msg_send![this, setNeedsDisplay: YES];
+        }
+    }
+
+    /// Get a raw pointer to the Cocoa view.
+    pub fn raw_object(&self) -> *mut Object {
+        self.object
+    }
+
+    /// Make this view a subview of another view.
+    pub unsafe fn make_subview_of(&self, view: *mut c_void) {
+        NSView::addSubview_(view as id, self.object);
+    }
+
+    /// Change the direction of the scroll deltas delivered to the application.
+    pub fn set_scroll_direction(&mut self, direction: ScrollDirection) {
+        self.event_handler_mut().scroll_direction = direction;
+    }
+
+    /// Change the factor the scroll deltas are multiplied by.
+    pub fn set_scroll_sensitivity(&mut self, sensitivity: f32) {
+        self.event_handler_mut().scroll_sensitivity = sensitivity;
+    }
+
+    /// Hide or show the cursor, e.g. while dragging a knob.
+    pub fn set_cursor_hidden(&mut self, is_hidden: bool) {
+        self.event_handler_mut().set_cursor_hidden(is_hidden);
+    }
+
+    /// Show a custom cursor made of RGBA pixels, instead of the cursors of the widgets.
+    ///
+    /// The `hotspot` is the point of the image, in pixels from the top-left corner, which
+    /// clicks.
+    ///
+    /// It panics if the pixels don't match the size or if the size is empty.
+    pub fn set_custom_cursor(&mut self, rgba: &[u8], size: Size<u32>, hotspot: Point) {
+        assert!(
+            size.width > 0 && size.height > 0,
+            "The cursor can't be empty."
+        );
+        assert_eq!(
+            Some(rgba.len()),
+            rgba_len(size),
+            "The pixels don't match the size of the cursor."
+        );
+        self.event_handler_mut()
+            .set_custom_cursor(rgba, size, hotspot);
+    }
+
+    /// Go back to the cursors of the widgets after `set_custom_cursor`.
+    pub fn clear_custom_cursor(&mut self) {
+        self.event_handler_mut().clear_custom_cursor();
+    }
+
+    /// Lock the pointer, e.g. while dragging a knob, so the drag can go on beyond the edges of
+    /// the screen.
+    ///
+    /// The cursor is hidden and stays still, and the mouse moves are delivered as
+    /// `RelativeMotion` events instead of `CursorMoved` ones until `end_pointer_lock` is called.
+    pub fn begin_pointer_lock(&mut self) {
+        self.event_handler_mut().begin_pointer_lock();
+    }
+
+    /// Unlock the pointer locked by `begin_pointer_lock`, moving the cursor back to where the
+    /// lock has begun.
+    pub fn end_pointer_lock(&mut self) {
+        self.event_handler_mut().end_pointer_lock();
+    }
+
+    /// Move the cursor to the `position` in the logical view coordinates, e.g. back onto the knob
+    /// after a fine adjustment.
+    ///
+    /// The widgets see the cursor at the new position, without the move the warp produces.
+    pub fn warp_cursor(&mut self, position: Point) {
+        unsafe {
+            let window: id = msg_send![self.object, window];
+            if window == nil {
+                return;
+            }
+
+            let screens: id = msg_send![class!(NSScreen), screens];
+            if screens.count() == 0 {
+                return;
+            }
+            let primary_screen_frame: NSRect = msg_send![screens.objectAtIndex(0), frame];
+            let bounds = NSView::bounds(self.object);
+            let geometry = ViewGeometry {
+                frame_in_window: msg_send![self.object, convertRect: bounds toView: nil],
+                window_frame: msg_send![window, frame],
+                scale_factor: msg_send![window, backingScaleFactor],
+                primary_screen_height: primary_screen_frame.size.height,
+            };
+
+            let _ = CGDisplay::warp_mouse_cursor_position(geometry.global_display_point(position));
+        }
+
+        let event_handler = self.event_handler_mut();
+        event_handler.warped_cursor_position = Some(position);
+        event_handler.queue_event(vec![Event::Mouse(mouse::Event::CursorMoved {
+            x: position.x,
+            y: position.y,
+        })]);
+        unsafe {
+            let () = msg_send![self.object, setNeedsDisplay: YES];
+        }
+    }
+
+    /// Start dragging the items of the session out of the view, e.g. onto the Finder.
+    ///
+    /// It has to be called while the left mouse button is held on the view, like after the
+    /// message of a press has been handled. It returns whether the drag has started.
+    pub fn begin_drag(&mut self, mut session: drag::DragSession<A::Message>) -> bool {
+        let event = self.event_handler().last_mouse_down;
+        if event == nil || session.items.is_empty() {
+            return false;
+        }
+
+        unsafe {
+            let position = NSEventT {
+                raw_event: event,
+                view: self.object,
+            }
+            .location();
+            let scale_factor = self.event_handler().viewport.scale_factor();
+            let items = drag::make_dragging_items(&mut session, position, scale_factor);
+            if items.is_empty() {
+                return false;
+            }
+            let array = NSArray::arrayWithObjects(nil, &items);
+            let dragging_session: id = msg_send![
+                self.object,
+                beginDraggingSessionWithItems: array
+                event: event
+                source: self.object
+            ];
+            let formation = session.formation.to_ns_dragging_formation();
+            self.event_handler_mut().drag_operation_mask = session.operation_mask();
+            let () = msg_send![dragging_session, setDraggingFormation: formation];
+            items.into_iter().for_each(|item| {
+                let () = msg_send![item, release];
+            });
+        }
+        // The session takes the mouse up, which would clear it otherwise.
+        self.event_handler_mut().set_last_mouse_down(nil);
+        self.event_handler_mut().on_drag_end = session.on_end;
+        true
+    }
+
+    /// Change the kinds of the things which can be dropped onto the view, registering the view
+    /// for them again.
+    pub fn set_accepted_drop_types(&mut self, drop_types: Vec<DropType>) {
+        unsafe {
+            Self::register_drop_types(self.object, &drop_types);
+        }
+        self.event_handler_mut().drop_types = drop_types;
+    }
+
+    /// Start dragging the payload inside the view, e.g. to reorder the items of a list.
+    ///
+    /// The drag doesn't leave the view. Until the left mouse button is released, the moves are
+    /// delivered as `InternalDragMoved` events, and the release as an `InternalDragDropped`
+    /// one, along with the payload given to [`Application::on_internal_drop`].
+    ///
+    /// [`Application::on_internal_drop`]: trait.Application.html#method.on_internal_drop
+    pub fn begin_internal_drag(&mut self, payload: Box<dyn Any + Send>) {
+        self.event_handler_mut().internal_drag = Some(payload);
+    }
+
+    /// Get the payload of the current internal drag, if it's of the type `T`.
+    pub fn internal_drag_payload<T: Any>(&self) -> Option<&T> {
+        self.event_handler()
+            .internal_drag
+            .as_ref()
+            .and_then(|payload| payload.downcast_ref())
+    }
+
+    /// Change the way the frames are presented, e.g. for a low latency mode, recreating the
+    /// swap chain.
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.event_handler_mut().set_present_mode(present_mode);
+        unsafe {
+            let () = msg_send![self.object, setNeedsDisplay: YES];
+        }
+    }
+
+    /// Change the tuning of the presentation by the Metal layer, recreating the swap chain.
+    ///
+    /// Without any tuning, the layer is configured by wgpu for the present mode again.
+    pub fn set_layer_tuning(&mut self, layer_tuning: Option<LayerTuning>) {
+        self.event_handler_mut().set_layer_tuning(layer_tuning);
+        unsafe {
+            let () = msg_send![self.object, setNeedsDisplay: YES];
+        }
+    }
+
+    /// Change the way the key codes are reported.
+    pub fn set_keymap_mode(&mut self, keymap_mode: KeymapMode) {
+        self.event_handler_mut().keys.set_keymap_mode(keymap_mode);
+    }
+
+    /// Get the recent events converted from the native events, along with their time, from the
+    /// oldest to the newest.
+    ///
+    /// It's useful for the interactions depending on the velocity, like flicking.
+    pub fn recent_events(&self) -> impl Iterator<Item = &event::TimedEvent> {
+        self.event_handler().recent_events.iter()
+    }
+
+    /// Get the clipboard the widgets copy to and paste from, e.g. to copy the text
+    /// programmatically.
+    pub fn clipboard(&self) -> &dyn clipboard::ClipboardBackend {
+        &*self.event_handler().clipboard
+    }
+
+    /// Show or hide the timings of the updates and of the rendering over the interface.
+    pub fn set_debug_overlay(&mut self, is_shown: bool) {
+        self.event_handler_mut().set_debug_overlay(is_shown);
+        unsafe {
+            let () = msg_send![self.object, setNeedsDisplay: YES];
+        }
+    }
+
+    /// Start or stop redrawing the view at each refresh of its display, e.g. for the meters,
+    /// with an `AnimationFrame` event before each redraw.
+    ///
+    /// The redraws are paused while the view isn't in a window or is hidden.
+    pub fn set_animating(&mut self, is_animating: bool) {
+        self.event_handler_mut().is_animating = is_animating;
+        unsafe {
+            Self::update_display_link(&mut *self.object);
+        }
+    }
+
+    /// Change the highest rate of the animation frames, or follow the refresh rate of the
+    /// display with `None`.
+    pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
+        self.event_handler_mut().min_frame_interval = max_fps.map(min_frame_interval);
+    }
+
+    /// Draw the current state of the interface offscreen and read its pixels back, even if the
+    /// view isn't in a window.
+    pub fn capture_frame(&mut self) -> Result<capture::RgbaImage, capture::CaptureError> {
+        self.event_handler_mut().capture_frame()
+    }
+
+    /// Get the number of redraws skipped as they wouldn't have changed the interface, which
+    /// tells how much drawing is spared.
+    pub fn skipped_frames(&self) -> u64 {
+        self.event_handler().skipped_frames
+    }
+
+    /// Get the optional features of the device, e.g. to use the anisotropic filtering only when
+    /// it's there.
+    pub fn extensions(&self) -> &Extensions {
+        self.event_handler().iced.gpu_context().extensions()
+    }
+
+    /// Get the limits of the device.
+    pub fn limits(&self) -> &Limits {
+        self.event_handler().iced.gpu_context().limits()
+    }
+
+    /// Get the context of the device the view draws with, e.g. to create another view with it.
+    pub fn gpu_context(&self) -> gpu::GpuContext {
+        self.event_handler().iced.gpu_context().clone()
+    }
+
+    /// Get the state of the modifiers, e.g. to adjust a knob finely while Shift is held.
+    pub fn modifiers(&self) -> keyboard::ModifiersState {
+        self.event_handler().modifiers
+    }
+
+    /// Get the state of the modifiers held while files are dragged over the view, or `None`
+    /// without a drag.
+    pub fn drag_modifiers(&self) -> Option<keyboard::ModifiersState> {
+        self.event_handler().file_drag.modifiers
+    }
+
+    /// Get the state of the modifiers not covered by iced.
+    pub fn extra_modifiers(&self) -> event::ExtraModifiers {
+        self.event_handler().keys.extra_modifiers()
+    }
+
+    /// Change how the cursor position is reported when a drag leaves the view.
+    pub fn set_drag_position_policy(&mut self, policy: DragPositionPolicy) {
+        self.event_handler_mut().drag_position_policy = policy;
+    }
+
+    /// Queue an iced event as if it had been received by the view, e.g. to drive the widgets
+    /// from the host or from a test.
+    ///
+    /// The event is processed on the next redraw, which is requested. It must be called on the
+    /// main thread.
+    pub fn queue_event(&mut self, event: Event) {
+        self.queue_events(vec![event]);
+    }
+
+    /// Queue several iced events at once, see [`queue_event`].
+    ///
+    /// [`queue_event`]: #method.queue_event
+    pub fn queue_events(&mut self, events: Vec<Event>) {
+        unsafe {
+            let is_main_thread: BOOL = msg_send![class!(NSThread), isMainThread];
+            assert!(
+                is_main_thread == YES,
+                "The events must be queued on the main thread."
+            );
+        }
+
+        self.event_handler_mut().queue_event(events);
+        unsafe {
+            let () = msg_send![self.object, setNeedsDisplay: YES];
+        }
+    }
+
+    /// Change the classes of the events delivered to the application.
+    ///
+    /// It takes effect for the subsequent events.
+    pub fn set_event_mask(&mut self, event_mask: EventMask) {
+        self.event_handler_mut().event_mask = event_mask;
+    }
+
+    /// Change when the view tracks the cursor.
+    pub fn set_mouse_tracking(&mut self, mouse_tracking: MouseTracking) {
+        self.event_handler_mut().mouse_tracking = mouse_tracking;
+        unsafe {
+            let () = msg_send![self.object, updateTrackingAreas];
+        }
+    }
+
+    fn event_handler(&self) -> &EventHandler<A> {
+        unsafe {
+            let value = (*self.object).get_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            &*(*value as *mut EventHandler<A>)
+        }
+    }
+
+    fn event_handler_mut(&mut self) -> &mut EventHandler<A> {
+        unsafe {
+            let value = (*self.object).get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            &mut *(*value as *mut EventHandler<A>)
+        }
+    }
+}
+
+impl<A: 'static + Application> Drop for IcedView<A> {
+    fn drop(&mut self) {
+        unsafe {
+            let value = (*self.object).get_mut_ivar::<*mut c_void>(Self::EVENT_HANDLER_IVAR);
+            let mut event_handler = Box::from_raw(*value as *mut EventHandler<A>);
+            // The mouse would stay detached from the cursor after the view is gone.
+            event_handler.end_pointer_lock();
+            // The calls to hide the cursor must be balanced.
+            event_handler.set_cursor_hidden(false);
+            event_handler.clear_custom_cursor();
+            event_handler.set_last_mouse_down(nil);
+            drop(event_handler);
+            // The readers of the promised files may outlive the view.
+            *value = std::ptr::null_mut();
+            let monitor = *(*self.object).get_ivar::<id>(Self::MEDIA_KEY_MONITOR_IVAR);
+            NSEvent::removeMonitor_(nil, monitor);
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let () = msg_send![center, removeObserver: self.object];
+            Self::remove_tracking_area(&mut *self.object);
+            let () = msg_send![self.object, release];
+        }
+    }
+}
+
+/// Implement this trait for your application then pass it into `IcedView::new`.
+pub trait Application {
+    /// The message your application will produce.
+    type Message: Clone + std::fmt::Debug + Send;
+
+    /// Message processing function.
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message>;
+
+    /// Application interface.
+    fn view(&mut self) -> Element<'_, Self::Message>;
+
+    /// Returns the background color of the [`Application`].
+    ///
+    /// With an alpha below 1.0, the content behind the view shows through it. The opaque
+    /// background is cheaper to composite.
+    ///
+    /// By default, it returns `Color::WHITE`.
+    fn background_color(&self) -> Color {
+        Color::WHITE
+    }
+
+    /// Creates the GPU resources of the custom drawing, once the device is there.
+    ///
+    /// By default, it does nothing.
+    fn setup_gpu(&mut self, _device: &wgpu::Device) {}
+
+    /// Records the custom drawing under the widgets, after the background is cleared.
+    ///
+    /// The state changed by the drawing, e.g. the time of an animation, has to be kept in a
+    /// `Cell` or a `RefCell`, as the application is only borrowed.
+    ///
+    /// By default, it does nothing.
+    fn custom_draw(&self, _context: &mut headless::CustomDrawContext<'_>) {}
+
+    /// Returns the message for an error of the rendering, e.g. to show that the view can't be
+    /// drawn anymore.
+    ///
+    /// By default, it returns `None`.
+    fn on_render_error(&self, _error: RenderError) -> Option<Self::Message> {
+        None
+    }
+
+    /// Handles an [`event::Event`] produced by the view, returning a message for it.
+    ///
+    /// By default, it ignores all the events.
+    ///
+    /// [`event::Event`]: event/enum.Event.html
+    fn on_event(&self, _event: event::Event) -> Option<Self::Message> {
+        None
+    }
+
+    /// Inspects a native event before it's converted, for the details iced doesn't cover, like
+    /// the device of a tablet event.
+    ///
+    /// The returned disposition tells whether the event is converted as usual. The event is only
+    /// valid during this call.
+    ///
+    /// By default, it passes all the events through.
+    fn on_native_event(
+        &self,
+        _event: &event::NativeEventRef<'_>,
+    ) -> event::NativeEventDisposition<Self::Message> {
+        event::NativeEventDisposition::Pass
+    }
+
+    /// Returns whether the view receives the clicks at the `point`.
+    ///
+    /// The `point` is in the logical view coordinates. When it returns `false`, the clicks go to
+    /// the views underneath, which is useful for the transparent regions.
+    ///
+    /// By default, it returns `true`.
+    fn hit_test(&self, _point: Point) -> bool {
+        true
+    }
+
+    /// Returns the context menu shown by right-clicking at the `point`.
+    ///
+    /// The `point` is in the logical view coordinates. When a menu is returned, the click isn't
+    /// delivered to the application, and the message of the chosen item is.
+    ///
+    /// By default, it returns `None`.
+    fn context_menu(&self, _point: Point) -> Option<menu::Menu<Self::Message>> {
+        None
+    }
+
+    /// Returns whether the window can be dragged by clicking at the `point`.
+    ///
+    /// The `point` is in the logical view coordinates. When it returns `true`, the click moves
+    /// the window and it's not delivered to the application, so it should return `false` for the
+    /// widgets.
+    ///
+    /// By default, it returns `false`.
+    fn is_draggable_region(&self, _point: Point) -> bool {
+        false
+    }
+
+    /// Returns whether the application claims the shortcut.
+    ///
+    /// Hosts usually handle the shortcuts with Command in their menus before the view receives
+    /// them. If this method returns `true`, the shortcut is delivered to the application as a
+    /// normal key press instead. It's consulted only while the view is focused.
+    ///
+    /// By default, it returns `false`.
+    fn wants_key_equivalent(
+        &self,
+        _key_code: keyboard::KeyCode,
+        _modifiers: keyboard::ModifiersState,
+    ) -> bool {
+        false
+    }
+
+    /// Returns what a drop of the files at the `point` would do, which the cursor shows.
+    ///
+    /// It's asked when a drag enters the view and whenever it moves. While it returns
+    /// [`DragOperation::None`], the drag isn't reported to the application, so neither
+    /// `FileHovered` nor `FileDropped` is delivered, and the cursor shows that the drop isn't
+    /// allowed. The `paths` are empty when something other than files is dragged.
+    ///
+    /// By default, it returns [`DragOperation::Copy`].
+    ///
+    /// [`DragOperation::None`]: event/enum.DragOperation.html#variant.None
+    /// [`DragOperation::Copy`]: event/enum.DragOperation.html#variant.Copy
+    fn drag_operation(&self, _paths: &[PathBuf], _point: Point) -> event::DragOperation {
+        event::DragOperation::Copy
+    }
+
+    /// Returns the message for the payload of an internal drag dropped at the `point`, e.g. the
+    /// index of the moved step downcast from the payload.
+    ///
+    /// The `point` is in the logical view coordinates. The message is handled before the ones
+    /// produced by the release of the button.
+    ///
+    /// By default, it returns `None`.
+    fn on_internal_drop(
+        &self,
+        _payload: Box<dyn Any + Send>,
+        _point: Point,
+    ) -> Option<Self::Message> {
+        None
+    }
+
+    /// Returns the keyboard shortcuts of the application.
+    ///
+    /// The key presses matching a shortcut produce its message instead of being delivered to the
+    /// widgets, and the shortcuts with Command are claimed from the host like with
+    /// [`wants_key_equivalent`]. While a text input is focused, which is told by
+    /// [`input_method_area`], only the shortcuts with Command fire.
+    ///
+    /// By default, it returns an empty map.
+    ///
+    /// [`wants_key_equivalent`]: #method.wants_key_equivalent
+    /// [`input_method_area`]: #method.input_method_area
+    fn shortcuts(&self) -> shortcuts::ShortcutMap<Self::Message> {
+        shortcuts::ShortcutMap::new()
+    }
+
+    /// Returns whether the focus has reached the last widget in the direction of Tab, which is
+    /// `forward` without Shift.
+    ///
+    /// When it returns `true`, Tab moves the focus to the next view of the window rather than
+    /// being delivered to the application, so the users navigating with the keyboard can leave
+    /// the view.
+    ///
+    /// By default, it returns `false`.
+    fn focus_direction_exhausted(&self, _forward: bool) -> bool {
+        false
+    }
+
+    /// Returns whether the key event should be passed to the next responder instead of the
+    /// application.
+    ///
+    /// The events are processed by the widgets asynchronously, so the view can't tell whether
+    /// they have been handled. This method lets the host receive the keys the application doesn't
+    /// use, e.g. the space bar for the transport control.
+    ///
+    /// By default, it returns `false`.
+    fn passes_key_through(
+        &self,
+        _key_code: keyboard::KeyCode,
+        _modifiers: keyboard::ModifiersState,
+    ) -> bool {
+        false
+    }
+
+    /// Returns the text selected in the focused text input, which is copied by Copy and Cut.
+    ///
+    /// The widgets don't expose their selection, so the application has to tell it. If `None` is
+    /// returned, nothing is copied.
+    ///
+    /// By default, it returns `None`.
+    fn selected_text(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the area of the focused text input in the logical view coordinates.
+    ///
+    /// The candidate window of the input method is placed next to this area. If `None` is
+    /// returned, the bounds of the view are used.
+    ///
+    /// By default, it returns `None`.
+    fn input_method_area(&self) -> Option<Rectangle> {
+        None
+    }
+
+    /// Returns whether the inertial scrolling should be stopped.
+    ///
+    /// While it returns `true`, the momentum `WheelScrolled` events are not delivered to the
+    /// application. It's useful when the scrolled content has reached its end.
+    ///
+    /// By default, it returns `false`.
+    fn should_cancel_scroll_momentum(&self) -> bool {
+        false
+    }
+
+    /// Returns the description of the Touch Bar shown while the view is focused.
+    ///
+    /// It's queried after every update, and the bar is recreated when its items change.
+    ///
+    /// By default, it returns `None`.
+    #[cfg(feature = "touch-bar")]
+    fn touch_bar(&self) -> Option<touch_bar::TouchBarSpec<Self::Message>> {
+        None
+    }
+}
+
+/// The settings of the view.
+#[derive(Debug)]
+pub struct Settings {
+    /// The bytes of the font that will be used by default.
+    ///
+    /// If `None` is provided, a default system font will be chosen.
+    pub default_font: Option<&'static [u8]>,
+    /// The default size of text.
+    ///
+    /// By default, it will be set to 20.
+    pub default_text_size: u16,
+    /// The antialiasing of some primitives.
+    ///
+    /// Enabling it can produce a smoother result in some widgets, like the `Canvas`, at a
+    /// performance cost, which grows with the sample count. If the GPU doesn't support the
+    /// sample count, the nearest supported one is used.
+    ///
+    /// By default, it is disabled.
+    pub antialiasing: Option<Antialiasing>,
+    /// The way the frames are presented to the display.
+    ///
+    /// By default, they're synchronized with the display, which spares the GPU.
+    pub present_mode: PresentMode,
+    /// The tuning of the presentation by the Metal layer, e.g. for the lowest latency.
+    ///
+    /// By default, the layer is left as wgpu configures it for the present mode.
+    pub layer_tuning: Option<LayerTuning>,
+    /// If set to true, every redraw draws a frame, e.g. for the animations of the custom
+    /// drawing. Otherwise, a redraw which wouldn't change the interface is skipped.
+    ///
+    /// It also makes the view animate from the start, see `IcedView::set_animating`.
+    ///
+    /// By default, it is disabled.
+    pub always_redraw: bool,
+    /// Whether the timings of the updates and of the rendering are shown over the interface.
+    ///
+    /// They're only measured with the `debug` feature of the crate, so the overlay is empty
+    /// without it.
+    ///
+    /// By default, they're shown in the debug builds only.
+    pub debug_overlay: bool,
+    /// The key which shows or hides the debug overlay when it's pressed with exactly the
+    /// modifiers, instead of being delivered to the application, along with its characters and
+    /// its release.
+    ///
+    /// By default, there's no such key.
+    pub debug_key: Option<(keyboard::KeyCode, keyboard::ModifiersState)>,
+    /// The highest rate of the animation frames, e.g. to spare the GPU on the 120 Hz displays.
+    /// The redraws caused by the input aren't limited.
+    ///
+    /// By default, the animation follows the refresh rate of the display.
+    pub max_fps: Option<u32>,
+    /// The format of the frames, for the hosts which composite them in another color space.
+    ///
+    /// By default, the frames are encoded into sRGB, see `TextureFormat::default`.
+    pub texture_format: Option<TextureFormat>,
+    /// The color space the colors of the application are given in, which the layer is tagged
+    /// with, e.g. Display P3 to match the mockups made for the wide-gamut displays.
+    ///
+    /// The background color is given in sRGB, like the colors of the host, and it's converted
+    /// into the color space, so it keeps matching them.
+    ///
+    /// By default, the layer isn't tagged, so its colors are shown as they were before this
+    /// setting.
+    pub color_space: ColorSpace,
+    /// The preference between the integrated and the discrete GPU of the Macs with both.
+    ///
+    /// The discrete GPU suits the heavy visualizations, but it drains the battery.
+    ///
+    /// By default, the integrated GPU is preferred.
+    pub power_preference: PowerPreference,
+    /// The graphics APIs which the GPU is looked for with.
+    ///
+    /// By default, only Metal is used, which spares the probing of the others at startup.
+    pub backends: BackendBit,
+    /// The optional features requested from the device, e.g. the anisotropic filtering for the
+    /// images.
+    ///
+    /// By default, none is requested.
+    pub extensions: Extensions,
+    /// The limits requested from the device.
+    ///
+    /// If they're beyond what wgpu can give, [`IcedView::try_new`] returns
+    /// `Error::TooManyBindGroups`, while [`IcedView::new`] panics.
+    ///
+    /// [`IcedView::try_new`]: struct.IcedView.html#method.try_new
+    /// [`IcedView::new`]: struct.IcedView.html#method.new
+    ///
+    /// By default, the default limits of wgpu are requested.
+    pub limits: Limits,
+    /// If set to true, the view draws with the device shared by the views of the thread, e.g. by
+    /// the editors of several instances of a plugin, which saves memory and time. The settings of
+    /// the device of the first of them are used, with a warning if the later ones differ.
+    ///
+    /// The views live on the main thread, so they share the device across the process.
+    ///
+    /// By default, it is enabled.
+    pub share_gpu: bool,
+    /// The direction of the scroll deltas delivered to the application.
+    ///
+    /// By default, it follows the system preference.
+    pub scroll_direction: ScrollDirection,
+    /// If set to true, scrolling vertically with Shift held scrolls horizontally, as in the
+    /// other applications of macOS.
+    ///
+    /// By default, it is enabled.
+    pub shift_scrolls_horizontally: bool,
+    /// The factor the scroll deltas are multiplied by, both the pixels and the lines.
+    ///
+    /// By default, it is 1.0.
+    pub scroll_sensitivity: f32,
+    /// The additional factor the scroll deltas are multiplied by while Option is held, for the
+    /// precise adjustments of the knobs.
+    ///
+    /// By default, it is 0.1.
+    pub scroll_fine_adjust_factor: f32,
+    /// If set to true, the click which activates the window is delivered to the application.
+    ///
+    /// It's useful for plugin windows, which are often inactive while the user interacts with
+    /// them.
+    ///
+    /// By default, it is disabled.
+    pub accepts_first_mouse: bool,
+    /// If set to true, clicking with Control held is delivered as a right click, following the
+    /// convention of macOS for the single-button mice and trackpads.
+    ///
+    /// By default, it is enabled.
+    pub ctrl_click_emulates_right: bool,
+    /// If set to true, the auto-repeated key presses are not delivered to the application.
+    ///
+    /// The characters received from the repeated events are delivered anyway, so holding a key
+    /// in a text input keeps working.
+    ///
+    /// By default, it is disabled.
+    pub ignore_key_repeat: bool,
+    /// If set to true, the consecutive cursor moves are merged into the last one, and the
+    /// consecutive scrolls are summed up, before being processed.
+    ///
+    /// The drawing tools can disable it to receive every sample.
+    ///
+    /// By default, it is enabled.
+    pub coalesce_events: bool,
+    /// How often the cursor moves are processed.
+    ///
+    /// The displays refreshing at 120 Hz receive the moves faster than they're drawn, so
+    /// processing them once per frame saves the work of the intermediate ones.
+    ///
+    /// By default, every move is processed.
+    pub pointer_update_rate: PointerRate,
+    /// The way the key codes are reported to the application.
+    ///
+    /// By default, the physical keys are reported.
+    pub keymap_mode: KeymapMode,
+    /// If set to true, the Edit actions, i.e. Copy, Cut, Paste and Select All, are performed on
+    /// the focused text input, from the shortcuts or from the Edit menu of the host.
+    ///
+    /// The applications implementing their own editing can disable it.
+    ///
+    /// By default, it is enabled.
+    pub routes_edit_actions: bool,
+    /// When the view tracks the cursor, delivering the moves and the hover changes.
+    ///
+    /// By default, the cursor is tracked while the window is key.
+    pub mouse_tracking: MouseTracking,
+    /// How the cursor position is reported when a drag leaves the view.
+    ///
+    /// By default, the position isn't clamped.
+    pub drag_position_policy: DragPositionPolicy,
+    /// The classes of the events delivered to the application.
+    ///
+    /// The events of the other classes are dropped before being converted. Without the keyboard
+    /// events, the view doesn't take the focus, so the host keeps receiving the keys.
+    ///
+    /// By default, all the events are delivered.
+    pub event_mask: EventMask,
+    /// The kinds of the things which can be dropped onto the view.
+    ///
+    /// Without any kind, the view doesn't register for the drags at all.
+    ///
+    /// By default, only the files are accepted.
+    pub accepted_drop_types: Vec<DropType>,
+    /// The clipboard the widgets copy to and paste from.
+    ///
+    /// By default, it's the general pasteboard of the system.
+    pub clipboard: Box<dyn clipboard::ClipboardBackend>,
+}
+
+impl Settings {
+    /// Creates the default settings with another clipboard, e.g. a `MemoryClipboard` for the
+    /// tests or a named pasteboard for a sandboxed plugin.
+    pub fn with_clipboard(clipboard: impl clipboard::ClipboardBackend + 'static) -> Self {
+        Self {
+            clipboard: Box::new(clipboard),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            default_font: Some(include_bytes!("../fonts/OpenSans-Regular.ttf")),
+            default_text_size: 20,
+            antialiasing: None,
+            present_mode: PresentMode::Fifo,
+            layer_tuning: None,
+            always_redraw: false,
+            debug_overlay: cfg!(debug_assertions),
+            debug_key: None,
+            max_fps: None,
+            texture_format: None,
+            color_space: ColorSpace::Untagged,
+            power_preference: PowerPreference::LowPower,
+            backends: BackendBit::METAL,
+            extensions: Extensions {
+                anisotropic_filtering: false,
+            },
+            limits: Limits::default(),
+            share_gpu: true,
+            scroll_direction: ScrollDirection::System,
+            shift_scrolls_horizontally: true,
+            scroll_sensitivity: 1.0,
+            scroll_fine_adjust_factor: 0.1,
+            accepts_first_mouse: false,
+            ctrl_click_emulates_right: true,
+            ignore_key_repeat: false,
+            coalesce_events: true,
+            pointer_update_rate: PointerRate::Unthrottled,
+            keymap_mode: KeymapMode::Physical,
+            routes_edit_actions: true,
+            mouse_tracking: MouseTracking::KeyWindow,
+            drag_position_policy: DragPositionPolicy::Unclamped,
+            event_mask: EventMask::all(),
+            accepted_drop_types: vec![DropType::Files],
+            clipboard: Box::new(clipboard::Pasteboard::general()),
+        }
+    }
+}
+
+/// The way the frames are presented to the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentMode {
+    /// The frames are synchronized with the refresh of the display, waiting for it if needed.
+    Fifo,
+    /// The frames are shown as soon as they're ready, which lowers the latency but may tear.
+    Immediate,
+    /// The last frame ready is shown at the refresh of the display, without waiting for it.
+    ///
+    /// Metal doesn't support it, so it falls back to `Fifo`.
+    Mailbox,
+}
+
+impl PresentMode {
+    /// The matching mode of wgpu, falling back to `Fifo` for the modes Metal doesn't support.
+    fn supported(self) -> wgpu::PresentMode {
+        match self {
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
+            PresentMode::Mailbox => {
+                log::warn!("The Mailbox present mode isn't supported by Metal, using Fifo.");
+                wgpu::PresentMode::Fifo
+            }
+        }
+    }
+}
+
+/// The tuning of the presentation by the Metal layer, which is applied over the configuration
+/// of wgpu each time the swap chain is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerTuning {
+    /// Whether the frames wait for the refresh of the display.
+    ///
+    /// It's what the present mode sets for Metal, as only `Immediate` doesn't wait, so the
+    /// tuning is followed with a warning if they disagree.
+    pub display_sync: bool,
+    /// The number of drawables the layer cycles through, which is 2 or 3.
+    ///
+    /// With fewer of them, the frames are shown sooner after the input, while the GPU may have
+    /// to wait for the display.
+    pub drawable_count: u8,
+}
+
+impl LayerTuning {
+    /// Returns whether the display sync disagrees with the present mode.
+    fn contradicts(self, present_mode: wgpu::PresentMode) -> bool {
+        self.display_sync != (present_mode != wgpu::PresentMode::Immediate)
+    }
+
+    /// The drawable count, clamped to what the layer supports.
+    fn supported_drawable_count(self) -> u8 {
+        self.drawable_count.max(2).min(3)
+    }
+
+    fn apply(self, layer: id, present_mode: wgpu::PresentMode) {
+        if self.contradicts(present_mode) {
+            log::warn!(
+                "The display sync of the layer is {}, despite the {:?} present mode.",
+                self.display_sync,
+                present_mode
+            );
+        }
+        let drawable_count = self.supported_drawable_count();
+        if drawable_count != self.drawable_count {
+            log::warn!(
+                "The layer can't have {} drawables, using {}.",
+                self.drawable_count,
+                drawable_count
+            );
+        }
+
+        let display_sync = if self.display_sync { YES } else { NO };
+        unsafe {
+            let () = msg_send![layer, setDisplaySyncEnabled: display_sync];
+            let () = msg_send![layer, setMaximumDrawableCount: NSUInteger::from(drawable_count)];
+        }
+    }
+}
+
+/// The format of the frames of the view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    /// 8-bit BGRA encoded into sRGB when written, so the colors are blended in linear space.
+    Bgra8UnormSrgb,
+    /// 8-bit BGRA written as is.
+    Bgra8Unorm,
+}
+
+// wgpu can't query the formats of the surface yet, and the Metal layer presents both variants
+// of BGRA8, so the sRGB one is the default, as iced blends in linear space.
+impl Default for TextureFormat {
+    fn default() -> Self {
+        TextureFormat::Bgra8UnormSrgb
+    }
+}
+
+impl TextureFormat {
+    fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            TextureFormat::Bgra8UnormSrgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Bgra8Unorm => wgpu::TextureFormat::Bgra8Unorm,
+        }
+    }
+}
+
+/// The color space of the colors of the application.
+///
+/// The tagged ones have the transfer function of sRGB, so the same texture formats suit them.
+///
+/// The layer is left untagged by default:
+///
+/// ```
+/// use iced_nsview::{ColorSpace, Settings};
+///
+/// assert_eq!(Settings::default().color_space, ColorSpace::Untagged);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// No color space, so the colors are matched to the display the way the untagged layers
+    /// are.
+    Untagged,
+    /// The color space of most displays and of the web.
+    Srgb,
+    /// The wider color space of the recent Apple displays, which shows more saturated colors on
+    /// them.
+    DisplayP3,
+}
+
+impl ColorSpace {
+    /// Converts a color given in sRGB into the color space, which keeps it as it is without a
+    /// tag.
+    pub fn convert_from_srgb(self, color: Color) -> Color {
+        match self {
+            ColorSpace::Untagged | ColorSpace::Srgb => color,
+            ColorSpace::DisplayP3 => {
+                // Both have the white point D65, so the linear components are only mixed.
+                let [r, g, b, a] = color.into_linear();
+                let mix = |row: [f32; 3]| srgb_encode(row[0] * r + row[1] * g + row[2] * b);
+                Color {
+                    r: mix([0.822_462, 0.177_538, 0.0]),
+                    g: mix([0.033_194, 0.966_806, 0.0]),
+                    b: mix([0.017_083, 0.072_397, 0.910_520]),
+                    a,
+                }
+            }
+        }
+    }
+
+    /// Creates the Core Graphics color space, which the caller releases, or returns null when
+    /// the layer is untagged.
+    unsafe fn create_cg_color_space(self) -> *mut c_void {
+        let name = match self {
+            ColorSpace::Untagged => return std::ptr::null_mut(),
+            ColorSpace::Srgb => kCGColorSpaceSRGB,
+            ColorSpace::DisplayP3 => kCGColorSpaceDisplayP3,
+        };
+        CGColorSpaceCreateWithName(name)
+    }
+}
+
+/// Encodes a linear component with the transfer function of sRGB, which Display P3 shares.
+fn srgb_encode(u: f32) -> f32 {
+    let u = u.max(0.0).min(1.0);
+    if u <= 0.003_130_8 {
+        u * 12.92
+    } else {
+        1.055 * u.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// An error of the creation of a view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// No adapter of the backends of the settings has been found, e.g. in some virtual
+    /// machines.
+    AdapterNotFound,
+    /// The limits of the settings ask for more bind groups than wgpu can give.
+    TooManyBindGroups {
+        /// The number of bind groups of the limits.
+        requested: u32,
+        /// The most bind groups wgpu can give.
+        max: u32,
+    },
+    /// The class of the view couldn't be registered with the Objective-C runtime.
+    ClassRegistrationFailed,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::AdapterNotFound => write!(f, "No adapter has been found."),
+            Error::TooManyBindGroups { requested, max } => write!(
+                f,
+                "Can't request {} bind groups, wgpu gives at most {}.",
+                requested, max
+            ),
+            Error::ClassRegistrationFailed => {
+                write!(f, "The class of the view couldn't be registered.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// An error of the rendering of the view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderError {
+    /// No frame could be acquired from the swap chain for several redraws, even after
+    /// recreating it.
+    FrameUnavailable,
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::FrameUnavailable => write!(f, "No frame could be acquired."),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// The direction of the scroll deltas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    /// The deltas are passed as is, following the "natural scrolling" preference of the system.
+    System,
+    /// The content follows the movement of the fingers, like with "natural scrolling" enabled.
+    Natural,
+    /// Scrolling up moves the content down, like with "natural scrolling" disabled.
+    Traditional,
+}
+
+/// The way the key codes are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeymapMode {
+    /// The key codes describe the position of the keys on a US keyboard, whatever the layout is.
+    ///
+    /// It suits the keys used for their position, like the piano keys mapped on the keyboard.
+    Physical,
+    /// The key codes of the letters, digits and punctuation follow the characters printed on the
+    /// keys by the current layout, e.g. the key labeled Z on a German keyboard is reported as
+    /// `KeyCode::Z`.
+    ///
+    /// It suits the shortcuts, which users find by the labels. The keys producing a character
+    /// absent from the US layout are reported as in the `Physical` mode.
+    Logical,
+}
+
+/// The standard actions of the Edit menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditAction {
+    Copy,
+    Cut,
+    Paste,
+    SelectAll,
+}
+
+impl EditAction {
+    fn from_selector(selector: Sel) -> Option<Self> {
+        if selector == sel!(copy:) {
+            Some(EditAction::Copy)
+        } else if selector == sel!(cut:) {
+            Some(EditAction::Cut)
+        } else if selector == sel!(paste:) {
+            Some(EditAction::Paste)
+        } else if selector == sel!(selectAll:) {
+            Some(EditAction::SelectAll)
+        } else {
+            None
+        }
+    }
+
+    /// Recognizes the shortcut of the action, which is the key with Command only.
+    ///
+    /// The key is told by the characters it types, so the shortcut follows the layout, like the
+    /// ones of the Edit menu.
+    fn from_key(characters: &str, modifiers: keyboard::ModifiersState) -> Option<Self> {
+        if modifiers
+            != (keyboard::ModifiersState {
+                logo: true,
+                ..Default::default()
+            })
+        {
+            return None;
+        }
+
+        match characters.to_lowercase().as_str() {
+            "c" => Some(EditAction::Copy),
+            "x" => Some(EditAction::Cut),
+            "v" => Some(EditAction::Paste),
+            "a" => Some(EditAction::SelectAll),
+            _ => None,
+        }
+    }
+}
+
+/// When the view tracks the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseTracking {
+    /// The cursor is tracked while the window of the view is key.
+    KeyWindow,
+    /// The cursor is tracked while the application is active, e.g. over a plugin window while
+    /// the main window of the host is key.
+    ActiveApp,
+    /// The cursor is tracked even if the application is inactive.
+    Always,
+}
+
+impl MouseTracking {
+    fn options(self) -> NSUInteger {
+        match self {
+            // NSTrackingActiveInKeyWindow
+            MouseTracking::KeyWindow => 0x20,
+            // NSTrackingActiveInActiveApp
+            MouseTracking::ActiveApp => 0x40,
+            // NSTrackingActiveAlways
+            MouseTracking::Always => 0x80,
+        }
+    }
+}
+
+/// How often the cursor moves are processed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerRate {
+    /// Every move is processed.
+    Unthrottled,
+    /// The moves received between two redraws are collapsed into the last one, so they're
+    /// processed once per frame. The button presses and releases are never delayed.
+    PerFrame,
+}
+
+/// How the cursor position is reported when a drag leaves the view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragPositionPolicy {
+    /// The position is clamped to the bounds of the view, which suits the sliders.
+    Clamp,
+    /// The position is reported as is, even if it's negative or beyond the bounds, which suits
+    /// the endless controls, like knobs.
+    Unclamped,
+}
+
+impl DragPositionPolicy {
+    /// Returns the position reported for the cursor at the position in a view of the size.
+    fn apply(self, position: Point, size: Size) -> Point {
+        match self {
+            DragPositionPolicy::Clamp => Point::new(
+                position.x.max(0.0).min(size.width),
+                position.y.max(0.0).min(size.height),
+            ),
+            DragPositionPolicy::Unclamped => position,
+        }
+    }
+}
+
+bitflags! {
+    /// The classes of the events delivered to the application.
+    pub struct EventMask: u32 {
+        /// The clicks, the cursor moves, the pressure and the tablet events.
+        const MOUSE = 1;
+        /// The key presses, the text input and the media keys.
+        const KEYBOARD = 1 << 1;
+        /// The scrolls and the trackpad gestures.
+        const SCROLL = 1 << 2;
+        /// The files dragged onto the view.
+        const DRAG_AND_DROP = 1 << 3;
+    }
+}
+
+impl EventMask {
+    fn from_event_type(event_type: NSEventType) -> Self {
+        match event_type {
+            NSEventType::NSKeyDown
+            | NSEventType::NSKeyUp
+            | NSEventType::NSFlagsChanged
+            | NSEventType::NSSystemDefined => EventMask::KEYBOARD,
+            NSEventType::NSScrollWheel
+            | NSEventType::NSEventTypeGesture
+            | NSEventType::NSEventTypeMagnify
+            | NSEventType::NSEventTypeSwipe
+            | NSEventType::NSEventTypeRotate
+            | NSEventType::NSEventTypeBeginGesture
+            | NSEventType::NSEventTypeEndGesture => EventMask::SCROLL,
+            _ => EventMask::MOUSE,
+        }
+    }
+}
+
+/// A kind of the things which can be dropped onto the view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DropType {
+    /// The files, which are delivered as `FileHovered` and `FileDropped`.
+    Files,
+    /// The text, which is delivered as `DragDropEvent::TextDropped`.
+    Text,
+    /// The images, like PNG or TIFF, which are delivered as `DragDropEvent::ImageDropped`.
+    Image,
+    /// The data of the uniform type identifier, which is delivered as
+    /// `DragDropEvent::DataDropped`.
+    Custom(String),
+}
+
+impl From<&Settings> for RendererSettings {
+    fn from(settings: &Settings) -> RendererSettings {
+        Self {
+            default_font: settings.default_font,
+            default_text_size: settings.default_text_size,
+            antialiasing: settings.antialiasing,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Settings> for RendererSettings {
+    fn from(settings: Settings) -> RendererSettings {
+        Self::from(&settings)
+    }
+}
+
+/// The shortest time between the frames at the rate.
+fn min_frame_interval(max_fps: u32) -> Duration {
+    Duration::from_secs(1) / max_fps.max(1)
+}
+
+/// The modes of the antialiasing, from the lowest sample count.
+const ANTIALIASING_MODES: [Antialiasing; 4] = [
+    Antialiasing::MSAAx2,
+    Antialiasing::MSAAx4,
+    Antialiasing::MSAAx8,
+    Antialiasing::MSAAx16,
+];
+
+/// Returns the indices of the modes from the nearest to the mode, preferring the lower sample
+/// count between two as near.
+fn nearest_antialiasing_modes(antialiasing: Antialiasing) -> Vec<usize> {
+    let index = ANTIALIASING_MODES
+        .iter()
+        .position(|mode| *mode == antialiasing)
+        .unwrap_or(0);
+    let mut candidates: Vec<usize> = (0..ANTIALIASING_MODES.len()).collect();
+    candidates.sort_by_key(|&candidate| {
+        let distance = (candidate as isize - index as isize).abs();
+        (distance, candidate > index)
+    });
+    candidates
+}
+
+/// Falls back to the mode with the nearest sample count supported by the Metal device of the
+/// adapter, preferring the lower one.
+///
+/// The device is found by the name of the adapter, as wgpu doesn't give it. Without any name,
+/// e.g. for the device of a host, the default device of the system is asked instead, which is
+/// the discrete GPU of the Macs with both.
+fn supported_antialiasing(antialiasing: Antialiasing, adapter_name: Option<&str>) -> Antialiasing {
+    let candidates = nearest_antialiasing_modes(antialiasing);
+
+    let supported = unsafe {
+        let device = metal_device(adapter_name);
+        if device == nil {
+            return antialiasing;
+        }
+
+        let supported = candidates.into_iter().find(|&candidate| {
+            // The sample counts of the modes are the powers of two from 2.
+            let sample_count: NSUInteger = 2 << candidate;
+            let is_supported: BOOL = msg_send![device, supportsTextureSampleCount: sample_count];
+            is_supported == YES
+        });
+        let () = msg_send![device, release];
+        supported
+    };
+
+    match supported.map(|candidate| ANTIALIASING_MODES[candidate]) {
+        Some(mode) if mode != antialiasing => {
+            log::warn!(
+                "The {:?} antialiasing isn't supported by the GPU, using {:?}.",
+                antialiasing,
+                mode
+            );
+            mode
+        }
+        _ => antialiasing,
+    }
+}
+
+/// Returns the retained Metal device with the name, or the default one of the system.
+unsafe fn metal_device(name: Option<&str>) -> id {
+    if let Some(name) = name {
+        let devices = MTLCopyAllDevices();
+        if devices != nil {
+            let device = (0..devices.count())
+                .map(|n| devices.objectAtIndex(n))
+                .find(|&device| {
+                    let device_name: id = msg_send![device, name];
+                    device_name != nil && string_from_text_input(device_name) == name
+                });
+            if let Some(device) = device {
+                let _: id = msg_send![device, retain];
+            }
+            let () = msg_send![devices, release];
+            if let Some(device) = device {
+                return device;
+            }
+        }
+    }
+
+    MTLCreateSystemDefaultDevice()
+}
+
+#[link(name = "Metal", kind = "framework")]
+extern "C" {
+    fn MTLCreateSystemDefaultDevice() -> id;
+    fn MTLCopyAllDevices() -> id;
+}
+
+// Display P3 is missing from `core_graphics`.
+#[allow(non_upper_case_globals)]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    static kCGColorSpaceSRGB: *const c_void;
+    static kCGColorSpaceDisplayP3: *const c_void;
+    fn CGColorSpaceCreateWithName(name: *const c_void) -> *mut c_void;
+    fn CGColorSpaceRelease(space: *mut c_void);
+}
+
+struct Program<A: Application> {
+    application: A,
+}
+
+impl<A: Application> Program<A> {
+    fn new(application: A) -> Self {
+        Self { application }
+    }
+}
+
+impl<A: Application> program::Program for Program<A> {
+    type Renderer = Renderer;
+    type Message = A::Message;
+
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        self.application.update(message)
+    }
+
+    /// Application interface.
+    fn view(&mut self) -> NativeElement<'_, Self::Message, Self::Renderer> {
+        self.application.view()
+    }
+}
+
+/// The swap chain of a view, which only exists while the view is in a window.
+///
+/// It's generic over the swap chain, so its transitions can be checked without a device.
+enum Presentation<S = wgpu::SwapChain> {
+    Active(S),
+    /// The view isn't in a window, so it has nothing to present to. The view starts there, as
+    /// the scale factor of its window is only known once it's in one.
+    Suspended,
+}
+
+impl<S> Presentation<S> {
+    fn is_suspended(&self) -> bool {
+        matches!(self, Presentation::Suspended)
+    }
+
+    /// Drops the swap chain, as the view is leaving its window.
+    fn suspend(&mut self) {
+        *self = Presentation::Suspended;
+    }
+
+    /// Creates the swap chain, returning whether it was suspended.
+    fn resume(&mut self, create: impl FnOnce() -> S) -> bool {
+        if !self.is_suspended() {
+            return false;
+        }
+
+        *self = Presentation::Active(create());
+        true
+    }
+
+    /// Creates the swap chain again, e.g. for a new size.
+    fn recreate(&mut self, create: impl FnOnce() -> S) {
+        // The previous one is dropped first, so the layer gives its drawables back.
+        self.suspend();
+        *self = Presentation::Active(create());
+    }
+}
+
+impl Presentation {
+    /// Acquires the next frame, unless it's suspended.
+    fn next_frame(&mut self) -> Option<Result<wgpu::SwapChainOutput, wgpu::TimeOut>> {
+        match self {
+            Self::Active(swap_chain) => Some(swap_chain.get_next_texture()),
+            Self::Suspended => None,
+        }
+    }
+}
+
+/// The size in pixels of a size in points, e.g. of the bounds of the view.
+fn physical_size(points: NSSize, scale_factor: CGFloat) -> Size<u32> {
+    Size::new(
+        (points.width * scale_factor).round() as u32,
+        (points.height * scale_factor).round() as u32,
+    )
+}
+
+/// The resize of the viewport, which is in points like the layout of the widgets.
+fn resized_event(viewport: &Viewport) -> window::Event {
+    let logical_size = viewport.logical_size();
+    window::Event::Resized {
+        width: logical_size.width.round() as u32,
+        height: logical_size.height.round() as u32,
+    }
+}
+
+/// The size the swap chain has been created at, which lags behind the viewport during a live
+/// resize, so the swap chain isn't created again for each frame.
+#[derive(Debug, Clone, Copy)]
+struct ResizeThrottle {
+    size: Size<u32>,
+    created_at: Instant,
+}
+
+impl ResizeThrottle {
+    fn new(size: Size<u32>, now: Instant) -> Self {
+        Self {
+            size,
+            created_at: now,
+        }
+    }
+
+    /// Returns whether the swap chain is to be created again for the viewport of the size, at
+    /// most every `LIVE_RESIZE_INTERVAL` during a live resize.
+    fn should_recreate(
+        &self,
+        size: Size<u32>,
+        is_viewport_changed: bool,
+        is_live: bool,
+        now: Instant,
+    ) -> bool {
+        if !is_viewport_changed && size == self.size {
+            return false;
+        }
+
+        !is_live || now.saturating_duration_since(self.created_at) >= LIVE_RESIZE_INTERVAL
+    }
+
+    fn on_created(&mut self, size: Size<u32>, now: Instant) {
+        self.size = size;
+        self.created_at = now;
+    }
+}
+
+struct EventHandler<A: 'static + Application> {
+    /// The state and the renderer of the application, which draws into the swap chain.
+    iced: headless::HeadlessRenderer<A>,
+    viewport: Viewport,
+    surface: wgpu::Surface,
+    /// The `CAMetalLayer` which the swap chain presents to.
+    layer: id,
+    /// Whether the background of the application is opaque.
+    is_opaque: bool,
+    format: wgpu::TextureFormat,
+    present_mode: wgpu::PresentMode,
+    layer_tuning: Option<LayerTuning>,
+    /// The number of redraws in a row which couldn't acquire a frame.
+    failed_frames: u32,
+    /// Whether the device has been requested again since the last frame, so it's only done
+    /// once for the failed redraws in a row.
+    has_recreated_gpu: bool,
+    always_redraw: bool,
+    debug_key: Option<(keyboard::KeyCode, keyboard::ModifiersState)>,
+    /// Whether the next redraw has to draw a frame, even if the interface hasn't changed.
+    needs_frame: bool,
+    /// The number of redraws skipped as nothing has changed.
+    skipped_frames: u64,
+    /// Whether the view is redrawn at each refresh of the display.
+    is_animating: bool,
+    /// Whether the window is fully covered or minimized, which pauses the drawing.
+    is_occluded: bool,
+    /// Paces the redraws while animating, created when the animation starts first.
+    display_link: Option<display_link::DisplayLink>,
+    /// The shortest time between the animation frames.
+    min_frame_interval: Option<Duration>,
+    /// The timestamps of the last tick of the display link and of the last animation frame.
+    last_tick: Option<Duration>,
+    last_animation_frame: Option<Duration>,
+    resize_throttle: ResizeThrottle,
+    presentation: Presentation,
+    clipboard: Box<dyn clipboard::ClipboardBackend>,
+    /// The change count of the clipboard when it's been checked last time.
+    clipboard_change_count: i64,
+    keys: KeyPipeline,
+    scroll_direction: ScrollDirection,
+    shift_scrolls_horizontally: bool,
+    scroll_sensitivity: f32,
+    scroll_fine_adjust_factor: f32,
+    pressure_stage: NSInteger,
+    accepts_first_mouse: bool,
+    mouse_tracking: MouseTracking,
+    drag_position_policy: DragPositionPolicy,
+    file_drag: FileDrag,
+    is_cursor_hidden: bool,
+    /// Where the cursor has been in the global display coordinates when the pointer has been
+    /// locked.
+    pointer_lock_origin: Option<CGPoint>,
+    /// Where the cursor has been warped to, until the move produced by the warp is dropped.
+    warped_cursor_position: Option<Point>,
+    custom_cursor: id,
+    /// The last left mouse down, which a drag from the view starts with.
+    last_mouse_down: id,
+    /// The operations allowed for the drag from the view.
+    drag_operation_mask: NSUInteger,
+    /// Produces the message when the drag from the view ends.
+    on_drag_end: Option<Box<dyn Fn(drag::DragOutcome) -> A::Message>>,
+    /// The payload of the current internal drag.
+    internal_drag: Option<Box<dyn Any + Send>>,
+    /// Whether the queued messages are handled before the pending events, e.g. the one of an
+    /// internal drop before the release of the button.
+    handles_messages_first: bool,
+    cursor: CursorTracker,
+    recent_events: VecDeque<event::TimedEvent>,
+    is_focused: bool,
+    modifiers: keyboard::ModifiersState,
+    ctrl_click_emulates_right: bool,
+    is_emulating_right_click: bool,
+    coalesce_events: bool,
+    pointer_update_rate: PointerRate,
+    routes_edit_actions: bool,
+    event_mask: EventMask,
+    drop_types: Vec<DropType>,
+    /// The events waiting for the next update, which are coalesced before being queued to the
+    /// state.
+    pending_events: Vec<Event>,
+    /// The directories of the promised files dropped since the last update, which keep them
+    /// until it's done.
+    promised_files_directories: Vec<Arc<PromisedFilesDirectory>>,
+    /// The keys whose presses have been turned into messages, whose releases are dropped too.
+    swallowed_keys: Vec<keyboard::KeyCode>,
+    /// The description of the Touch Bar shown by the view.
+    #[cfg(feature = "touch-bar")]
+    touch_bar: Option<touch_bar::TouchBarSpec<A::Message>>,
+}
+
+impl<A: 'static + Application> EventHandler<A> {
+    fn new(
+        application: A,
+        object: *mut Object,
+        viewport: Viewport,
+        settings: Settings,
+        gpu: gpu::GpuContext,
+    ) -> Self {
+        let (surface, layer) = unsafe {
+            Self::init_surface_layer(object, viewport.scale_factor(), settings.color_space)
+        };
+        let format = settings.texture_format.unwrap_or_default().to_wgpu();
+        let present_mode = settings.present_mode.supported();
+        let always_redraw = settings.always_redraw;
+        let debug_key = settings.debug_key;
+        let min_frame_interval = settings.max_fps.map(min_frame_interval);
+        let swap_chain_size = viewport.physical_size();
+        let layer_tuning = settings.layer_tuning;
+        let iced = headless::HeadlessRenderer::new(application, &viewport, &settings, gpu, format);
+        let scroll_direction = settings.scroll_direction;
+        let shift_scrolls_horizontally = settings.shift_scrolls_horizontally;
+        let scroll_sensitivity = settings.scroll_sensitivity;
+        let scroll_fine_adjust_factor = settings.scroll_fine_adjust_factor;
+        let accepts_first_mouse = settings.accepts_first_mouse;
+        let ctrl_click_emulates_right = settings.ctrl_click_emulates_right;
+        let coalesce_events = settings.coalesce_events;
+        let pointer_update_rate = settings.pointer_update_rate;
+        let ignore_key_repeat = settings.ignore_key_repeat;
+        let keymap_mode = settings.keymap_mode;
+        let routes_edit_actions = settings.routes_edit_actions;
+        let mouse_tracking = settings.mouse_tracking;
+        let drag_position_policy = settings.drag_position_policy;
+        let event_mask = settings.event_mask;
+        let drop_types = settings.accepted_drop_types.clone();
+        let clipboard = settings.clipboard;
+        // The contents at the creation aren't a change.
+        let clipboard_change_count = clipboard.change_count();
+
+        Self {
+            iced,
+            viewport,
+            surface,
+            layer,
+            is_opaque: true,
+            format,
+            present_mode,
+            layer_tuning,
+            failed_frames: 0,
+            has_recreated_gpu: false,
+            always_redraw,
+            debug_key,
+            needs_frame: true,
+            skipped_frames: 0,
+            is_animating: always_redraw,
+            is_occluded: false,
+            display_link: None,
+            min_frame_interval,
+            last_tick: None,
+            last_animation_frame: None,
+            resize_throttle: ResizeThrottle::new(swap_chain_size, Instant::now()),
+            // The swap chain is created once the view is in a window.
+            presentation: Presentation::Suspended,
+            clipboard,
+            clipboard_change_count,
+            keys: KeyPipeline::new(ignore_key_repeat, keymap_mode, PhysicalLayout::current()),
+            scroll_direction,
+            shift_scrolls_horizontally,
+            scroll_sensitivity,
+            scroll_fine_adjust_factor,
+            pressure_stage: 0,
+            accepts_first_mouse,
+            mouse_tracking,
+            drag_position_policy,
+            file_drag: FileDrag::default(),
+            is_cursor_hidden: false,
+            pointer_lock_origin: None,
+            warped_cursor_position: None,
+            custom_cursor: nil,
+            last_mouse_down: nil,
+            drag_operation_mask: drag::DragSession::<A::Message>::new().operation_mask(),
+            on_drag_end: None,
+            internal_drag: None,
+            handles_messages_first: false,
+            cursor: CursorTracker::default(),
+            recent_events: VecDeque::with_capacity(RECENT_EVENTS_CAPACITY),
+            is_focused: false,
+            modifiers: keyboard::ModifiersState::default(),
+            ctrl_click_emulates_right,
+            is_emulating_right_click: false,
+            coalesce_events,
+            pointer_update_rate,
+            routes_edit_actions,
+            event_mask,
+            drop_types,
+            pending_events: Vec::new(),
+            promised_files_directories: Vec::new(),
+            swallowed_keys: Vec::new(),
+            #[cfg(feature = "touch-bar")]
+            touch_bar: None,
+        }
+    }
+
+    unsafe fn init_surface_layer(
+        view: *mut Object,
+        scale: f64,
+        color_space: ColorSpace,
+    ) -> (wgpu::Surface, id) {
+        let class = class!(CAMetalLayer);
+        let layer: *mut Object = msg_send![class, new];
+        let () = msg_send![view, setWantsLayer: YES];
+        let parent: *mut Object = msg_send![view, layer];
+        let () = msg_send![parent, addSublayer: layer];
+        let bounds: CGRect = msg_send![view, bounds];
+        let () = msg_send![layer, setBounds: bounds];
+        let () = msg_send![layer, setContentsScale: scale];
+        let () = msg_send![layer, setAnchorPoint: CGPoint::new(0.0, 0.0)];
+        // kCALayerWidthSizable | kCALayerHeightSizable
+        let autoresizing_mask = 1u64 << 1 | 1 << 4;
+        let () = msg_send![layer, setAutoresizingMask: autoresizing_mask];
+        let () = msg_send![layer, setOpaque: YES];
+        let color_space = color_space.create_cg_color_space();
+        if !color_space.is_null() {
+            let () = msg_send![layer, setColorspace: color_space];
+            CGColorSpaceRelease(color_space);
+        }
+        let _: *mut c_void = msg_send![view, retain];
+
+        let surface = wgpu::Surface::create_surface_from_core_animation_layer(layer as *mut c_void);
+        (surface, layer)
+    }
+
+    fn init_swap_chain(
+        size: &Size<u32>,
+        device: &wgpu::Device,
+        surface: &wgpu::Surface,
+        format: &wgpu::TextureFormat,
+        present_mode: wgpu::PresentMode,
+    ) -> wgpu::SwapChain {
+        device.create_swap_chain(
+            &surface,
+            &wgpu::SwapChainDescriptor {
+                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
+                format: format.clone(),
+                width: size.width,
+                height: size.height,
+                present_mode,
+            },
+        )
+    }
+
+    /// Resizes the viewport, recreating the swap chain at most every `LIVE_RESIZE_INTERVAL`
+    /// during a live resize. The resize at its end recreates it at the exact size.
+    fn resize(&mut self, new_size: Size<u32>, scale_factor: f64, is_live: bool) {
+        let is_viewport_changed = self.set_viewport(new_size, scale_factor);
+        if !self.is_suspended()
+            && self.resize_throttle.should_recreate(
+                new_size,
+                is_viewport_changed,
+                is_live,
+                Instant::now(),
+            )
+        {
+            self.recreate_swap_chain();
+        }
+    }
+
+    /// Changes the viewport, returning whether it's changed.
+    fn set_viewport(&mut self, new_size: Size<u32>, scale_factor: f64) -> bool {
+        let is_changed = new_size != self.viewport.physical_size()
+            || scale_factor != self.viewport.scale_factor();
+        if !is_changed {
+            return false;
+        }
+
+        // The layer would be blurry or too costly at another scale than the viewport.
+        if scale_factor != self.viewport.scale_factor() {
+            unsafe {
+                let () = msg_send![self.layer, setContentsScale: scale_factor];
+            }
+        }
+        self.viewport = Viewport::with_physical_size(new_size, scale_factor);
+        self.on_window_event(resized_event(&self.viewport));
+        true
+    }
+
+    fn recreate_swap_chain(&mut self) {
+        self.create_swap_chain(|presentation, create| {
+            presentation.recreate(create);
+            true
+        });
+    }
+
+    /// Creates the swap chain for the viewport through a transition of the presentation,
+    /// returning whether it's been created.
+    fn create_swap_chain(
+        &mut self,
+        transition: impl FnOnce(&mut Presentation, &mut dyn FnMut() -> wgpu::SwapChain) -> bool,
+    ) -> bool {
+        let size = self.viewport.physical_size();
+        let iced = &self.iced;
+        let surface = &self.surface;
+        let format = &self.format;
+        let present_mode = self.present_mode;
+        let mut create = || {
+            Self::init_swap_chain(
+                &size,
+                &iced.gpu_context().device(),
+                surface,
+                format,
+                present_mode,
+            )
+        };
+        if !transition(&mut self.presentation, &mut create) {
+            return false;
+        }
+
+        // The new swap chain has nothing to show yet.
+        self.needs_frame = true;
+        self.resize_throttle.on_created(size, Instant::now());
+        if let Some(layer_tuning) = self.layer_tuning {
+            layer_tuning.apply(self.layer, self.present_mode);
+        }
+        true
+    }
+
+    fn set_layer_tuning(&mut self, layer_tuning: Option<LayerTuning>) {
+        self.layer_tuning = layer_tuning;
+        // The configuration of wgpu is restored by creating the swap chain again.
+        if !self.is_suspended() {
+            self.recreate_swap_chain();
+        }
+    }
+
+    fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.present_mode = present_mode.supported();
+        // The swap chain is created with it when the view is in a window again.
+        if !self.is_suspended() {
+            self.recreate_swap_chain();
+        }
+    }
+
+    fn is_suspended(&self) -> bool {
+        self.presentation.is_suspended()
+    }
+
+    /// Drops the swap chain and stops the display link, as the view is leaving its window.
+    fn suspend(&mut self) {
+        self.presentation.suspend();
+        if let Some(display_link) = &mut self.display_link {
+            display_link.set_running(false);
+        }
+    }
+
+    /// Creates the swap chain for the size and the scale factor of the new window, returning
+    /// whether the view is to be redrawn.
+    fn resume(&mut self, new_size: Size<u32>, scale_factor: f64) -> bool {
+        if !self.is_suspended() {
+            return false;
+        }
+
+        // The size and the scale factor of the window are only known by now.
+        self.set_viewport(new_size, scale_factor);
+        self.create_swap_chain(|presentation, create| presentation.resume(create))
+    }
+
+    fn on_window_event(&mut self, event: window::Event) {
+        self.queue_event(vec![Event::Window(event)]);
+    }
+
+    fn on_file_drag_entered(
+        &mut self,
+        position: Point,
+        paths: Vec<PathBuf>,
+    ) -> event::DragOperation {
+        self.file_drag.enter(paths);
+        let operation = self.drag_operation(position);
+        if operation != event::DragOperation::None {
+            self.accept_file_drag(position);
+        }
+        operation
+    }
+
+    /// Asks the application again, as the answer may depend on the position.
+    fn on_file_drag_updated(&mut self, position: Point) -> event::DragOperation {
+        let operation = self.drag_operation(position);
+        match (
+            operation != event::DragOperation::None,
+            self.file_drag.is_accepted,
+        ) {
+            (true, true) => self.on_file_drag_moved(position),
+            (true, false) => self.accept_file_drag(position),
+            (false, true) => self.reject_file_drag(),
+            (false, false) => (),
+        }
+        operation
+    }
+
+    fn drag_operation(&self, position: Point) -> event::DragOperation {
+        self.iced
+            .application()
+            .drag_operation(&self.file_drag.paths, position)
+    }
+
+    /// Reports the drag, which is hidden from the application until it accepts it.
+    fn accept_file_drag(&mut self, position: Point) {
+        let events = self.file_drag.accept(position);
+        self.update_drag_modifiers();
+        self.queue_event(events);
+    }
+
+    fn reject_file_drag(&mut self) {
+        let events = self.file_drag.reject();
+        self.clear_drag_modifiers();
+        self.queue_event(events);
+    }
+
+    fn on_file_drag_moved(&mut self, position: Point) {
+        self.update_drag_modifiers();
+        let events = self.file_drag.move_to(position);
+        self.queue_event(events);
+    }
+
+    fn on_file_drag_exited(&mut self) {
+        if self.file_drag.is_accepted {
+            self.reject_file_drag();
+        }
+    }
+
+    /// Drops the paths and the other things read from the pasteboard.
+    fn on_file_drop(
+        &mut self,
+        position: Point,
+        paths: Vec<PathBuf>,
+        drops: Vec<event::DragDropEvent>,
+    ) {
+        let flags = unsafe { current_modifier_flags() };
+        let modifiers_event = self.file_drag.drop_with(flags);
+        self.on_drag_modifiers_changed(modifiers_event);
+        paths
+            .into_iter()
+            .for_each(|path| self.on_file_dropped(path, position));
+        drops
+            .into_iter()
+            .for_each(|dropped| self.on_view_event(event::Event::DragDrop(dropped)));
+    }
+
+    /// Forgets the drag, whether it's been dropped or not.
+    fn on_file_drag_ended(&mut self, position: Point, is_inside: bool) {
+        self.clear_drag_modifiers();
+        let events = self.file_drag.end(position, is_inside);
+        self.queue_event(events);
+    }
+
+    fn set_last_mouse_down(&mut self, event: id) {
+        unsafe {
+            if event != nil {
+                let _: id = msg_send![event, retain];
+            }
+            if self.last_mouse_down != nil {
+                let () = msg_send![self.last_mouse_down, release];
+            }
+        }
+        self.last_mouse_down = event;
+    }
+
+    fn on_drag_source_ended(&mut self, outcome: drag::DragOutcome) {
+        // The session takes the mouse up, so the widgets would see the button held forever.
+        self.queue_event(vec![Event::Mouse(mouse::Event::ButtonReleased(
+            mouse::Button::Left,
+        ))]);
+        if let Some(on_end) = self.on_drag_end.take() {
+            self.iced.queue_message(on_end(outcome));
+        }
+    }
+
+    fn on_file_dropped(&mut self, path: PathBuf, position: Point) {
+        self.on_window_event(window::Event::FileDropped(path.clone()));
+        self.on_view_event(event::Event::DragDrop(
+            event::DragDropEvent::FileDroppedAt { path, position },
+        ));
+    }
+
+    /// Takes the modifiers held during the drag, as the dragging info doesn't tell them.
+    fn update_drag_modifiers(&mut self) {
+        let flags = unsafe { current_modifier_flags() };
+        let modifiers_event = self.file_drag.set_modifiers(Some(flags));
+        self.on_drag_modifiers_changed(modifiers_event);
+    }
+
+    fn clear_drag_modifiers(&mut self) {
+        let modifiers_event = self.file_drag.set_modifiers(None);
+        self.on_drag_modifiers_changed(modifiers_event);
+    }
+
+    fn on_drag_modifiers_changed(&mut self, modifiers_event: Option<event::Event>) {
+        if let Some(modifiers_event) = modifiers_event {
+            self.on_view_event(modifiers_event);
+        }
+    }
+
+    fn queue_event(&mut self, events: Vec<Event>) {
+        events.into_iter().for_each(|e| {
+            self.cursor.on_event(&e);
+            queue_pending_event(
+                &mut self.pending_events,
+                e,
+                self.coalesce_events,
+                self.pointer_update_rate,
+            );
+        });
+    }
+
+    fn track_internal_drag(&mut self, event: &NSEventT<id>) {
+        let event_type = unsafe { NSEvent::eventType(event.raw_event) };
+        let drag_event = match internal_drag_event(event_type, || unsafe { event.location() }) {
+            Some(drag_event) => drag_event,
+            None => return,
+        };
+
+        self.on_view_event(drag_event.clone());
+        if let event::Event::InternalDragDropped { position } = drag_event {
+            if let Some(payload) = self.internal_drag.take() {
+                if let Some(message) = self.iced.application().on_internal_drop(payload, position) {
+                    // The drop is handled before the release, which the widgets see after it.
+                    self.iced.queue_message(message);
+                    self.handles_messages_first = true;
+                }
+            }
+        }
+    }
+
+    fn set_marked_text(&mut self, text: String) {
+        if self.keys.set_marked_text(&text) {
+            self.on_view_event(event::Event::MarkedText(text));
+        }
+    }
+
+    /// Returns whether the left click is treated as a right click, as Control is held.
+    unsafe fn is_right_click_emulated(&self, event: id) -> bool {
+        self.ctrl_click_emulates_right
+            && NSEvent::modifierFlags(event).contains(NSEventModifierFlags::NSControlKeyMask)
+    }
+
+    fn begin_pointer_lock(&mut self) {
+        if self.pointer_lock_origin.is_some() {
+            return;
+        }
+
+        let origin = CGEventSource::new(CGEventSourceStateID::CombinedSessionState)
+            .and_then(CGEvent::new)
+            .map(|event| event.location());
+        if let Ok(origin) = origin {
+            self.pointer_lock_origin = Some(origin);
+            let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(false);
+            unsafe {
+                let () = msg_send![class!(NSCursor), hide];
+            }
+        }
+    }
+
+    fn end_pointer_lock(&mut self) {
+        if let Some(origin) = self.pointer_lock_origin.take() {
+            let _ = CGDisplay::warp_mouse_cursor_position(origin);
+            let _ = CGDisplay::associate_mouse_and_mouse_cursor_position(true);
+            unsafe {
+                let () = msg_send![class!(NSCursor), unhide];
+            }
+        }
+    }
+
+    fn set_focused(&mut self, is_focused: bool) {
+        if is_focused != self.is_focused {
+            self.is_focused = is_focused;
+            self.on_view_event(event::Event::FocusChanged(is_focused));
+        }
+
+        // The modifiers may have changed while another view was focused.
+        if is_focused {
+            self.update_modifiers(unsafe { current_modifier_flags() });
+        }
+    }
+
+    fn update_modifiers(&mut self, flags: NSEventModifierFlags) {
+        let modifiers = keyboard::ModifiersState::from(ModifierFlags(flags));
+        if modifiers != self.modifiers {
+            self.modifiers = modifiers;
+            self.on_view_event(event::Event::Modifiers(modifiers));
+        }
+    }
+
+    /// Delivers `ClipboardChanged` if the clipboard has changed since the last check, returning
+    /// whether it has.
+    fn check_clipboard(&mut self) -> bool {
+        let change_count = self.clipboard.change_count();
+        if change_count == self.clipboard_change_count {
+            return false;
+        }
+
+        self.clipboard_change_count = change_count;
+        self.on_view_event(event::Event::ClipboardChanged(
+            self.clipboard.available_types(),
+        ));
+        true
+    }
+
+    /// Returns whether the Edit action applies to the focused text input.
+    fn can_perform_edit_action(&self, action: EditAction) -> bool {
+        let application = self.iced.application();
+        if !self.routes_edit_actions || application.input_method_area().is_none() {
+            return false;
+        }
+
+        match action {
+            EditAction::Copy | EditAction::Cut => application.selected_text().is_some(),
+            EditAction::Paste | EditAction::SelectAll => true,
+        }
+    }
+
+    /// Performs the Edit action on the focused text input, returning whether it's been
+    /// performed.
+    fn perform_edit_action(&mut self, action: EditAction) -> bool {
+        if !self.can_perform_edit_action(action) {
+            return false;
+        }
+
+        match action {
+            EditAction::Copy | EditAction::Cut => {
+                if let Some(text) = self.iced.application().selected_text() {
+                    self.clipboard.write(&text);
+                    // Erasing the selection cuts it.
+                    if action == EditAction::Cut {
+                        self.queue_event(backspace_events(1));
+                    }
+                }
+            }
+            EditAction::Paste => {
+                if let Some(text) = self.clipboard.read() {
+                    self.queue_event(text_events(&text));
+                }
+            }
+            EditAction::SelectAll => self.queue_event(key_stroke_events(
+                keyboard::KeyCode::A,
+                keyboard::ModifiersState {
+                    logo: true,
+                    ..Default::default()
+                },
+            )),
+        }
+        true
+    }
+
+    /// Toggles the debug overlay with the presses of the debug key, returning the other events.
+    fn resolve_debug_key(&mut self, events: Vec<Event>) -> Vec<Event> {
+        let (debug_key_code, debug_modifiers) = match self.debug_key {
+            Some(debug_key) => debug_key,
+            None => return events,
+        };
+
+        let mut presses = 0;
+        let events = events
+            .into_iter()
+            .filter(|e| match e {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }) if *key_code == debug_key_code && *modifiers == debug_modifiers => {
+                    presses += 1;
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+
+        if presses > 0 {
+            self.swallow_key(debug_key_code);
+        }
+        if presses % 2 == 1 {
+            let is_shown = self.iced.is_debug_overlay_shown();
+            self.set_debug_overlay(!is_shown);
+        }
+        events
+    }
+
+    fn set_debug_overlay(&mut self, is_shown: bool) {
+        self.iced.set_debug_overlay(is_shown);
+        // The overlay isn't a part of the appearance the redraws are skipped by.
+        self.needs_frame = true;
+    }
+
+    /// Turns the key presses matching the shortcuts of the application into their messages,
+    /// returning the other events.
+    fn resolve_shortcuts(&mut self, events: Vec<Event>) -> Vec<Event> {
+        let application = self.iced.application();
+        let shortcuts = application.shortcuts();
+        if shortcuts.is_empty() {
+            return events;
+        }
+
+        // The application tells where the focused text input is, which is how it's known.
+        let is_text_focused = application.input_method_area().is_some();
+        let mut messages = Vec::new();
+        let mut swallowed_keys = Vec::new();
+        let events = events
+            .into_iter()
+            .filter(|e| match e {
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }) => match shortcuts.resolve(*key_code, *modifiers, is_text_focused) {
+                    Some(message) => {
+                        messages.push(message.clone());
+                        swallowed_keys.push(*key_code);
+                        false
+                    }
+                    None => true,
+                },
+                _ => true,
+            })
+            .collect();
+
+        messages
+            .into_iter()
+            .for_each(|message| self.iced.queue_message(message));
+        swallowed_keys
+            .into_iter()
+            .for_each(|key_code| self.swallow_key(key_code));
+        events
+    }
+
+    /// Drops the next release of the key, whose press has been turned into something else.
+    fn swallow_key(&mut self, key_code: keyboard::KeyCode) {
+        // The repeats of the key are released at once.
+        if !self.swallowed_keys.contains(&key_code) {
+            self.swallowed_keys.push(key_code);
+        }
+    }
+
+    /// Returns whether the key press toggles the debug overlay or is turned into the message of
+    /// a shortcut, so it doesn't type any text.
+    fn is_shortcut(
+        &self,
+        key_code: keyboard::KeyCode,
+        modifiers: keyboard::ModifiersState,
+    ) -> bool {
+        if self.debug_key == Some((key_code, modifiers)) {
+            return true;
+        }
+
+        let application = self.iced.application();
+        let is_text_focused = application.input_method_area().is_some();
+        application
+            .shortcuts()
+            .resolve(key_code, modifiers, is_text_focused)
+            .is_some()
+    }
+
+    /// Drops the releases of the keys whose presses have been swallowed.
+    fn drop_swallowed_releases(&mut self, events: Vec<Event>) -> Vec<Event> {
+        if self.swallowed_keys.is_empty() {
+            return events;
+        }
+
+        let swallowed_keys = &mut self.swallowed_keys;
+        events
+            .into_iter()
+            .filter(|e| match e {
+                Event::Keyboard(keyboard::Event::KeyReleased { key_code, .. }) => {
+                    match swallowed_keys
+                        .iter()
+                        .position(|swallowed| swallowed == key_code)
+                    {
+                        Some(index) => {
+                            swallowed_keys.swap_remove(index);
+                            false
+                        }
+                        None => true,
+                    }
+                }
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Runs or pauses the display link, depending on whether the view is animating and visible.
+    fn update_display_link(&mut self, view: id) {
+        let is_visible = unsafe {
+            let window: id = msg_send![view, window];
+            let is_hidden: BOOL = msg_send![view, isHiddenOrHasHiddenAncestor];
+            window != nil && is_hidden == NO
+        };
+        let is_running = self.is_animating && is_visible && !self.is_occluded;
+        if is_running && self.display_link.is_none() {
+            self.display_link = display_link::DisplayLink::new(view);
+        }
+        if let Some(display_link) = &mut self.display_link {
+            display_link.set_running(is_running);
+        }
+    }
+
+    /// Pauses or resumes the drawing, returning whether the view is to be redrawn to deliver
+    /// the change.
+    fn set_occluded(&mut self, is_occluded: bool) -> bool {
+        if is_occluded == self.is_occluded {
+            return false;
+        }
+
+        self.is_occluded = is_occluded;
+        // The frame shown before may be stale.
+        if !is_occluded {
+            self.needs_frame = true;
+        }
+        self.on_view_event(event::Event::Occluded(is_occluded));
+        true
+    }
+
+    /// Delivers the tick of the display link, returning whether the view is to be redrawn.
+    fn on_animation_frame(&mut self, timestamp: Duration) -> bool {
+        // The ticks sent before the animation has stopped are dropped.
+        if !self.is_animating {
+            self.last_tick = None;
+            self.last_animation_frame = None;
+            return false;
+        }
+
+        // The refresh period is measured, as it changes with the displays and ProMotion.
+        let refresh_period = self
+            .last_tick
+            .and_then(|last_tick| timestamp.checked_sub(last_tick))
+            .unwrap_or_default();
+        self.last_tick = Some(timestamp);
+        if let (Some(min_frame_interval), Some(last_animation_frame)) =
+            (self.min_frame_interval, self.last_animation_frame)
+        {
+            // The tick closest to the due time is taken, so the rate doesn't drift below the
+            // cap.
+            let due = last_animation_frame + min_frame_interval;
+            if timestamp + refresh_period / 2 < due {
+                return false;
+            }
+        }
+        self.last_animation_frame = Some(timestamp);
+
+        self.needs_frame = true;
+        self.on_view_event(event::Event::AnimationFrame { timestamp });
+        true
+    }
+
+    fn on_view_event(&mut self, event: event::Event) {
+        if let Some(message) = self.iced.application().on_event(event) {
+            self.iced.queue_message(message);
+        }
+    }
+
+    fn on_native_event(&mut self, event: NSEventT<id>) {
+        self.update_modifiers(unsafe { NSEvent::modifierFlags(event.raw_event) });
+
+        if let Some(tablet_event) = unsafe { event.as_tablet_event() } {
+            self.on_view_event(event::Event::Tablet(tablet_event));
+        }
+
+        if self.internal_drag.is_some() {
+            self.track_internal_drag(&event);
+        }
+
+        let events = unsafe {
+            match NSEvent::eventType(event.raw_event) {
+                NSEventType::NSFlagsChanged => {
+                    let extra_modifiers = self.keys.extra_modifiers();
+                    let events = self.keys.flags_changed(
+                        NSEvent::keyCode(event.raw_event),
+                        NSEvent::modifierFlags(event.raw_event),
+                    );
+
+                    let is_function_pressed = self.keys.extra_modifiers().function;
+                    if self.keys.extra_modifiers() != extra_modifiers {
+                        self.on_view_event(event::Event::ExtraModifiers(
+                            self.keys.extra_modifiers(),
+                        ));
+                    }
+                    if is_function_pressed != extra_modifiers.function {
+                        self.on_view_event(event::Event::FunctionKey {
+                            is_pressed: is_function_pressed,
+                        });
+                    }
+
+                    events
+                }
+                NSEventType::NSScrollWheel => {
+                    let momentum_phase = NSEvent::momentumPhase(event.raw_event);
+                    if let Some(phase) = event::ScrollPhase::from_phases(
+                        NSEvent::phase(event.raw_event),
+                        momentum_phase,
+                    ) {
+                        self.on_view_event(event::Event::ScrollPhase(phase));
+                    }
+
+                    if momentum_phase != NSEventPhase::NSEventPhaseNone
+                        && self.iced.application().should_cancel_scroll_momentum()
+                    {
+                        return;
+                    }
+
+                    event.as_wheel_scrolled(
+                        self.scroll_direction,
+                        self.shift_scrolls_horizontally,
+                        self.scroll_sensitivity,
+                        self.scroll_fine_adjust_factor,
+                    )
+                }
+                NSEventType::NSKeyDown => {
+                    let events = self.keys.key_down(
+                        NSEvent::keyCode(event.raw_event),
+                        &characters_ignoring_modifiers(event.raw_event),
+                        NSEvent::modifierFlags(event.raw_event),
+                        NSEvent::isARepeat(event.raw_event) == YES,
+                    );
+                    let events = self.drop_swallowed_releases(events);
+                    let events = self.resolve_debug_key(events);
+                    self.resolve_shortcuts(events)
+                }
+                NSEventType::NSKeyUp => {
+                    let events = self.keys.key_up(
+                        NSEvent::keyCode(event.raw_event),
+                        NSEvent::modifierFlags(event.raw_event),
+                    );
+                    self.drop_swallowed_releases(events)
+                }
+                NSEventType::NSEventTypeMagnify => {
+                    self.on_view_event(event::Event::Gesture(event::GestureEvent::Magnify {
+                        delta: NSEvent::magnification(event.raw_event) as f32,
+                        position: event.location(),
+                        phase: event::Phase::from(NSEvent::phase(event.raw_event)),
+                    }));
+                    return;
+                }
+                NSEventType::NSEventTypeRotate => {
+                    self.on_view_event(event::Event::Gesture(event::GestureEvent::Rotate {
+                        delta: NSEvent::rotation(event.raw_event),
+                        position: event.location(),
+                        phase: event::Phase::from(NSEvent::phase(event.raw_event)),
+                    }));
+                    return;
+                }
+                NSEventType::NSEventTypePressure => {
+                    let stage = NSEvent::stage(event.raw_event);
+                    let position = event.location();
+                    if stage != self.pressure_stage {
+                        self.pressure_stage = stage;
+                        self.on_view_event(event::Event::Pressure(
+                            event::PressureEvent::StageChanged {
+                                stage: stage as u8,
+                                position,
+                            },
+                        ));
+                    }
+                    self.on_view_event(event::Event::Pressure(event::PressureEvent::Changed {
+                        stage: stage as u8,
+                        pressure: NSEvent::pressure(event.raw_event),
+                        position,
+                    }));
+                    return;
+                }
+                NSEventType::NSMouseMoved
+                | NSEventType::NSLeftMouseDragged
+                | NSEventType::NSRightMouseDragged
+                | NSEventType::NSOtherMouseDragged
+                    if self.pointer_lock_origin.is_some() =>
+                {
+                    self.on_view_event(event::Event::Mouse(event::MouseEvent::RelativeMotion {
+                        dx: NSEvent::deltaX(event.raw_event) as f32,
+                        dy: NSEvent::deltaY(event.raw_event) as f32,
+                    }));
+                    return;
+                }
+                // The widgets already know where the cursor has been warped to.
+                NSEventType::NSMouseMoved
+                    if self
+                        .warped_cursor_position
+                        .take()
+                        .map(|warped| is_warp_move(warped, event.location()))
+                        .unwrap_or(false) =>
+                {
+                    return;
+                }
+                NSEventType::NSLeftMouseDragged
+                | NSEventType::NSRightMouseDragged
+                | NSEventType::NSOtherMouseDragged => {
+                    let position = event.location();
+                    self.apply_drag_position_policy(vec![Event::Mouse(mouse::Event::CursorMoved {
+                        x: position.x,
+                        y: position.y,
+                    })])
+                }
+                NSEventType::NSLeftMouseDown if self.is_right_click_emulated(event.raw_event) => {
+                    self.is_emulating_right_click = true;
+                    let position = event.location();
+                    self.apply_drag_position_policy(vec![
+                        Event::Mouse(mouse::Event::CursorMoved {
+                            x: position.x,
+                            y: position.y,
+                        }),
+                        Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)),
+                    ])
+                }
+                // Control can be released before the button, so the release is paired with the
+                // press rather than checking the modifiers again.
+                NSEventType::NSLeftMouseUp
+                    if std::mem::take(&mut self.is_emulating_right_click) =>
+                {
+                    let position = event.location();
+                    self.apply_drag_position_policy(vec![
+                        Event::Mouse(mouse::Event::CursorMoved {
+                            x: position.x,
+                            y: position.y,
+                        }),
+                        Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Right)),
+                    ])
+                }
+                // The release ends the drag, so the cursor is reported where the drag left it.
+                NSEventType::NSLeftMouseUp
+                | NSEventType::NSRightMouseUp
+                | NSEventType::NSOtherMouseUp => self.apply_drag_position_policy(event.into()),
+                NSEventType::NSSystemDefined => {
+                    if let Some(media_key_event) = event.as_media_key() {
+                        self.on_view_event(event::Event::MediaKey(media_key_event));
+                    }
+                    return;
+                }
+                NSEventType::NSEventTypeSwipe => {
+                    let modifiers = keyboard::ModifiersState::from(ModifierFlags(
+                        NSEvent::modifierFlags(event.raw_event),
+                    ));
+                    event.swipe_directions().into_iter().for_each(|direction| {
+                        self.on_view_event(event::Event::Gesture(event::GestureEvent::Swipe {
+                            direction,
+                            modifiers,
+                        }))
+                    });
+                    return;
+                }
+                _ => event.into(),
+            }
+        };
+
+        let time = unsafe { event.time() };
+        events.iter().for_each(|e| {
+            if self.recent_events.len() == RECENT_EVENTS_CAPACITY {
+                self.recent_events.pop_front();
+            }
+            self.recent_events.push_back(event::TimedEvent {
+                time,
+                event: e.clone(),
+            });
+        });
+        self.queue_event(events);
+
+        // It goes after the normal press so the widgets keep working as usual.
+        if let Some(multi_click) = unsafe { event.as_multi_click() } {
+            self.on_view_event(event::Event::Mouse(multi_click));
+        }
+    }
+
+    /// Reports the cursor positions of the events, produced while a mouse button is held, the
+    /// way the drag position policy says.
+    fn apply_drag_position_policy(&self, events: Vec<Event>) -> Vec<Event> {
+        let size = self.viewport.logical_size();
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::Mouse(mouse::Event::CursorMoved { x, y }) => {
+                    let position = self.drag_position_policy.apply(Point::new(x, y), size);
+                    Event::Mouse(mouse::Event::CursorMoved {
+                        x: position.x,
+                        y: position.y,
+                    })
+                }
+                event => event,
+            })
+            .collect()
+    }
+
+    /// Draws a frame, returning whether another redraw is needed as the frame couldn't be
+    /// acquired.
+    fn redraw(&mut self) -> bool {
+        self.check_clipboard();
+        if self.update_state() {
+            self.needs_frame = true;
+        }
+        // The layer keeps showing the last frame.
+        if self.is_occluded || !self.needs_frame && !self.always_redraw {
+            self.skipped_frames = self.skipped_frames.saturating_add(1);
+            return false;
+        }
+
+        let frame = match self.presentation.next_frame() {
+            Some(Ok(frame)) => frame,
+            // The swap chain may be stale after a quick resize or a change of the displays, so
+            // it's recreated and asked once more.
+            Some(Err(_)) => {
+                self.recreate_swap_chain();
+                match self.presentation.next_frame() {
+                    Some(Ok(frame)) => frame,
+                    _ => return self.on_frame_failed(),
+                }
+            }
+            // Nothing is shown while the view isn't in a window.
+            None => return false,
+        };
+        self.failed_frames = 0;
+        self.has_recreated_gpu = false;
+
+        let background_color = self.iced.application().background_color();
+        self.set_opaque(background_color.a >= 1.0);
+
+        let mut encoder = self
+            .iced
+            .gpu_context()
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.iced.clear(&mut encoder, &frame.view);
+        let mouse_interaction = self
+            .iced
+            .render_to(&mut encoder, &frame.view, &self.viewport);
+        self.iced.gpu_context().queue().submit(&[encoder.finish()]);
+        self.needs_frame = false;
+
+        if let Some(mouse_interaction) = self.cursor.update(mouse_interaction) {
+            self.set_cursor_icon(mouse_interaction);
+        }
+        false
+    }
+
+    fn capture_frame(&mut self) -> Result<capture::RgbaImage, capture::CaptureError> {
+        // The pending events are handled first, so the capture shows what the next frame will.
+        if self.update_state() {
+            self.needs_frame = true;
+        }
+        self.iced.capture(&self.viewport)
+    }
+
+    /// Counts the redraws without a frame, returning whether to try again.
+    ///
+    /// After `MAX_FAILED_FRAMES` of them, the device is requested again once, as it may have
+    /// been lost, e.g. with an external GPU unplugged. Then the error is reported, and one more
+    /// redraw lets the application handle its message.
+    fn on_frame_failed(&mut self) -> bool {
+        self.failed_frames = self.failed_frames.saturating_add(1);
+        if self.failed_frames == MAX_FAILED_FRAMES && !self.has_recreated_gpu {
+            self.has_recreated_gpu = true;
+            if self.recreate_gpu() {
+                self.failed_frames = 0;
+                return true;
+            }
+        }
+        if self.failed_frames == MAX_FAILED_FRAMES {
+            if let Some(message) = self
+                .iced
+                .application()
+                .on_render_error(RenderError::FrameUnavailable)
+            {
+                self.iced.queue_message(message);
+            }
+        }
+        self.failed_frames <= MAX_FAILED_FRAMES
+    }
+
+    /// Replaces the device with a new one, along with the renderer and the swap chain, returning
+    /// whether it's been done.
+    ///
+    /// The device of the host isn't replaced, as only the host can request it.
+    fn recreate_gpu(&mut self) -> bool {
+        let gpu = match self.iced.gpu_context().recreate() {
+            Some(Ok(gpu)) => gpu,
+            Some(Err(error)) => {
+                log::error!("The device couldn't be requested again: {}", error);
+                return false;
+            }
+            None => return false,
+        };
+
+        log::warn!("The device has been requested again, as no frame could be acquired.");
+        self.iced.set_gpu_context(gpu);
+        if !self.is_suspended() {
+            self.recreate_swap_chain();
+        }
+        self.on_view_event(event::Event::GpuRecreated);
+        true
+    }
+
+    /// Returns whether an event or a message waits for the next update, or a frame for the next
+    /// redraw.
+    fn has_pending_update(&self) -> bool {
+        !self.pending_events.is_empty() || !self.iced.is_queue_empty() || self.needs_frame
+    }
+
+    /// Updates the state with the pending events, returning whether the interface looks
+    /// different.
+    fn update_state(&mut self) -> bool {
+        let mut is_changed = false;
+        // The widgets produce their messages before the queued ones are handled.
+        if std::mem::take(&mut self.handles_messages_first) {
+            is_changed |= self.iced.update(&self.viewport, Some(&*self.clipboard));
+        }
+
+        let iced = &mut self.iced;
+        self.pending_events
+            .drain(..)
+            .for_each(|e| iced.queue_event(e));
+
+        is_changed |= self.iced.update(&self.viewport, Some(&*self.clipboard));
+        self.promised_files_directories.clear();
+        is_changed
+    }
+
+    /// Lets the content behind the view show through while the background is translucent.
+    fn set_opaque(&mut self, is_opaque: bool) {
+        if is_opaque == self.is_opaque {
+            return;
+        }
+
+        self.is_opaque = is_opaque;
+        let value = if is_opaque { YES } else { NO };
+        unsafe {
+            let () = msg_send![self.layer, setOpaque: value];
+            // The layer of the view follows `isOpaque` only when it's created.
+            let superlayer: id = msg_send![self.layer, superlayer];
+            let () = msg_send![superlayer, setOpaque: value];
+        }
+    }
+
+    fn set_cursor_hidden(&mut self, is_hidden: bool) {
+        if is_hidden == self.is_cursor_hidden {
+            return;
+        }
+
+        self.is_cursor_hidden = is_hidden;
+        unsafe {
+            if is_hidden {
+                let () = msg_send![class!(NSCursor), hide];
+            } else {
+                let () = msg_send![class!(NSCursor), unhide];
+            }
+        }
+    }
+
+    fn set_custom_cursor(&mut self, rgba: &[u8], size: Size<u32>, hotspot: Point) {
+        self.clear_custom_cursor();
+
+        unsafe {
+            let image = match image_from_rgba(rgba, size) {
+                Some(image) => image,
+                None => return,
+            };
+            let alloc: id = msg_send![class!(NSCursor), alloc];
+            self.custom_cursor = msg_send![
+                alloc,
+                initWithImage: image
+                hotSpot: NSPoint::new(hotspot.x.into(), hotspot.y.into())
+            ];
+            let () = msg_send![self.custom_cursor, set];
+            self.cursor.invalidate();
+
+            let () = msg_send![image, release];
+        }
+    }
+
+    fn clear_custom_cursor(&mut self) {
+        if self.custom_cursor != nil {
+            unsafe {
+                let () = msg_send![self.custom_cursor, release];
+            }
+            self.custom_cursor = nil;
+            self.cursor.invalidate();
+        }
+    }
+
+    fn apply_current_cursor(&mut self) {
+        if let Some(interaction) = self.cursor.current() {
+            self.cursor.applied = Some(interaction);
+            self.set_cursor_icon(interaction);
+        }
+    }
+
+    fn set_cursor_icon(&self, cursor: mouse::Interaction) {
+        unsafe {
+            let () = msg_send![self.ns_cursor(cursor), set];
+        }
+    }
+
+    fn ns_cursor(&self, cursor: mouse::Interaction) -> id {
+        // The custom cursor takes precedence over the cursors of the widgets.
+        if self.custom_cursor != nil {
+            return self.custom_cursor;
+        }
+
+        unsafe {
+            let class = class!(NSCursor);
+            match cursor {
+                mouse::Interaction::Idle => msg_send![class, arrowCursor],
+                mouse::Interaction::Pointer => msg_send![class, pointingHandCursor],
+                mouse::Interaction::Grab => msg_send![class, openHandCursor],
+                mouse::Interaction::Text => msg_send![class, IBeamCursor],
+                mouse::Interaction::Crosshair => msg_send![class, crosshairCursor],
+                mouse::Interaction::Working => msg_send![class, arrowCursor],
+                mouse::Interaction::Grabbing => msg_send![class, closedHandCursor],
+                mouse::Interaction::ResizingHorizontally => msg_send![class, resizeLeftRightCursor],
+                mouse::Interaction::ResizingVertically => msg_send![class, resizeUpDownCursor],
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct NSEventT<T: NSEvent + Copy> {
+    raw_event: T,
+    view: *mut Object,
+}
+
+impl<T: NSEvent + Copy> From<NSEventT<T>> for Vec<Event> {
+    fn from(event: NSEventT<T>) -> Self {
+        unsafe {
+            let location = event.location();
+            let moved = Event::Mouse(mouse::Event::CursorMoved {
+                x: location.x,
+                y: location.y,
+            });
+            let button_num = NSEvent::buttonNumber(event.raw_event);
+
+            match NSEvent::eventType(event.raw_event) {
+                // The buttons can be pressed without any preceding moves, e.g. when the click
+                // activates the window or the view has been reparented, so the cursor position
+                // is updated first.
+                NSEventType::NSLeftMouseDown => vec![
+                    moved,
+                    Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)),
+                ],
+                NSEventType::NSLeftMouseUp => vec![
+                    moved,
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)),
+                ],
+                NSEventType::NSRightMouseDown => vec![
+                    moved,
+                    Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)),
+                ],
+                NSEventType::NSRightMouseUp => vec![
+                    moved,
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Right)),
+                ],
+                NSEventType::NSMouseMoved => vec![moved],
+                NSEventType::NSMouseEntered => vec![Event::Mouse(mouse::Event::CursorEntered)],
+                NSEventType::NSMouseExited => vec![Event::Mouse(mouse::Event::CursorLeft)],
+                NSEventType::NSOtherMouseDown => vec![
+                    moved,
+                    Event::Mouse(mouse::Event::ButtonPressed(ButtonNumber(button_num).into())),
+                ],
+                NSEventType::NSOtherMouseUp => vec![
+                    moved,
+                    Event::Mouse(mouse::Event::ButtonReleased(
+                        ButtonNumber(button_num).into(),
+                    )),
+                ],
+                _ => vec![],
+            }
+        }
+    }
+}
+
+impl<T: NSEvent + Copy> NSEventT<T> {
+    /// The time of the event.
+    unsafe fn time(&self) -> Instant {
+        let process_info: id = msg_send![class!(NSProcessInfo), processInfo];
+        let uptime: NSTimeInterval = msg_send![process_info, systemUptime];
+        instant_from_timestamp(NSEvent::timestamp(self.raw_event), Instant::now(), uptime)
+    }
+
+    /// The location of the event in the view coordinates.
+    unsafe fn location(&self) -> Point {
+        let location_in_window: NSPoint = NSEvent::locationInWindow(self.raw_event);
+        let location = NSView::convertPoint_fromView_(self.view, location_in_window, nil);
+        Point::new(location.x as f32, location.y as f32)
+    }
+}
+
+impl NSEventT<id> {
+    // NSTabletPointEventSubtype
+    const TABLET_POINT_SUBTYPE: i16 = 1;
+    // NSTabletProximityEventSubtype
+    const TABLET_PROXIMITY_SUBTYPE: i16 = 2;
+
+    // NX_SUBTYPE_AUX_CONTROL_BUTTONS
+    const AUX_CONTROL_BUTTONS_SUBTYPE: i16 = 8;
+
+    /// Decodes the media key from a system-defined event.
+    unsafe fn as_media_key(&self) -> Option<event::MediaKeyEvent> {
+        let subtype: i16 = msg_send![self.raw_event, subtype];
+        if subtype != Self::AUX_CONTROL_BUTTONS_SUBTYPE {
+            return None;
+        }
+
+        let data = NSEvent::data1(self.raw_event);
+        let key = event::MediaKey::from_key_type((data & 0xFFFF_0000) >> 16)?;
+        let flags = data & 0xFFFF;
+
+        match (flags & 0xFF00) >> 8 {
+            // NX_KEYDOWN
+            0x0A => Some(event::MediaKeyEvent::Pressed {
+                key,
+                is_repeat: flags & 0x1 != 0,
+            }),
+            // NX_KEYUP
+            0x0B => Some(event::MediaKeyEvent::Released(key)),
+            _ => None,
+        }
+    }
+
+    unsafe fn as_multi_click(&self) -> Option<event::MouseEvent> {
+        let event = self.raw_event;
+        let button = match NSEvent::eventType(event) {
+            NSEventType::NSLeftMouseDown => mouse::Button::Left,
+            NSEventType::NSRightMouseDown => mouse::Button::Right,
+            NSEventType::NSOtherMouseDown => ButtonNumber(NSEvent::buttonNumber(event)).into(),
+            _ => return None,
+        };
+        let count = NSEvent::clickCount(event);
+
+        if count < 2 {
+            return None;
+        }
+
+        Some(event::MouseEvent::MultiClick {
+            button,
+            count: count as u32,
+            position: self.location(),
+        })
+    }
+
+    unsafe fn as_tablet_event(&self) -> Option<event::TabletEvent> {
+        let event = self.raw_event;
+        let subtype = match NSEvent::eventType(event) {
+            NSEventType::NSTabletPoint => Self::TABLET_POINT_SUBTYPE,
+            NSEventType::NSTabletProximity => Self::TABLET_PROXIMITY_SUBTYPE,
+            // Only the mouse events have a subtype telling whether they come from a tablet.
+            NSEventType::NSLeftMouseDown
+            | NSEventType::NSLeftMouseUp
+            | NSEventType::NSLeftMouseDragged
+            | NSEventType::NSRightMouseDown
+            | NSEventType::NSRightMouseUp
+            | NSEventType::NSRightMouseDragged
+            | NSEventType::NSOtherMouseDown
+            | NSEventType::NSOtherMouseUp
+            | NSEventType::NSOtherMouseDragged
+            | NSEventType::NSMouseMoved => msg_send![event, subtype],
+            _ => return None,
+        };
+
+        match subtype {
+            Self::TABLET_POINT_SUBTYPE => {
+                let tilt = NSEvent::tilt(event);
+                Some(event::TabletEvent::Point {
+                    position: self.location(),
+                    pressure: NSEvent::pressure(event),
+                    tilt: Vector::new(tilt.x as f32, tilt.y as f32),
+                })
+            }
+            Self::TABLET_PROXIMITY_SUBTYPE => {
+                let is_entering: BOOL = msg_send![event, isEnteringProximity];
+                let device_type: NSUInteger = msg_send![event, pointingDeviceType];
+                Some(event::TabletEvent::Proximity {
+                    is_entering: is_entering != NO,
+                    device: event::PointingDevice::from_device_type(device_type),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    unsafe fn swipe_directions(&self) -> Vec<event::SwipeDirection> {
+        let x = NSEvent::deltaX(self.raw_event);
+        let y = NSEvent::deltaY(self.raw_event);
+        let horizontal = if x > 0.0 {
+            Some(event::SwipeDirection::Left)
+        } else if x < 0.0 {
+            Some(event::SwipeDirection::Right)
+        } else {
+            None
+        };
+        let vertical = if y > 0.0 {
+            Some(event::SwipeDirection::Up)
+        } else if y < 0.0 {
+            Some(event::SwipeDirection::Down)
+        } else {
+            None
+        };
+
+        horizontal.into_iter().chain(vertical).collect()
+    }
+
+    unsafe fn as_wheel_scrolled(
+        self,
+        direction: ScrollDirection,
+        shift_scrolls_horizontally: bool,
+        sensitivity: f32,
+        fine_adjust_factor: f32,
+    ) -> Vec<Event> {
+        let event = self.raw_event;
+        let inverted: BOOL = msg_send![event, isDirectionInvertedFromDevice];
+        let sign = match direction {
+            ScrollDirection::Natural if inverted == NO => -1.0,
+            ScrollDirection::Traditional if inverted != NO => -1.0,
+            _ => 1.0,
+        };
+        let mut x = NSEvent::scrollingDeltaX(event) as f32 * sign;
+        let mut y = NSEvent::scrollingDeltaY(event) as f32 * sign;
+        let is_precise = NSEvent::hasPreciseScrollingDeltas(event) != NO;
+
+        // Very old devices only report the legacy deltas.
+        if !is_precise && x == 0.0 && y == 0.0 {
+            x = NSEvent::deltaX(event) as f32 * sign;
+            y = NSEvent::deltaY(event) as f32 * sign;
+        }
+
+        if shift_scrolls_horizontally
+            && x == 0.0
+            && NSEvent::modifierFlags(event).contains(NSEventModifierFlags::NSShiftKeyMask)
+        {
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        let mut scale = sensitivity;
+        if NSEvent::modifierFlags(event).contains(NSEventModifierFlags::NSAlternateKeyMask) {
+            scale *= fine_adjust_factor;
+        }
+        x *= scale;
+        y *= scale;
+
+        let delta = if is_precise {
+            mouse::ScrollDelta::Pixels { x, y }
+        } else {
+            mouse::ScrollDelta::Lines { x, y }
+        };
+
+        vec![Event::Mouse(mouse::Event::WheelScrolled { delta })]
+    }
+}
+
+/// Queues an event, merging it into the last queued one if both are cursor moves or scrolls of
+/// the same kind, so a storm of them doesn't have to be processed one by one.
+///
+/// The other events are queued as is, so the order of the moves and scrolls relative to them is
+/// preserved.
+fn coalesce_event(events: &mut Vec<Event>, event: Event) {
+    if let Some(last) = events.last_mut() {
+        if merge_event(last, &event) {
+            return;
+        }
+    }
+
+    events.push(event);
+}
+
+/// Queues the event after the pending ones, coalescing it with the last one if enabled.
+///
+/// Once per frame, a cursor move supersedes all the moves queued since the last mouse event of
+/// another kind, even across the key events, rather than only the one right before it.
+fn queue_pending_event(
+    events: &mut Vec<Event>,
+    event: Event,
+    coalesces: bool,
+    pointer_rate: PointerRate,
+) {
+    if pointer_rate == PointerRate::PerFrame && is_cursor_move(&event) {
+        drop_superseded_moves(events);
+    }
+
+    if coalesces {
+        coalesce_event(events, event);
+    } else {
+        events.push(event);
+    }
+}
+
+fn is_cursor_move(event: &Event) -> bool {
+    matches!(event, Event::Mouse(mouse::Event::CursorMoved { .. }))
+}
+
+/// Drops the cursor moves queued since the last mouse event of another kind, as a new move
+/// supersedes them. The moves before the button presses are kept, so the presses land where they
+/// have been made.
+fn drop_superseded_moves(events: &mut Vec<Event>) {
+    let start = events
+        .iter()
+        .rposition(|e| matches!(e, Event::Mouse(_)) && !is_cursor_move(e))
+        .map_or(0, |index| index + 1);
+
+    let mut index = 0;
+    events.retain(|e| {
+        let is_superseded = index >= start && is_cursor_move(e);
+        index += 1;
+        !is_superseded
+    });
+}
+
+/// Merges the event into the last one, returning whether it has been merged.
+fn merge_event(last: &mut Event, event: &Event) -> bool {
+    use mouse::ScrollDelta;
+
+    match (last, event) {
+        (
+            Event::Mouse(mouse::Event::CursorMoved { x, y }),
+            Event::Mouse(mouse::Event::CursorMoved { x: new_x, y: new_y }),
+        ) => {
+            *x = *new_x;
+            *y = *new_y;
+            true
+        }
+        (
+            Event::Mouse(mouse::Event::WheelScrolled { delta }),
+            Event::Mouse(mouse::Event::WheelScrolled { delta: new_delta }),
+        ) => match (delta, new_delta) {
+            (ScrollDelta::Lines { x, y }, ScrollDelta::Lines { x: dx, y: dy })
+            | (ScrollDelta::Pixels { x, y }, ScrollDelta::Pixels { x: dx, y: dy }) => {
+                *x += dx;
+                *y += dy;
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns the modifiers held now, rather than when an event has occurred.
+unsafe fn current_modifier_flags() -> NSEventModifierFlags {
+    let flags: NSUInteger = msg_send![class!(NSEvent), modifierFlags];
+    NSEventModifierFlags::from_bits_truncate(flags)
+}
+
+/// Converts a point on the screen from the Cocoa coordinates, whose origin is at the bottom-left
+/// corner of the primary screen, to the global display coordinates, whose origin is at its
+/// top-left corner.
+///
+/// Both are in points, so the backing scale doesn't matter.
+fn global_display_point(point: NSPoint, primary_screen_height: CGFloat) -> CGPoint {
+    CGPoint::new(point.x, primary_screen_height - point.y)
+}
+
+/// Where a view is, so its points are converted to the screen without AppKit.
+#[derive(Debug, Clone, Copy)]
+struct ViewGeometry {
+    /// The frame of the view in the window coordinates, whose origin is at the bottom-left
+    /// corner of the window.
+    frame_in_window: NSRect,
+    /// The frame of the window on the screen.
+    window_frame: NSRect,
+    /// The backing scale factor of the window.
+    scale_factor: CGFloat,
+    /// The height of the primary screen, which the screen coordinates are flipped against.
+    primary_screen_height: CGFloat,
+}
+
+impl ViewGeometry {
+    /// Converts a point of the view to the global display coordinates, snapped to the pixels
+    /// of the window, which the cursor is placed on.
+    fn global_display_point(&self, position: Point) -> CGPoint {
+        let x = self.window_frame.origin.x + self.frame_in_window.origin.x + f64::from(position.x);
+        // The view is flipped, so its points go down from its top-left corner.
+        let y = self.window_frame.origin.y
+            + self.frame_in_window.origin.y
+            + self.frame_in_window.size.height
+            - f64::from(position.y);
+        let snap = |value: CGFloat| (value * self.scale_factor).round() / self.scale_factor;
+        global_display_point(NSPoint::new(snap(x), snap(y)), self.primary_screen_height)
+    }
+}
+
+/// Returns whether a move to the position is the one produced by warping the cursor, which
+/// may be off by the rounding to the pixels of the screen.
+fn is_warp_move(warped: Point, position: Point) -> bool {
+    (position.x - warped.x).abs() < 1.0 && (position.y - warped.y).abs() < 1.0
+}
+
+/// Converts the timestamp of an event, which is the time since the system startup, given the
+/// current instant and the current time since the system startup.
+fn instant_from_timestamp(
+    timestamp: NSTimeInterval,
+    now: Instant,
+    uptime: NSTimeInterval,
+) -> Instant {
+    let elapsed = Duration::from_secs_f64((uptime - timestamp).max(0.0));
+    now.checked_sub(elapsed).unwrap_or(now)
+}
+
+/// Tracks the cursor to set it only when the interaction changes over the view, so the cursors
+/// of the other views aren't overridden.
+#[derive(Debug, Default)]
+struct CursorTracker {
+    is_inside: bool,
+    current: Option<mouse::Interaction>,
+    applied: Option<mouse::Interaction>,
+    is_changed: bool,
+}
+
+impl CursorTracker {
+    fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::Mouse(mouse::Event::CursorEntered) => {
+                self.is_inside = true;
+                // Another view could have changed the cursor in the meantime.
+                self.applied = None;
+            }
+            Event::Mouse(mouse::Event::CursorLeft) => self.is_inside = false,
+            _ => {}
+        }
+    }
+
+    /// Forgets the applied interaction, so it's applied again.
+    fn invalidate(&mut self) {
+        self.applied = None;
+        self.is_changed = true;
+    }
+
+    /// The interaction of the widgets under the cursor.
+    fn current(&self) -> Option<mouse::Interaction> {
+        self.current
+    }
+
+    /// Returns whether the interaction has changed since the last call.
+    fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.is_changed)
+    }
+
+    /// Returns the interaction to apply, if it must be applied.
+    fn update(&mut self, interaction: mouse::Interaction) -> Option<mouse::Interaction> {
+        if self.current != Some(interaction) {
+            self.current = Some(interaction);
+            self.is_changed = true;
+        }
+
+        if !self.is_inside || self.applied == Some(interaction) {
+            return None;
+        }
+
+        self.applied = Some(interaction);
+        Some(interaction)
+    }
+}
+
+/// Replaces the tracking area of the view with one over its bounds, returning the new one,
+/// which is retained.
+///
+/// AppKit updates the tracking areas on every change of the geometry, so the previous area is
+/// replaced rather than accumulated.
+unsafe fn replace_tracking_area(view: &mut Object, previous: id, options: NSUInteger) -> id {
+    if previous != nil {
+        let () = msg_send![view, removeTrackingArea: previous];
+        let () = msg_send![previous, release];
+    }
+
+    let bounds: NSRect = msg_send![view, bounds];
+    let alloc: *mut Object = msg_send![class!(NSTrackingArea), alloc];
+    let tracking_area: *mut Object =
+        msg_send![alloc, initWithRect:bounds options:options owner:&*view userInfo:nil];
+    let () = msg_send![view, addTrackingArea: tracking_area];
+    tracking_area
+}
+
+/// The number of the tracking areas of the view which it owns.
+unsafe fn owned_tracking_areas_count(view: &Object) -> usize {
+    let tracking_areas: id = msg_send![view, trackingAreas];
+    (0..tracking_areas.count())
+        .filter(|n| {
+            let owner: *const Object = msg_send![tracking_areas.objectAtIndex(*n), owner];
+            std::ptr::eq(owner, view)
+        })
+        .count()
+}
+
+/// The event of an internal drag reported for an event of the type, if any. The drop ends the
+/// drag.
+///
+/// The location is only read for the mouse events, as it's undefined for the others.
+fn internal_drag_event(
+    event_type: NSEventType,
+    location: impl FnOnce() -> Point,
+) -> Option<event::Event> {
+    match event_type {
+        NSEventType::NSLeftMouseDragged => Some(event::Event::InternalDragMoved {
+            position: location(),
+        }),
+        NSEventType::NSLeftMouseUp => Some(event::Event::InternalDragDropped {
+            position: location(),
+        }),
+        _ => None,
+    }
+}
+
+/// The drag of files over the view, as a dragging destination, and the events it's reported
+/// to the application with.
+#[derive(Debug, Default)]
+struct FileDrag {
+    /// Whether the application accepts the drag, which is hidden from it otherwise.
+    is_accepted: bool,
+    /// Whether the drag has been dropped onto the view.
+    is_dropped: bool,
+    /// The paths of the dragged files.
+    paths: Vec<PathBuf>,
+    /// The last position of the cursor reported during the drag.
+    position: Option<Point>,
+    /// The modifiers held during the drag, as of its last move or its drop.
+    modifiers: Option<keyboard::ModifiersState>,
+}
+
+impl FileDrag {
+    /// Starts a drag of the paths, which isn't accepted yet.
+    fn enter(&mut self, paths: Vec<PathBuf>) {
+        *self = FileDrag {
+            paths,
+            ..Default::default()
+        };
+    }
+
+    fn accept(&mut self, position: Point) -> Vec<Event> {
+        self.is_accepted = true;
+        self.position = Some(position);
+        let mut events = vec![
+            Event::Mouse(mouse::Event::CursorEntered),
+            Event::Mouse(mouse::Event::CursorMoved {
+                x: position.x,
+                y: position.y,
+            }),
+        ];
+        events.extend(
+            self.paths
+                .iter()
+                .map(|path| Event::Window(window::Event::FileHovered(path.clone()))),
+        );
+        events
+    }
+
+    fn reject(&mut self) -> Vec<Event> {
+        self.is_accepted = false;
+        self.position = None;
+        vec![
+            Event::Mouse(mouse::Event::CursorLeft),
+            Event::Window(window::Event::FilesHoveredLeft),
+        ]
+    }
+
+    fn move_to(&mut self, position: Point) -> Vec<Event> {
+        // The drag is reported continuously, even if the cursor stays still.
+        if let Some(last) = self.position {
+            if (position.x - last.x).abs() < 1.0 && (position.y - last.y).abs() < 1.0 {
+                return Vec::new();
+            }
+        }
+        self.position = Some(position);
+
+        vec![Event::Mouse(mouse::Event::CursorMoved {
+            x: position.x,
+            y: position.y,
+        })]
+    }
+
+    /// Takes the modifiers held during the drag, or `None` once it's over, returning the event
+    /// reporting them if they've changed.
+    fn set_modifiers(&mut self, flags: Option<NSEventModifierFlags>) -> Option<event::Event> {
+        let modifiers = flags.map(|flags| ModifierFlags(flags).into());
+        if modifiers == self.modifiers {
+            return None;
+        }
+
+        self.modifiers = modifiers;
+        Some(event::Event::DragModifiers(modifiers))
+    }
+
+    /// Marks the drag as dropped onto the view with the modifiers held, which are reported
+    /// before the dropped things.
+    fn drop_with(&mut self, flags: NSEventModifierFlags) -> Option<event::Event> {
+        self.is_dropped = true;
+        self.set_modifiers(Some(flags))
+    }
+
+    /// Returns whether the drag can be dropped where it's ended.
+    fn can_drop(&self, is_inside: bool) -> bool {
+        self.is_accepted && is_inside
+    }
+
+    /// Forgets the drag and its modifiers, whether it's been dropped or not.
+    fn end(&mut self, position: Point, is_inside: bool) -> Vec<Event> {
+        let mut drag = std::mem::take(self);
+        // The cursor of a dropped drag is still reported over the view.
+        if drag.is_dropped {
+            return Vec::new();
+        }
+
+        // A drag ending without a drop, e.g. with the operation rejected by the destination,
+        // is a cancellation even if it hasn't exited the view.
+        let mut events = Vec::new();
+        if drag.is_accepted {
+            events.extend(drag.reject());
+        }
+
+        // A cancelled drag exits the view even if the cursor stays over it, and the tracking
+        // area won't report the cursor entering again.
+        if is_inside {
+            events.extend(vec![
+                Event::Mouse(mouse::Event::CursorEntered),
+                Event::Mouse(mouse::Event::CursorMoved {
+                    x: position.x,
+                    y: position.y,
+                }),
+            ]);
+        }
+        events
+    }
+}
+
+struct ButtonNumber(i64);
+
+impl From<ButtonNumber> for mouse::Button {
+    fn from(number: ButtonNumber) -> Self {
+        match number.0 {
+            2 => mouse::Button::Middle,
+            value => mouse::Button::Other(value as u8),
+        }
+    }
+}
+
+/// The `NSRange` of the `NSTextInputClient` methods, which needs an encoding to declare them.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+struct TextRange(NSRange);
+
+impl TextRange {
+    const NOT_FOUND: TextRange = TextRange(NSRange {
+        location: NSNotFound as NSUInteger,
+        length: 0,
+    });
+}
+
+unsafe impl Encode for TextRange {
+    fn encode() -> Encoding {
+        let encoding = format!(
+            "{{_NSRange={}{}}}",
+            NSUInteger::encode().as_str(),
+            NSUInteger::encode().as_str()
+        );
+        unsafe { Encoding::from_str(&encoding) }
+    }
+}
+
+/// Returns the characters typed with a key event as if no modifiers, except Shift, were held.
+unsafe fn characters_ignoring_modifiers(event: id) -> String {
+    string_from_text_input(NSEvent::charactersIgnoringModifiers(event))
+}
+
+/// Returns the text passed to the `NSTextInputClient` methods, which is either `NSString` or
+/// `NSAttributedString`.
+unsafe fn string_from_text_input(string: id) -> String {
+    let is_attributed: BOOL = msg_send![string, isKindOfClass: class!(NSAttributedString)];
+    let string: id = if is_attributed != NO {
+        msg_send![string, string]
+    } else {
+        string
+    };
+    let ptr = string.UTF8String();
+
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().to_string()
+    }
+}
+
+/// Creates a new directory for the files promised to a drop, so the files of the different
+/// drops can't collide.
+fn promised_files_directory() -> std::io::Result<PathBuf> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let directory = std::env::temp_dir().join("iced-nsview").join(format!(
+        "drop-{}-{}",
+        std::process::id(),
+        nanos
+    ));
+    std::fs::create_dir_all(&directory)?;
+    Ok(directory)
+}
+
+/// The temporary directory of the files promised to a drop, which is removed with them once
+/// the last reader and the update of the drop have dropped it.
+struct PromisedFilesDirectory(PathBuf);
+
+impl Drop for PromisedFilesDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The length of the RGBA pixels of an image of the size, or `None` if it overflows.
+pub(crate) fn rgba_len(size: Size<u32>) -> Option<usize> {
+    (size.width as usize)
+        .checked_mul(size.height as usize)?
+        .checked_mul(4)
+}
+
+/// Creates an `NSImage` of the RGBA pixels, whose size in points is the size in pixels.
+///
+/// It returns `None` if the image is empty, if the pixels don't match the size, or if the
+/// bitmap couldn't be allocated.
+pub(crate) unsafe fn image_from_rgba(rgba: &[u8], size: Size<u32>) -> Option<id> {
+    // The pixels are copied into a bitmap of the size, which they mustn't overflow.
+    if size.width == 0 || size.height == 0 || Some(rgba.len()) != rgba_len(size) {
+        return None;
+    }
+
+    let width = size.width as NSInteger;
+    let height = size.height as NSInteger;
+    let planes: *mut *mut u8 = std::ptr::null_mut();
+    let bits_per_sample: NSInteger = 8;
+    let samples_per_pixel: NSInteger = 4;
+    let color_space = NSString::alloc(nil).init_str("NSDeviceRGBColorSpace");
+    let alloc: id = msg_send![class!(NSBitmapImageRep), alloc];
+    let image_rep: id = msg_send![
+        alloc,
+        initWithBitmapDataPlanes: planes
+        pixelsWide: width
+        pixelsHigh: height
+        bitsPerSample: bits_per_sample
+        samplesPerPixel: samples_per_pixel
+        hasAlpha: YES
+        isPlanar: NO
+        colorSpaceName: color_space
+        bytesPerRow: width * samples_per_pixel
+        bitsPerPixel: bits_per_sample * samples_per_pixel
+    ];
+    let () = msg_send![color_space, release];
+    if image_rep == nil {
+        return None;
+    }
+    let data: *mut u8 = msg_send![image_rep, bitmapData];
+    if data.is_null() {
+        let () = msg_send![image_rep, release];
+        return None;
+    }
+    std::ptr::copy_nonoverlapping(rgba.as_ptr(), data, rgba.len());
+
+    let alloc: id = msg_send![class!(NSImage), alloc];
+    let image: id = msg_send![
+        alloc,
+        initWithSize: NSSize::new(size.width.into(), size.height.into())
+    ];
+    let () = msg_send![image, addRepresentation: image_rep];
+    let () = msg_send![image_rep, release];
+    Some(image)
+}
+
+/// Keeps an Objective-C object alive while it's captured by a block.
+struct RetainedObject(*mut Object);
+
+// Retaining and releasing are thread-safe, while the object is only messaged on the threads it
+// allows.
+unsafe impl Send for RetainedObject {}
+
+impl RetainedObject {
+    unsafe fn new(object: *mut Object) -> Self {
+        let _: id = msg_send![object, retain];
+        RetainedObject(object)
+    }
+
+    /// Takes over an object which has already been retained.
+    fn from_retained(object: *mut Object) -> Self {
+        RetainedObject(object)
+    }
+}
+
+impl Drop for RetainedObject {
+    fn drop(&mut self) {
+        unsafe {
+            let () = msg_send![self.0, release];
+        }
+    }
+}
+
+unsafe fn pathbuf_from_nsurl(url: *mut Object) -> PathBuf {
+    let ptr: *const c_char = msg_send![url, fileSystemRepresentation];
+    CStr::from_ptr(ptr).to_string_lossy().to_string().into()
+}
+
+/// This function returns scale factor of the passed view.
+///
+/// It returns `None` if the view has no window.
+pub unsafe fn get_nsview_scale_factor(view: *mut c_void) -> Option<f64> {
+    let window: id = msg_send![view as *mut Object, window];
+    if window.is_null() {
+        None
+    } else {
+        let scale_factor: CGFloat = msg_send![window, backingScaleFactor];
+        Some(scale_factor)
+    }
 }
 
-struct Program<A: Application> {
-    application: A,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // CGEventType
+    const LEFT_MOUSE_DOWN: u32 = 1;
+    const LEFT_MOUSE_UP: u32 = 2;
+    const RIGHT_MOUSE_DOWN: u32 = 3;
+    const RIGHT_MOUSE_UP: u32 = 4;
+    const OTHER_MOUSE_DOWN: u32 = 25;
+    const OTHER_MOUSE_UP: u32 = 26;
+    // CGMouseButton
+    const LEFT_BUTTON: u32 = 0;
+    const RIGHT_BUTTON: u32 = 1;
+    const CENTER_BUTTON: u32 = 2;
+    // CGScrollEventUnit
+    const PIXEL_UNIT: u32 = 0;
+    const LINE_UNIT: u32 = 1;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventCreateMouseEvent(
+            source: *const c_void,
+            mouse_type: u32,
+            position: CGPoint,
+            button: u32,
+        ) -> *mut c_void;
+        fn CGEventCreateScrollWheelEvent(
+            source: *const c_void,
+            units: u32,
+            wheel_count: u32,
+            wheel1: i32,
+            ...
+        ) -> *mut c_void;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+
+    /// Converts an event synthesized by Quartz without a view, so its location is the origin.
+    fn convert_cg_event<T>(cg_event: *mut c_void, convert: impl FnOnce(NSEventT<id>) -> T) -> T {
+        assert!(!cg_event.is_null());
+        objc::rc::autoreleasepool(|| unsafe {
+            let raw_event = NSEvent::eventWithCGEvent_(nil, cg_event);
+            CFRelease(cg_event);
+            convert(NSEventT {
+                raw_event,
+                view: nil,
+            })
+        })
+    }
+
+    fn click(mouse_type: u32, button: u32) -> Vec<Event> {
+        let cg_event = unsafe {
+            CGEventCreateMouseEvent(std::ptr::null(), mouse_type, CGPoint::new(0.0, 0.0), button)
+        };
+        convert_cg_event(cg_event, Vec::from)
+    }
+
+    fn scroll_up(units: u32) -> mouse::ScrollDelta {
+        let cg_event = unsafe { CGEventCreateScrollWheelEvent(std::ptr::null(), units, 1, 3) };
+        let events = convert_cg_event(cg_event, |event| unsafe {
+            event.as_wheel_scrolled(ScrollDirection::System, false, 1.0, 1.0)
+        });
+        match events.as_slice() {
+            [Event::Mouse(mouse::Event::WheelScrolled { delta })] => *delta,
+            events => panic!("{:?}", events),
+        }
+    }
+
+    #[test]
+    fn mouse_wheels_scroll_by_lines() {
+        assert!(matches!(
+            scroll_up(LINE_UNIT),
+            mouse::ScrollDelta::Lines { x, y } if x == 0.0 && y > 0.0
+        ));
+    }
+
+    #[test]
+    fn trackpads_scroll_by_pixels() {
+        assert!(matches!(
+            scroll_up(PIXEL_UNIT),
+            mouse::ScrollDelta::Pixels { x, y } if x == 0.0 && y > 0.0
+        ));
+    }
+
+    #[test]
+    fn presses_and_releases_move_the_cursor_first() {
+        let moved = Event::Mouse(mouse::Event::CursorMoved { x: 0.0, y: 0.0 });
+        vec![
+            (
+                click(LEFT_MOUSE_DOWN, LEFT_BUTTON),
+                mouse::Event::ButtonPressed(mouse::Button::Left),
+            ),
+            (
+                click(LEFT_MOUSE_UP, LEFT_BUTTON),
+                mouse::Event::ButtonReleased(mouse::Button::Left),
+            ),
+            (
+                click(RIGHT_MOUSE_DOWN, RIGHT_BUTTON),
+                mouse::Event::ButtonPressed(mouse::Button::Right),
+            ),
+            (
+                click(RIGHT_MOUSE_UP, RIGHT_BUTTON),
+                mouse::Event::ButtonReleased(mouse::Button::Right),
+            ),
+        ]
+        .into_iter()
+        .for_each(|(events, button)| assert_eq!(events, vec![moved.clone(), Event::Mouse(button)]));
+    }
+
+    #[test]
+    fn tracking_areas_are_replaced_rather_than_accumulated() {
+        objc::rc::autoreleasepool(|| unsafe {
+            let view: id = msg_send![class!(NSView), alloc];
+            let view: id = msg_send![view, initWithFrame: NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(100.0, 100.0),
+            )];
+            // NSTrackingMouseEnteredAndExited | NSTrackingActiveAlways
+            let options = 0x01 | 0x80;
+            let tracking_area = (0..100).fold(nil, |previous, _| {
+                replace_tracking_area(&mut *view, previous, options)
+            });
+            assert_eq!(owned_tracking_areas_count(&*view), 1);
+
+            let () = msg_send![view, removeTrackingArea: tracking_area];
+            let () = msg_send![tracking_area, release];
+            let () = msg_send![view, release];
+        });
+    }
+
+    #[test]
+    fn cursors_are_set_only_inside_the_view() {
+        let mut cursor = CursorTracker::default();
+        // Another view owns the cursor until it enters.
+        assert_eq!(cursor.update(mouse::Interaction::Pointer), None);
+        assert!(cursor.take_changed());
+
+        cursor.on_event(&Event::Mouse(mouse::Event::CursorEntered));
+        assert_eq!(
+            cursor.update(mouse::Interaction::Pointer),
+            Some(mouse::Interaction::Pointer)
+        );
+
+        cursor.on_event(&Event::Mouse(mouse::Event::CursorLeft));
+        assert_eq!(cursor.update(mouse::Interaction::Text), None);
+        assert_eq!(cursor.current(), Some(mouse::Interaction::Text));
+    }
+
+    #[test]
+    fn cursors_are_set_once_per_interaction() {
+        let mut cursor = CursorTracker::default();
+        cursor.on_event(&Event::Mouse(mouse::Event::CursorEntered));
+        cursor.update(mouse::Interaction::Idle);
+        assert!(cursor.take_changed());
+
+        // The redraws keep the cursor of the other views over the unchanged interaction.
+        assert_eq!(cursor.update(mouse::Interaction::Idle), None);
+        assert!(!cursor.take_changed());
+
+        // It's applied again once entered, as another view may have changed it meanwhile.
+        cursor.on_event(&Event::Mouse(mouse::Event::CursorEntered));
+        assert_eq!(
+            cursor.update(mouse::Interaction::Idle),
+            Some(mouse::Interaction::Idle)
+        );
+        cursor.invalidate();
+        assert!(cursor.take_changed());
+        assert_eq!(
+            cursor.update(mouse::Interaction::Idle),
+            Some(mouse::Interaction::Idle)
+        );
+    }
+
+    #[test]
+    fn timestamps_are_converted_relative_to_now() {
+        let now = Instant::now();
+        let event = instant_from_timestamp(99.75, now, 100.0);
+        assert_eq!(now - event, Duration::from_millis(250));
+
+        // The intervals between the events are kept.
+        let next = instant_from_timestamp(99.875, now, 100.0);
+        assert_eq!(next - event, Duration::from_millis(125));
+    }
+
+    #[test]
+    fn timestamps_after_the_uptime_are_now() {
+        let now = Instant::now();
+        assert_eq!(instant_from_timestamp(100.5, now, 100.0), now);
+    }
+
+    #[test]
+    fn screen_points_are_flipped_from_the_primary_screen() {
+        // The primary screen is 900 points high, the point is 100 points above its bottom.
+        let point = global_display_point(NSPoint::new(40.0, 100.0), 900.0);
+        assert_eq!((point.x, point.y), (40.0, 800.0));
+        // The screens above the primary one are at negative coordinates.
+        let point = global_display_point(NSPoint::new(40.0, 1000.0), 900.0);
+        assert_eq!((point.x, point.y), (40.0, -100.0));
+    }
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> NSRect {
+        NSRect::new(NSPoint::new(x, y), NSSize::new(width, height))
+    }
+
+    #[test]
+    fn view_points_are_converted_through_the_window_to_the_screen() {
+        // The view fills the content of a window on the primary screen, below the title bar.
+        let geometry = ViewGeometry {
+            frame_in_window: rect(0.0, 0.0, 800.0, 578.0),
+            window_frame: rect(100.0, 200.0, 800.0, 600.0),
+            scale_factor: 2.0,
+            primary_screen_height: 900.0,
+        };
+        let point = geometry.global_display_point(Point::new(10.0, 20.0));
+        assert_eq!((point.x, point.y), (110.0, 142.0));
+
+        // The view is inside the content, in a window on a screen above and left of the
+        // primary one.
+        let geometry = ViewGeometry {
+            frame_in_window: rect(50.0, 40.0, 300.0, 200.0),
+            window_frame: rect(-1200.0, 1000.0, 800.0, 600.0),
+            scale_factor: 1.0,
+            primary_screen_height: 900.0,
+        };
+        let point = geometry.global_display_point(Point::new(10.0, 20.0));
+        assert_eq!((point.x, point.y), (-1140.0, -320.0));
+    }
+
+    #[test]
+    fn view_points_are_snapped_to_the_pixels_of_the_window() {
+        let geometry = ViewGeometry {
+            frame_in_window: rect(50.0, 40.0, 300.0, 200.0),
+            window_frame: rect(-1200.0, 1000.0, 800.0, 600.0),
+            scale_factor: 1.0,
+            primary_screen_height: 900.0,
+        };
+        let position = Point::new(10.4, 20.6);
+        let point = geometry.global_display_point(position);
+        assert_eq!((point.x, point.y), (-1140.0, -319.0));
+
+        // The Retina displays have half points.
+        let point = ViewGeometry {
+            scale_factor: 2.0,
+            ..geometry
+        }
+        .global_display_point(position);
+        assert_eq!((point.x, point.y), (-1139.5, -319.5));
+    }
+
+    #[test]
+    fn only_the_move_of_the_warp_is_dropped() {
+        let warped = Point::new(10.0, 20.0);
+        assert!(is_warp_move(warped, Point::new(10.5, 19.5)));
+        assert!(!is_warp_move(warped, Point::new(11.0, 20.0)));
+        assert!(!is_warp_move(warped, Point::new(10.0, 30.0)));
+    }
+
+    #[test]
+    fn other_buttons_follow_their_number() {
+        assert_eq!(mouse::Button::from(ButtonNumber(2)), mouse::Button::Middle);
+        assert_eq!(
+            mouse::Button::from(ButtonNumber(3)),
+            mouse::Button::Other(3)
+        );
+    }
+
+    #[test]
+    fn middle_clicks_are_pressed_and_released() {
+        let moved = Event::Mouse(mouse::Event::CursorMoved { x: 0.0, y: 0.0 });
+        assert_eq!(
+            click(OTHER_MOUSE_DOWN, CENTER_BUTTON),
+            vec![
+                moved.clone(),
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Middle)),
+            ]
+        );
+        assert_eq!(
+            click(OTHER_MOUSE_UP, CENTER_BUTTON),
+            vec![
+                moved,
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Middle)),
+            ]
+        );
+    }
+
+    fn command() -> keyboard::ModifiersState {
+        keyboard::ModifiersState {
+            logo: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn edit_actions_follow_the_typed_characters() {
+        assert_eq!(EditAction::from_key("c", command()), Some(EditAction::Copy));
+        assert_eq!(EditAction::from_key("x", command()), Some(EditAction::Cut));
+        assert_eq!(
+            EditAction::from_key("v", command()),
+            Some(EditAction::Paste)
+        );
+        assert_eq!(
+            EditAction::from_key("a", command()),
+            Some(EditAction::SelectAll)
+        );
+        // The key of A in the QWERTY layout types Q in the AZERTY one.
+        assert_eq!(EditAction::from_key("q", command()), None);
+    }
+
+    #[test]
+    fn edit_actions_need_command_only() {
+        assert_eq!(
+            EditAction::from_key("c", keyboard::ModifiersState::default()),
+            None
+        );
+        let command_shift = keyboard::ModifiersState {
+            shift: true,
+            ..command()
+        };
+        assert_eq!(EditAction::from_key("c", command_shift), None);
+    }
+
+    fn file_drag() -> FileDrag {
+        let mut drag = FileDrag::default();
+        drag.enter(vec![PathBuf::from("/tmp/kick.wav")]);
+        drag
+    }
 
-impl<A: Application> Program<A> {
-    fn new(application: A) -> Self {
-        Self { application }
+    fn left() -> Vec<Event> {
+        vec![
+            Event::Mouse(mouse::Event::CursorLeft),
+            Event::Window(window::Event::FilesHoveredLeft),
+        ]
     }
-}
 
-impl<A: Application> program::Program for Program<A> {
-    type Renderer = Renderer;
-    type Message = A::Message;
+    #[test]
+    fn accepted_file_drags_are_hovered() {
+        let mut drag = file_drag();
+        assert_eq!(
+            drag.accept(Point::new(10.0, 20.0)),
+            vec![
+                Event::Mouse(mouse::Event::CursorEntered),
+                Event::Mouse(mouse::Event::CursorMoved { x: 10.0, y: 20.0 }),
+                Event::Window(window::Event::FileHovered(PathBuf::from("/tmp/kick.wav"))),
+            ]
+        );
+        // The moves under a pixel aren't reported.
+        assert!(drag.move_to(Point::new(10.5, 20.5)).is_empty());
+        assert_eq!(
+            drag.move_to(Point::new(12.0, 20.0)),
+            vec![Event::Mouse(mouse::Event::CursorMoved { x: 12.0, y: 20.0 })]
+        );
+    }
 
-    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
-        self.application.update(message)
+    #[test]
+    fn file_drags_enter_and_leave_the_view_like_the_cursor() {
+        let mut drag = file_drag();
+        let entered = drag.accept(Point::new(10.0, 20.0));
+        assert_eq!(entered[0], Event::Mouse(mouse::Event::CursorEntered));
+
+        // The application rejects the drag over another widget, then accepts it again.
+        assert_eq!(drag.reject(), left());
+        assert_eq!(drag.accept(Point::new(10.0, 20.0)), entered);
+        // The position reported on entering isn't reported again.
+        assert!(drag.move_to(Point::new(10.0, 20.0)).is_empty());
     }
 
-    /// Application interface.
-    fn view(&mut self) -> NativeElement<'_, Self::Message, Self::Renderer> {
-        self.application.view()
+    #[test]
+    fn file_drags_exited_before_they_end_are_left_once() {
+        let mut drag = file_drag();
+        drag.accept(Point::new(10.0, 20.0));
+
+        assert_eq!(drag.reject(), left());
+        assert!(!drag.can_drop(false));
+        assert!(drag.end(Point::new(-5.0, 20.0), false).is_empty());
     }
-}
 
-struct EventHandler<A: 'static + Application> {
-    state: program::State<Program<A>>,
-    viewport: Viewport,
-    surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    format: wgpu::TextureFormat,
-    swap_chain: wgpu::SwapChain,
-    debug: Debug,
-    renderer: Renderer,
-    pasteboard: Pasteboard,
-}
+    #[test]
+    fn performed_file_drags_end_over_the_view() {
+        let mut drag = file_drag();
+        drag.accept(Point::new(10.0, 20.0));
 
-impl<A: 'static + Application> EventHandler<A> {
-    fn new(application: A, object: *mut Object, viewport: Viewport, settings: Settings) -> Self {
-        let surface = unsafe { Self::init_surface_layer(object, viewport.scale_factor()) };
-        let (mut device, queue) = Self::init_device_and_queue(&surface);
-        let format = wgpu::TextureFormat::Bgra8UnormSrgb;
-        let swap_chain =
-            Self::init_swap_chain(&viewport.physical_size(), &device, &surface, &format);
-        let mut debug = Debug::new();
-        let mut renderer = Renderer::new(Backend::new(&mut device, settings.into()));
-        let program = Program::new(application);
-        let state: program::State<Program<A>> =
-            program::State::new(program, viewport.logical_size(), &mut renderer, &mut debug);
+        assert!(drag.can_drop(true));
+        drag.is_dropped = true;
+        assert!(drag.end(Point::new(10.0, 20.0), true).is_empty());
+        assert!(!drag.is_accepted);
+        assert!(drag.paths.is_empty());
+    }
 
-        Self {
-            state,
-            viewport,
-            surface,
-            device,
-            queue,
-            format,
-            swap_chain,
-            debug,
-            renderer,
-            pasteboard: Pasteboard::new(),
-        }
+    #[test]
+    fn cancelled_file_drags_are_left_and_entered_again() {
+        let mut drag = file_drag();
+        drag.accept(Point::new(10.0, 20.0));
+
+        // Escape, or a drop rejected by the destination, ends the drag over the view.
+        let mut events = left();
+        events.extend(vec![
+            Event::Mouse(mouse::Event::CursorEntered),
+            Event::Mouse(mouse::Event::CursorMoved { x: 10.0, y: 20.0 }),
+        ]);
+        assert_eq!(drag.end(Point::new(10.0, 20.0), true), events);
     }
 
-    unsafe fn init_surface_layer(view: *mut Object, scale: f64) -> wgpu::Surface {
-        let class = class!(CAMetalLayer);
-        let layer: *mut Object = msg_send![class, new];
-        let () = msg_send![view, setWantsLayer: YES];
-        let parent: *mut Object = msg_send![view, layer];
-        let () = msg_send![parent, addSublayer: layer];
-        let bounds: CGRect = msg_send![view, bounds];
-        let () = msg_send![layer, setBounds: bounds];
-        let () = msg_send![layer, setContentsScale: scale];
-        let () = msg_send![layer, setAnchorPoint: CGPoint::new(0.0, 0.0)];
-        // kCALayerWidthSizable | kCALayerHeightSizable
-        let autoresizing_mask = 1u64 << 1 | 1 << 4;
-        let () = msg_send![layer, setAutoresizingMask: autoresizing_mask];
-        let _: *mut c_void = msg_send![view, retain];
+    #[test]
+    fn file_drags_ended_outside_are_not_dropped() {
+        let mut drag = file_drag();
+        drag.accept(Point::new(10.0, 20.0));
 
-        wgpu::Surface::create_surface_from_core_animation_layer(layer as *mut c_void)
+        // The drag is released over the Finder before the exit has been reported.
+        assert!(!drag.can_drop(false));
+        assert_eq!(drag.end(Point::new(-5.0, 20.0), false), left());
     }
 
-    fn init_device_and_queue(surface: &wgpu::Surface) -> (wgpu::Device, wgpu::Queue) {
-        futures::executor::block_on(async {
-            let adapter = wgpu::Adapter::request(
-                &wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::Default,
-                    compatible_surface: Some(&surface),
-                },
-                wgpu::BackendBit::PRIMARY,
-            )
-            .await
-            .expect("Request adapter");
+    #[test]
+    fn rejected_file_drags_are_not_dropped_or_left() {
+        let mut drag = file_drag();
 
-            adapter
-                .request_device(&wgpu::DeviceDescriptor {
-                    extensions: wgpu::Extensions {
-                        anisotropic_filtering: false,
-                    },
-                    limits: wgpu::Limits::default(),
-                })
-                .await
-        })
+        assert!(!drag.can_drop(true));
+        assert!(drag.end(Point::new(-5.0, 20.0), false).is_empty());
     }
 
-    fn init_swap_chain(
-        size: &Size<u32>,
-        device: &wgpu::Device,
-        surface: &wgpu::Surface,
-        format: &wgpu::TextureFormat,
-    ) -> wgpu::SwapChain {
-        device.create_swap_chain(
-            &surface,
-            &wgpu::SwapChainDescriptor {
-                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-                format: format.clone(),
-                width: size.width,
-                height: size.height,
-                present_mode: wgpu::PresentMode::Mailbox,
-            },
-        )
+    #[test]
+    fn background_colors_are_converted_into_the_tagged_space() {
+        let color = Color::from_rgb8(0x33, 0x66, 0x99);
+        assert_eq!(ColorSpace::Untagged.convert_from_srgb(color), color);
+        assert_eq!(ColorSpace::Srgb.convert_from_srgb(color), color);
+
+        // The red of sRGB is less saturated in Display P3, while the white stays white.
+        let red = ColorSpace::DisplayP3.convert_from_srgb(Color::from_rgb(1.0, 0.0, 0.0));
+        [(red.r, 0.9175), (red.g, 0.2003), (red.b, 0.1386)]
+            .iter()
+            .for_each(|(converted, expected)| {
+                assert!((converted - expected).abs() < 0.001, "{:?}", red)
+            });
+        let white = ColorSpace::DisplayP3.convert_from_srgb(Color::WHITE);
+        [white.r, white.g, white.b]
+            .iter()
+            .for_each(|component| assert!((component - 1.0).abs() < 0.001, "{:?}", white));
     }
 
-    fn resize(&mut self, new_size: Size<u32>, scale_factor: f64) {
-        self.viewport = Viewport::with_physical_size(new_size, scale_factor);
+    #[test]
+    fn rgba_lengths_are_computed_without_overflowing() {
+        assert_eq!(rgba_len(Size::new(2, 3)), Some(24));
+        // The length of 65536 × 65536 pixels overflows u32.
+        assert_eq!(rgba_len(Size::new(65_536, 65_536)), Some(1 << 34));
+        assert_eq!(
+            rgba_len(Size::new(u32::max_value(), u32::max_value())),
+            None
+        );
+    }
 
-        self.swap_chain = self.device.create_swap_chain(
-            &self.surface,
-            &wgpu::SwapChainDescriptor {
-                usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-                format: self.format,
-                width: new_size.width,
-                height: new_size.height,
-                present_mode: wgpu::PresentMode::Mailbox,
-            },
+    #[test]
+    fn empty_or_mismatched_images_are_not_created() {
+        unsafe {
+            assert!(image_from_rgba(&[], Size::new(0, 0)).is_none());
+            assert!(image_from_rgba(&[], Size::new(0, 16)).is_none());
+            assert!(image_from_rgba(&[0; 4], Size::new(2, 2)).is_none());
+        }
+    }
+
+    #[test]
+    fn promised_files_are_removed_with_their_last_holder() {
+        let directory = Arc::new(PromisedFilesDirectory(
+            promised_files_directory().expect("Create the directory"),
+        ));
+        let path = directory.0.join("mail.pdf");
+        std::fs::write(&path, b"%PDF").expect("Write the file");
+
+        // The update holds it once the reader is done.
+        let update = Arc::clone(&directory);
+        drop(directory);
+        assert!(path.exists());
+        drop(update);
+        assert!(!path.exists());
+    }
+
+    fn drag_modifiers(modifiers: keyboard::ModifiersState) -> Option<event::Event> {
+        Some(event::Event::DragModifiers(Some(modifiers)))
+    }
+
+    #[test]
+    fn option_and_command_are_reported_during_file_drags() {
+        let mut drag = file_drag();
+        drag.accept(Point::new(10.0, 20.0));
+        let option = keyboard::ModifiersState {
+            alt: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            drag.set_modifiers(Some(NSEventModifierFlags::NSAlternateKeyMask)),
+            drag_modifiers(option)
+        );
+        // The modifiers are reported only when they change.
+        assert_eq!(
+            drag.set_modifiers(Some(NSEventModifierFlags::NSAlternateKeyMask)),
+            None
+        );
+        assert_eq!(
+            drag.set_modifiers(Some(NSEventModifierFlags::NSCommandKeyMask)),
+            drag_modifiers(command())
         );
+        assert_eq!(drag.modifiers, Some(command()));
+    }
 
-        self.on_window_event(window::Event::Resized {
-            width: new_size.width,
-            height: new_size.height,
-        });
+    #[test]
+    fn option_and_command_are_reported_with_the_drop() {
+        let mut drag = file_drag();
+        drag.accept(Point::new(10.0, 20.0));
+        drag.set_modifiers(Some(NSEventModifierFlags::empty()));
+        let option_command = keyboard::ModifiersState {
+            alt: true,
+            logo: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            drag.drop_with(
+                NSEventModifierFlags::NSAlternateKeyMask | NSEventModifierFlags::NSCommandKeyMask
+            ),
+            drag_modifiers(option_command)
+        );
+        assert!(drag.is_dropped);
     }
 
-    fn on_window_event(&mut self, event: window::Event) {
-        self.queue_event(vec![Event::Window(event)]);
+    #[test]
+    fn ended_file_drags_have_no_modifiers() {
+        let mut drag = file_drag();
+        drag.accept(Point::new(10.0, 20.0));
+        drag.set_modifiers(Some(NSEventModifierFlags::NSAlternateKeyMask));
+        assert_eq!(
+            drag.set_modifiers(None),
+            Some(event::Event::DragModifiers(None))
+        );
+
+        drag.set_modifiers(Some(NSEventModifierFlags::NSAlternateKeyMask));
+        drag.end(Point::new(10.0, 20.0), true);
+        assert_eq!(drag.modifiers, None);
     }
 
-    fn queue_event(&mut self, events: Vec<Event>) {
-        events.into_iter().for_each(|e| self.state.queue_event(e));
+    #[test]
+    fn internal_drags_follow_the_left_button() {
+        let position = Point::new(10.0, 20.0);
+        assert_eq!(
+            internal_drag_event(NSEventType::NSLeftMouseDragged, || position),
+            Some(event::Event::InternalDragMoved { position })
+        );
+        assert_eq!(
+            internal_drag_event(NSEventType::NSLeftMouseUp, || position),
+            Some(event::Event::InternalDragDropped { position })
+        );
+        // The other buttons and the moves without a button held don't move the payload.
+        assert_eq!(
+            internal_drag_event(NSEventType::NSRightMouseUp, || position),
+            None
+        );
+        assert_eq!(
+            internal_drag_event(NSEventType::NSMouseMoved, || position),
+            None
+        );
     }
 
-    fn redraw(&mut self) {
-        self.update_state();
+    #[test]
+    fn live_resizes_recreate_the_swap_chain_at_most_every_interval() {
+        let start = Instant::now();
+        let mut throttle = ResizeThrottle::new(Size::new(400, 300), start);
+        let frame = Duration::from_millis(16);
 
-        if let Ok(frame) = self.swap_chain.get_next_texture() {
-            self.debug.render_started();
+        // The window is widened by a pixel each frame for a second.
+        let mut size = Size::new(400, 300);
+        let recreations = (1..=60u32)
+            .filter(|i| {
+                let now = start + frame * *i;
+                size.width += 1;
+                let is_recreated = throttle.should_recreate(size, true, true, now);
+                if is_recreated {
+                    throttle.on_created(size, now);
+                }
+                is_recreated
+            })
+            .count();
+        // Every fourth frame is 64 ms after the previous recreation.
+        assert_eq!(recreations, 15);
 
-            let mut encoder = self
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        // The size at the end of the resize is always recreated.
+        size.width += 1;
+        assert!(throttle.should_recreate(size, true, false, start + frame * 60));
+    }
 
-            self.render_pass(&frame, &mut encoder);
+    #[test]
+    fn resizes_to_the_same_size_keep_the_swap_chain() {
+        let start = Instant::now();
+        let throttle = ResizeThrottle::new(Size::new(400, 300), start);
+        let later = start + LIVE_RESIZE_INTERVAL * 2;
 
-            let mouse_interaction = self.render_pass_iced(&frame, &mut encoder);
+        assert!(!throttle.should_recreate(Size::new(400, 300), false, false, later));
+        // The scale factor may change without the size, e.g. across displays.
+        assert!(throttle.should_recreate(Size::new(400, 300), true, false, later));
+    }
 
-            self.queue.submit(&[encoder.finish()]);
+    #[test]
+    fn retina_viewports_are_in_pixels_and_resized_in_points() {
+        let size = physical_size(NSSize::new(400.0, 300.0), 2.0);
+        assert_eq!(size, Size::new(800, 600));
 
-            self.debug.render_finished();
+        let viewport = Viewport::with_physical_size(size, 2.0);
+        assert_eq!(viewport.physical_size(), Size::new(800, 600));
+        assert_eq!(viewport.logical_size(), Size::new(400.0, 300.0));
+        assert_eq!(
+            resized_event(&viewport),
+            window::Event::Resized {
+                width: 400,
+                height: 300,
+            }
+        );
+    }
 
-            self.set_cursor_icon(mouse_interaction);
-        }
+    #[test]
+    fn half_points_are_whole_pixels_on_retina_displays() {
+        assert_eq!(
+            physical_size(NSSize::new(100.5, 50.25), 2.0),
+            Size::new(201, 101)
+        );
+        assert_eq!(
+            physical_size(NSSize::new(100.5, 50.25), 1.0),
+            Size::new(101, 50)
+        );
     }
 
-    fn update_state(&mut self) {
-        if !self.state.is_queue_empty() {
-            self.state.update(
-                Some(&self.pasteboard),
-                self.viewport.logical_size(),
-                &mut self.renderer,
-                &mut self.debug,
-            );
-        }
+    #[test]
+    fn drawable_counts_are_clamped_to_two_or_three() {
+        let tuning = |drawable_count| LayerTuning {
+            display_sync: true,
+            drawable_count,
+        };
+        assert_eq!(tuning(0).supported_drawable_count(), 2);
+        assert_eq!(tuning(2).supported_drawable_count(), 2);
+        assert_eq!(tuning(3).supported_drawable_count(), 3);
+        assert_eq!(tuning(8).supported_drawable_count(), 3);
+    }
+
+    #[test]
+    fn only_the_immediate_present_mode_disables_the_display_sync() {
+        let tuning = |display_sync| LayerTuning {
+            display_sync,
+            drawable_count: 2,
+        };
+        assert!(!tuning(true).contradicts(wgpu::PresentMode::Fifo));
+        assert!(tuning(true).contradicts(wgpu::PresentMode::Immediate));
+        assert!(!tuning(false).contradicts(wgpu::PresentMode::Immediate));
+        assert!(tuning(false).contradicts(wgpu::PresentMode::Fifo));
+        // Mailbox is presented as Fifo by Metal.
+        assert_eq!(PresentMode::Mailbox.supported(), wgpu::PresentMode::Fifo);
+    }
+
+    #[test]
+    fn drag_positions_are_clamped_to_the_view() {
+        let size = Size::new(100.0, 50.0);
+        assert_eq!(
+            DragPositionPolicy::Clamp.apply(Point::new(-10.0, 70.0), size),
+            Point::new(0.0, 50.0)
+        );
+        assert_eq!(
+            DragPositionPolicy::Clamp.apply(Point::new(30.0, 20.0), size),
+            Point::new(30.0, 20.0)
+        );
+        assert_eq!(
+            DragPositionPolicy::Unclamped.apply(Point::new(-10.0, 70.0), size),
+            Point::new(-10.0, 70.0)
+        );
     }
 
-    fn render_pass(&mut self, frame: &wgpu::SwapChainOutput, encoder: &mut wgpu::CommandEncoder) {
-        let background_color = self.state.program().application.background_color();
+    #[test]
+    fn presentation_starts_suspended_and_resumes_once() {
+        let mut presentation = Presentation::<u32>::Suspended;
+        assert!(presentation.is_suspended());
 
-        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: &frame.view,
-                resolve_target: None,
-                load_op: wgpu::LoadOp::Clear,
-                store_op: wgpu::StoreOp::Store,
-                clear_color: wgpu::Color {
-                    r: background_color.r as f64,
-                    g: background_color.g as f64,
-                    b: background_color.b as f64,
-                    a: background_color.a as f64,
-                },
-            }],
-            depth_stencil_attachment: None,
-        });
+        assert!(presentation.resume(|| 1));
+        // Resuming again keeps the swap chain.
+        assert!(!presentation.resume(|| 2));
+        assert!(matches!(presentation, Presentation::Active(1)));
+
+        presentation.suspend();
+        assert!(presentation.is_suspended());
+        assert!(presentation.resume(|| 3));
+        assert!(matches!(presentation, Presentation::Active(3)));
     }
 
-    fn render_pass_iced(
-        &mut self,
-        frame: &wgpu::SwapChainOutput,
-        encoder: &mut wgpu::CommandEncoder,
-    ) -> mouse::Interaction {
-        self.renderer.backend_mut().draw(
-            &mut self.device,
-            encoder,
-            &frame.view,
-            &self.viewport,
-            self.state.primitive(),
-            &self.debug.overlay(),
-        )
+    #[test]
+    fn presentation_recreates_after_dropping_the_previous_swap_chain() {
+        let dropped = std::rc::Rc::new(std::cell::Cell::new(false));
+        let mut presentation = Presentation::Active(DropFlag(dropped.clone()));
+        presentation.recreate(|| {
+            assert!(dropped.get());
+            DropFlag(std::rc::Rc::new(std::cell::Cell::new(false)))
+        });
+        assert!(!presentation.is_suspended());
     }
 
-    fn set_cursor_icon(&self, cursor: mouse::Interaction) {
-        unsafe {
-            let class = class!(NSCursor);
-            let cocoa_cursor: *mut Object = match cursor {
-                mouse::Interaction::Idle => msg_send![class, arrowCursor],
-                mouse::Interaction::Pointer => msg_send![class, pointingHandCursor],
-                mouse::Interaction::Grab => msg_send![class, openHandCursor],
-                mouse::Interaction::Text => msg_send![class, IBeamCursor],
-                mouse::Interaction::Crosshair => msg_send![class, crosshairCursor],
-                mouse::Interaction::Working => msg_send![class, arrowCursor],
-                mouse::Interaction::Grabbing => msg_send![class, closedHandCursor],
-                mouse::Interaction::ResizingHorizontally => msg_send![class, resizeLeftRightCursor],
-                mouse::Interaction::ResizingVertically => msg_send![class, resizeUpDownCursor],
-            };
+    struct DropFlag(std::rc::Rc<std::cell::Cell<bool>>);
 
-            let () = msg_send![cocoa_cursor, set];
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.set(true);
         }
     }
-}
 
-struct NSEventT<T: NSEvent + Copy> {
-    raw_event: T,
-    view: *mut Object,
-}
+    #[test]
+    fn antialiasing_falls_back_to_the_nearest_mode() {
+        let modes = |antialiasing| {
+            nearest_antialiasing_modes(antialiasing)
+                .into_iter()
+                .map(|index| ANTIALIASING_MODES[index])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            modes(Antialiasing::MSAAx8),
+            vec![
+                Antialiasing::MSAAx8,
+                Antialiasing::MSAAx4,
+                Antialiasing::MSAAx16,
+                Antialiasing::MSAAx2,
+            ]
+        );
+        assert_eq!(
+            modes(Antialiasing::MSAAx2),
+            vec![
+                Antialiasing::MSAAx2,
+                Antialiasing::MSAAx4,
+                Antialiasing::MSAAx8,
+                Antialiasing::MSAAx16,
+            ]
+        );
+    }
 
-impl<T: NSEvent + Copy> From<NSEventT<T>> for Vec<Event> {
-    fn from(event: NSEventT<T>) -> Self {
-        unsafe {
-            let mouse_location: NSPoint = NSEvent::locationInWindow(event.raw_event);
-            let converted_location =
-                NSView::convertPoint_fromView_(event.view, mouse_location, nil);
-            let moved = Event::Mouse(mouse::Event::CursorMoved {
-                x: converted_location.x as f32,
-                y: converted_location.y as f32,
-            });
-            let button_num = NSEvent::buttonNumber(event.raw_event);
+    fn moved(x: f32) -> Event {
+        Event::Mouse(mouse::Event::CursorMoved { x, y: 0.0 })
+    }
 
-            match NSEvent::eventType(event.raw_event) {
-                NSEventType::NSLeftMouseDown => vec![Event::Mouse(mouse::Event::ButtonPressed(
-                    mouse::Button::Left,
-                ))],
-                NSEventType::NSLeftMouseUp => vec![Event::Mouse(mouse::Event::ButtonReleased(
-                    mouse::Button::Left,
-                ))],
-                NSEventType::NSRightMouseDown => vec![Event::Mouse(mouse::Event::ButtonPressed(
-                    mouse::Button::Right,
-                ))],
-                NSEventType::NSRightMouseUp => vec![Event::Mouse(mouse::Event::ButtonReleased(
-                    mouse::Button::Right,
-                ))],
-                NSEventType::NSMouseMoved => vec![moved],
-                NSEventType::NSLeftMouseDragged => vec![moved],
-                NSEventType::NSMouseEntered => vec![Event::Mouse(mouse::Event::CursorEntered)],
-                NSEventType::NSMouseExited => vec![Event::Mouse(mouse::Event::CursorLeft)],
-                NSEventType::NSKeyDown => event.as_key_down(),
-                NSEventType::NSKeyUp => event.as_key_up(),
-                NSEventType::NSScrollWheel => vec![Event::Mouse(mouse::Event::WheelScrolled {
-                    delta: mouse::ScrollDelta::Pixels {
-                        x: NSEvent::scrollingDeltaX(event.raw_event) as f32,
-                        y: NSEvent::scrollingDeltaY(event.raw_event) as f32,
-                    },
-                })],
-                NSEventType::NSOtherMouseDown => vec![Event::Mouse(mouse::Event::ButtonPressed(
-                    ButtonNumber(button_num).into(),
-                ))],
-                NSEventType::NSOtherMouseUp => vec![Event::Mouse(mouse::Event::ButtonReleased(
-                    ButtonNumber(button_num).into(),
-                ))],
-                _ => vec![],
-            }
-        }
+    fn shift_pressed() -> Event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code: keyboard::KeyCode::LShift,
+            modifiers: keyboard::ModifiersState::default(),
+        })
     }
-}
 
-impl<T: NSEvent + Copy> NSEventT<T> {
-    unsafe fn as_key_down(self) -> Vec<Event> {
-        let event = self.raw_event;
-        let modifiers =
-            keyboard::ModifiersState::from(ModifierFlags(NSEvent::modifierFlags(event)));
-
-        [
-            self.into_chars(),
-            Option::<keyboard::KeyCode>::from(NSKeyCode(NSEvent::keyCode(event)))
-                .map(|kc| {
-                    vec![Event::Keyboard(keyboard::Event::KeyPressed {
-                        key_code: kc,
-                        modifiers,
-                    })]
-                })
-                .unwrap_or_default(),
+    /// The moves of a fast gesture, interleaved with the presses of a key.
+    fn event_storm() -> Vec<Event> {
+        (0..1000)
+            .flat_map(|n| {
+                let mut events = vec![moved(n as f32)];
+                if n % 10 == 0 {
+                    events.push(shift_pressed());
+                }
+                events
+            })
+            .collect()
+    }
+
+    fn queue_storm(pointer_rate: PointerRate) -> Vec<Event> {
+        let mut pending = Vec::new();
+        event_storm()
+            .into_iter()
+            .for_each(|event| queue_pending_event(&mut pending, event, true, pointer_rate));
+        pending
+    }
+
+    #[test]
+    fn per_frame_moves_supersede_the_moves_across_keys() {
+        let pending = queue_storm(PointerRate::PerFrame);
+        assert_eq!(pending.iter().filter(|e| is_cursor_move(e)).count(), 1);
+        assert_eq!(pending.len(), 101);
+        assert!(matches!(
+            pending.last(),
+            Some(Event::Mouse(mouse::Event::CursorMoved { x, .. })) if *x == 999.0
+        ));
+
+        let pending = queue_storm(PointerRate::Unthrottled);
+        assert_eq!(pending.iter().filter(|e| is_cursor_move(e)).count(), 100);
+    }
+
+    #[test]
+    fn per_frame_moves_keep_the_position_of_a_press() {
+        let mut pending = Vec::new();
+        vec![
+            moved(1.0),
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)),
+            moved(2.0),
+            moved(3.0),
         ]
-        .concat()
+        .into_iter()
+        .for_each(|event| queue_pending_event(&mut pending, event, false, PointerRate::PerFrame));
+
+        assert_eq!(pending.len(), 3);
+        assert!(matches!(
+            pending[0],
+            Event::Mouse(mouse::Event::CursorMoved { x, .. }) if x == 1.0
+        ));
+        assert!(matches!(
+            pending[2],
+            Event::Mouse(mouse::Event::CursorMoved { x, .. }) if x == 3.0
+        ));
     }
 
-    unsafe fn into_chars(self) -> Vec<Event> {
-        let chars = NSEvent::characters(self.raw_event);
-        let ptr = chars.UTF8String();
-        CStr::from_ptr(ptr)
-            .to_string_lossy()
-            .chars()
-            .map(|c| Event::Keyboard(keyboard::Event::CharacterReceived(c)))
-            .collect()
+    fn scrolled(y: f32) -> Event {
+        Event::Mouse(mouse::Event::WheelScrolled {
+            delta: mouse::ScrollDelta::Pixels { x: 0.0, y },
+        })
     }
 
-    unsafe fn as_key_up(self) -> Vec<Event> {
-        let modifiers =
-            keyboard::ModifiersState::from(ModifierFlags(NSEvent::modifierFlags(self.raw_event)));
+    #[test]
+    fn consecutive_moves_and_scrolls_are_coalesced() {
+        let mut pending = Vec::new();
+        vec![
+            moved(1.0),
+            moved(2.0),
+            scrolled(1.0),
+            scrolled(2.5),
+            moved(3.0),
+        ]
+        .into_iter()
+        .for_each(|event| coalesce_event(&mut pending, event));
 
-        Option::<keyboard::KeyCode>::from(NSKeyCode(NSEvent::keyCode(self.raw_event)))
-            .map(|kc| {
-                vec![Event::Keyboard(keyboard::Event::KeyReleased {
-                    key_code: kc,
-                    modifiers,
-                })]
-            })
-            .unwrap_or_default()
-    }
-}
-
-struct NSKeyCode(u16);
-
-impl From<NSKeyCode> for Option<keyboard::KeyCode> {
-    fn from(key_code: NSKeyCode) -> Self {
-        match key_code.0 {
-            29 => Some(keyboard::KeyCode::Key0),
-            18 => Some(keyboard::KeyCode::Key1),
-            19 => Some(keyboard::KeyCode::Key2),
-            20 => Some(keyboard::KeyCode::Key3),
-            21 => Some(keyboard::KeyCode::Key4),
-            23 => Some(keyboard::KeyCode::Key5),
-            22 => Some(keyboard::KeyCode::Key6),
-            26 => Some(keyboard::KeyCode::Key7),
-            28 => Some(keyboard::KeyCode::Key8),
-            25 => Some(keyboard::KeyCode::Key9),
-            0 => Some(keyboard::KeyCode::A),
-            11 => Some(keyboard::KeyCode::B),
-            8 => Some(keyboard::KeyCode::C),
-            2 => Some(keyboard::KeyCode::D),
-            14 => Some(keyboard::KeyCode::E),
-            3 => Some(keyboard::KeyCode::F),
-            5 => Some(keyboard::KeyCode::G),
-            4 => Some(keyboard::KeyCode::H),
-            34 => Some(keyboard::KeyCode::I),
-            38 => Some(keyboard::KeyCode::J),
-            40 => Some(keyboard::KeyCode::K),
-            37 => Some(keyboard::KeyCode::L),
-            46 => Some(keyboard::KeyCode::M),
-            45 => Some(keyboard::KeyCode::N),
-            31 => Some(keyboard::KeyCode::O),
-            35 => Some(keyboard::KeyCode::P),
-            12 => Some(keyboard::KeyCode::Q),
-            15 => Some(keyboard::KeyCode::R),
-            1 => Some(keyboard::KeyCode::S),
-            17 => Some(keyboard::KeyCode::T),
-            32 => Some(keyboard::KeyCode::U),
-            9 => Some(keyboard::KeyCode::V),
-            13 => Some(keyboard::KeyCode::W),
-            7 => Some(keyboard::KeyCode::X),
-            16 => Some(keyboard::KeyCode::Y),
-            6 => Some(keyboard::KeyCode::Z),
-            // 10 => Some(::SectionSign),
-            50 => Some(keyboard::KeyCode::Grave),
-            27 => Some(keyboard::KeyCode::Minus),
-            24 => Some(keyboard::KeyCode::Equals),
-            33 => Some(keyboard::KeyCode::LBracket),
-            30 => Some(keyboard::KeyCode::RBracket),
-            41 => Some(keyboard::KeyCode::Semicolon),
-            39 => Some(keyboard::KeyCode::Apostrophe),
-            43 => Some(keyboard::KeyCode::Comma),
-            47 => Some(keyboard::KeyCode::Period),
-            44 => Some(keyboard::KeyCode::Slash),
-            42 => Some(keyboard::KeyCode::Backslash),
-            82 => Some(keyboard::KeyCode::Numpad0),
-            83 => Some(keyboard::KeyCode::Numpad1),
-            84 => Some(keyboard::KeyCode::Numpad2),
-            85 => Some(keyboard::KeyCode::Numpad3),
-            86 => Some(keyboard::KeyCode::Numpad4),
-            87 => Some(keyboard::KeyCode::Numpad5),
-            88 => Some(keyboard::KeyCode::Numpad6),
-            89 => Some(keyboard::KeyCode::Numpad7),
-            91 => Some(keyboard::KeyCode::Numpad8),
-            92 => Some(keyboard::KeyCode::Numpad9),
-            65 => Some(keyboard::KeyCode::NumpadComma),
-            67 => Some(keyboard::KeyCode::Multiply),
-            69 => Some(keyboard::KeyCode::Add),
-            75 => Some(keyboard::KeyCode::Divide),
-            78 => Some(keyboard::KeyCode::Minus),
-            81 => Some(keyboard::KeyCode::NumpadEquals),
-            // 71 => Some(::KeypadClear),
-            76 => Some(keyboard::KeyCode::NumpadEnter),
-            49 => Some(keyboard::KeyCode::Space),
-            36 => Some(keyboard::KeyCode::Enter),
-            48 => Some(keyboard::KeyCode::Tab),
-            51 => Some(keyboard::KeyCode::Backspace),
-            117 => Some(keyboard::KeyCode::Delete),
-            // 52 => Some(::Linefeed),
-            53 => Some(keyboard::KeyCode::Escape),
-            55 => Some(keyboard::KeyCode::LWin),
-            56 => Some(keyboard::KeyCode::LShift),
-            57 => Some(keyboard::KeyCode::Capital),
-            58 => Some(keyboard::KeyCode::LAlt),
-            59 => Some(keyboard::KeyCode::LControl),
-            60 => Some(keyboard::KeyCode::RShift),
-            61 => Some(keyboard::KeyCode::RAlt),
-            62 => Some(keyboard::KeyCode::RControl),
-            // 63 => Some(::Function),
-            122 => Some(keyboard::KeyCode::F1),
-            120 => Some(keyboard::KeyCode::F2),
-            99 => Some(keyboard::KeyCode::F3),
-            118 => Some(keyboard::KeyCode::F4),
-            96 => Some(keyboard::KeyCode::F5),
-            97 => Some(keyboard::KeyCode::F6),
-            98 => Some(keyboard::KeyCode::F7),
-            100 => Some(keyboard::KeyCode::F8),
-            101 => Some(keyboard::KeyCode::F9),
-            109 => Some(keyboard::KeyCode::F10),
-            103 => Some(keyboard::KeyCode::F11),
-            111 => Some(keyboard::KeyCode::F12),
-            105 => Some(keyboard::KeyCode::F13),
-            107 => Some(keyboard::KeyCode::F14),
-            113 => Some(keyboard::KeyCode::F15),
-            106 => Some(keyboard::KeyCode::F16),
-            64 => Some(keyboard::KeyCode::F17),
-            79 => Some(keyboard::KeyCode::F18),
-            80 => Some(keyboard::KeyCode::F19),
-            90 => Some(keyboard::KeyCode::F20),
-            72 => Some(keyboard::KeyCode::VolumeUp),
-            73 => Some(keyboard::KeyCode::VolumeDown),
-            74 => Some(keyboard::KeyCode::Mute),
-            114 => Some(keyboard::KeyCode::Insert),
-            115 => Some(keyboard::KeyCode::Home),
-            119 => Some(keyboard::KeyCode::End),
-            116 => Some(keyboard::KeyCode::PageUp),
-            121 => Some(keyboard::KeyCode::PageDown),
-            123 => Some(keyboard::KeyCode::Left),
-            124 => Some(keyboard::KeyCode::Right),
-            125 => Some(keyboard::KeyCode::Down),
-            126 => Some(keyboard::KeyCode::Up),
-            _ => None,
-        }
+        assert_eq!(pending, vec![moved(2.0), scrolled(3.5), moved(3.0)]);
     }
-}
 
-struct ModifierFlags(NSEventModifierFlags);
+    #[test]
+    fn coalescing_keeps_the_order_of_the_buttons_and_the_keys() {
+        let pressed = Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+        let mut pending = Vec::new();
+        vec![
+            moved(1.0),
+            pressed.clone(),
+            moved(2.0),
+            shift_pressed(),
+            moved(3.0),
+            moved(4.0),
+        ]
+        .into_iter()
+        .for_each(|event| coalesce_event(&mut pending, event));
 
-impl From<ModifierFlags> for keyboard::ModifiersState {
-    fn from(flags: ModifierFlags) -> Self {
-        Self {
-            shift: flags.0.contains(NSEventModifierFlags::NSShiftKeyMask),
-            control: flags.0.contains(NSEventModifierFlags::NSControlKeyMask),
-            alt: flags.0.contains(NSEventModifierFlags::NSAlternateKeyMask),
-            logo: flags.0.contains(NSEventModifierFlags::NSCommandKeyMask),
-        }
+        assert_eq!(
+            pending,
+            vec![moved(1.0), pressed, moved(2.0), shift_pressed(), moved(4.0)]
+        );
     }
-}
 
-struct ButtonNumber(i64);
+    #[test]
+    fn lines_and_pixels_are_not_summed() {
+        let lines = Event::Mouse(mouse::Event::WheelScrolled {
+            delta: mouse::ScrollDelta::Lines { x: 0.0, y: 1.0 },
+        });
+        let mut last = lines.clone();
+        assert!(!merge_event(&mut last, &scrolled(1.0)));
+        assert_eq!(last, lines);
+    }
 
-impl From<ButtonNumber> for mouse::Button {
-    fn from(number: ButtonNumber) -> Self {
-        match number.0 {
-            2 => mouse::Button::Middle,
-            value => mouse::Button::Other(value as u8),
-        }
+    /// Measures the queueing and the update of a storm of moves and scrolls with and without the
+    /// coalescing.
+    ///
+    /// It needs a GPU, so it's run on demand with
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn coalescing_shortens_the_update_of_a_storm() {
+        let viewport = Viewport::with_physical_size(Size::new(800, 600), 1.0);
+        // Runs of ten moves and ten scrolls, as a gesture is both.
+        let storm: Vec<Event> = (0..1000)
+            .map(|n| {
+                if n / 10 % 2 == 0 {
+                    moved(n as f32)
+                } else {
+                    scrolled(1.0)
+                }
+            })
+            .collect();
+        [false, true].iter().for_each(|&coalesces| {
+            let mut iced = headless::HeadlessRenderer::with_settings(
+                Buttons::new(100),
+                &viewport,
+                &Settings::default(),
+            );
+
+            let start = Instant::now();
+            let mut pending = Vec::new();
+            storm.iter().cloned().for_each(|event| {
+                queue_pending_event(&mut pending, event, coalesces, PointerRate::Unthrottled)
+            });
+            let queued = start.elapsed();
+            let count = pending.len();
+            pending
+                .into_iter()
+                .for_each(|event| iced.queue_event(event));
+            iced.update(&viewport, None);
+            println!(
+                "Coalescing {}: {} events queued in {:?} and updated in {:?}",
+                coalesces,
+                count,
+                queued,
+                start.elapsed() - queued
+            );
+        });
     }
-}
 
-struct Pasteboard {
-    object: id,
-}
+    /// Measures the update of an event storm with each pointer rate, which the debug overlay
+    /// shows too with the `debug` feature.
+    ///
+    /// It needs a GPU, so it's run on demand with
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn per_frame_pointer_rate_shortens_the_update_of_a_storm() {
+        let viewport = Viewport::with_physical_size(Size::new(800, 600), 1.0);
+        [PointerRate::Unthrottled, PointerRate::PerFrame]
+            .iter()
+            .for_each(|&pointer_rate| {
+                let mut iced = headless::HeadlessRenderer::with_settings(
+                    Buttons::new(100),
+                    &viewport,
+                    &Settings::default(),
+                );
+                let pending = queue_storm(pointer_rate);
+                let count = pending.len();
+                pending
+                    .into_iter()
+                    .for_each(|event| iced.queue_event(event));
 
-impl Pasteboard {
-    fn new() -> Self {
-        let object = unsafe { NSPasteboard::generalPasteboard(nil) };
+                let start = Instant::now();
+                iced.update(&viewport, None);
+                println!(
+                    "{:?}: {} events updated in {:?}",
+                    pointer_rate,
+                    count,
+                    start.elapsed()
+                );
+            });
+    }
 
-        Self { object }
+    struct Buttons {
+        buttons: Vec<button::State>,
     }
-}
 
-impl Clipboard for Pasteboard {
-    fn content(&self) -> Option<String> {
-        let ptr = unsafe {
-            let class = class!(NSString);
-            let class_ref: *mut Object = msg_send![class, self];
-            let classes = NSArray::arrayWithObject(nil, class_ref);
-            let objects = self.object.readObjectsForClasses_options(classes, nil);
-            if objects.is_null() || objects.count() == 0 {
-                return None;
+    impl Buttons {
+        fn new(count: usize) -> Self {
+            Self {
+                buttons: vec![button::State::new(); count],
             }
-            NSString::UTF8String(objects.objectAtIndex(0))
-        };
-
-        if ptr.is_null() {
-            None
-        } else {
-            unsafe { Some(CStr::from_ptr(ptr).to_string_lossy().to_string()) }
         }
     }
-}
 
-unsafe fn pathbuf_from_nsurl(url: *mut Object) -> PathBuf {
-    let ptr: *const c_char = msg_send![url, fileSystemRepresentation];
-    CStr::from_ptr(ptr).to_string_lossy().to_string().into()
-}
+    impl Application for Buttons {
+        type Message = ();
 
-/// This function returns scale factor of the passed view.
-///
-/// It returns `None` if the view has no window.
-pub unsafe fn get_nsview_scale_factor(view: *mut c_void) -> Option<f64> {
-    let window: id = msg_send![view as *mut Object, window];
-    if window.is_null() {
-        None
-    } else {
-        let scale_factor: CGFloat = msg_send![window, backingScaleFactor];
-        Some(scale_factor)
+        fn update(&mut self, _message: ()) -> Command<()> {
+            Command::none()
+        }
+
+        fn view(&mut self) -> Element<'_, ()> {
+            self.buttons
+                .iter_mut()
+                .fold(Column::new(), |column, state| {
+                    column.push(Button::new(state, Text::new("Button")).on_press(()))
+                })
+                .into()
+        }
     }
 }
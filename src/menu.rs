@@ -0,0 +1,234 @@
+//! Native context menus.
+//!
+//! The application describes the menu with [`Application::context_menu`], and the chosen item
+//! is turned into its message.
+//!
+//! [`Application::context_menu`]: ../trait.Application.html#method.context_menu
+
+use cocoa::base::{id, nil, NO, YES};
+use cocoa::foundation::{NSInteger, NSString};
+
+use objc::declare::ClassDecl;
+use objc::runtime::{Class, Object, Sel};
+use objc::{class, msg_send, sel, sel_impl};
+
+/// A context menu.
+#[derive(Debug, Clone)]
+pub struct Menu<Message> {
+    items: Vec<MenuItem<Message>>,
+}
+
+impl<Message> Menu<Message> {
+    /// Creates an empty menu.
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Adds an item to the end of the menu.
+    pub fn push(mut self, item: MenuItem<Message>) -> Self {
+        self.items.push(item);
+        self
+    }
+}
+
+impl<Message> Default for Menu<Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An item of a menu.
+#[derive(Debug, Clone)]
+pub enum MenuItem<Message> {
+    /// An item producing a message when chosen.
+    Action {
+        /// The title of the item.
+        title: String,
+        /// The message produced when the item is chosen.
+        message: Message,
+        /// Whether the item can be chosen.
+        is_enabled: bool,
+        /// Whether the item has a checkmark.
+        is_checked: bool,
+    },
+    /// A line separating the groups of items.
+    Separator,
+    /// An item opening another menu.
+    Submenu {
+        /// The title of the item.
+        title: String,
+        /// The nested menu.
+        menu: Menu<Message>,
+    },
+}
+
+impl<Message> MenuItem<Message> {
+    /// Creates an enabled item without a checkmark.
+    pub fn action(title: impl Into<String>, message: Message) -> Self {
+        MenuItem::Action {
+            title: title.into(),
+            message,
+            is_enabled: true,
+            is_checked: false,
+        }
+    }
+
+    /// Creates an item opening another menu.
+    pub fn submenu(title: impl Into<String>, menu: Menu<Message>) -> Self {
+        MenuItem::Submenu {
+            title: title.into(),
+            menu,
+        }
+    }
+
+    /// Sets whether the item can be chosen. It has no effect on the other kinds of items.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        if let MenuItem::Action { is_enabled, .. } = &mut self {
+            *is_enabled = enabled;
+        }
+        self
+    }
+
+    /// Sets whether the item has a checkmark. It has no effect on the other kinds of items.
+    pub fn checked(mut self, checked: bool) -> Self {
+        if let MenuItem::Action { is_checked, .. } = &mut self {
+            *is_checked = checked;
+        }
+        self
+    }
+}
+
+/// Shows the menu for the mouse event and returns the message of the chosen item.
+///
+/// The menu is tracked synchronously, so the chosen item is known when it returns. It isn't
+/// shown if the class of its target couldn't be declared.
+pub(crate) unsafe fn pop_up<Message: Clone>(
+    menu: &Menu<Message>,
+    event: id,
+    view: id,
+) -> Option<Message> {
+    let target_class = match menu_target_class() {
+        Some(class) => class,
+        None => {
+            log::error!("The class of the target of the menu items couldn't be declared.");
+            return None;
+        }
+    };
+    let target: id = msg_send![target_class, new];
+    let mut messages = Vec::new();
+    let ns_menu = make_menu(menu, target, &mut messages);
+
+    let () = msg_send![class!(NSMenu), popUpContextMenu: ns_menu withEvent: event forView: view];
+
+    let chosen = *(*target).get_ivar::<NSInteger>(CHOSEN_TAG_IVAR);
+    let () = msg_send![ns_menu, release];
+    let () = msg_send![target, release];
+
+    if chosen < 0 {
+        None
+    } else {
+        messages
+            .get(chosen as usize)
+            .map(|message| (*message).clone())
+    }
+}
+
+/// Builds the `NSMenu`, collecting the messages of the items, whose tags are their indices.
+unsafe fn make_menu<'a, Message>(
+    menu: &'a Menu<Message>,
+    target: id,
+    messages: &mut Vec<&'a Message>,
+) -> id {
+    let ns_menu: id = msg_send![class!(NSMenu), new];
+    // The state of the items is given by the application.
+    let () = msg_send![ns_menu, setAutoenablesItems: NO];
+
+    menu.items.iter().for_each(|item| {
+        let ns_item: id = match item {
+            MenuItem::Action {
+                title,
+                message,
+                is_enabled,
+                is_checked,
+            } => {
+                let ns_item = make_item(title);
+                let tag = messages.len() as NSInteger;
+                messages.push(message);
+                let () = msg_send![ns_item, setTag: tag];
+                let () = msg_send![ns_item, setTarget: target];
+                let () = msg_send![ns_item, setAction: sel!(menuItemChosen:)];
+                let () = msg_send![ns_item, setEnabled: if *is_enabled { YES } else { NO }];
+                // NSControlStateValueOn and NSControlStateValueOff
+                let state: NSInteger = if *is_checked { 1 } else { 0 };
+                let () = msg_send![ns_item, setState: state];
+                ns_item
+            }
+            MenuItem::Separator => {
+                let ns_item: id = msg_send![class!(NSMenuItem), separatorItem];
+                msg_send![ns_item, retain]
+            }
+            MenuItem::Submenu { title, menu } => {
+                let ns_item = make_item(title);
+                let submenu = make_menu(menu, target, messages);
+                let () = msg_send![ns_item, setSubmenu: submenu];
+                let () = msg_send![submenu, release];
+                ns_item
+            }
+        };
+        let () = msg_send![ns_menu, addItem: ns_item];
+        let () = msg_send![ns_item, release];
+    });
+
+    ns_menu
+}
+
+unsafe fn make_item(title: &str) -> id {
+    let title = NSString::alloc(nil).init_str(title);
+    let key_equivalent = NSString::alloc(nil).init_str("");
+    let alloc: id = msg_send![class!(NSMenuItem), alloc];
+    let ns_item: id =
+        msg_send![alloc, initWithTitle: title action: nil keyEquivalent: key_equivalent];
+    let () = msg_send![title, release];
+    let () = msg_send![key_equivalent, release];
+    ns_item
+}
+
+const CHOSEN_TAG_IVAR: &str = "_chosen_tag";
+
+/// The class of the target of the menu items, which remembers the chosen one, declared on the
+/// first call under a name unique to the binary.
+fn menu_target_class() -> Option<&'static Class> {
+    let name = format!("IcedMenuTarget_{:x}", menu_target_class as usize);
+    if let Some(class) = Class::get(&name) {
+        return Some(class);
+    }
+
+    let mut decl = ClassDecl::new(&name, class!(NSObject))?;
+    decl.add_ivar::<NSInteger>(CHOSEN_TAG_IVAR);
+
+    extern "C" fn init(this: &mut Object, _cmd: Sel) -> id {
+        unsafe {
+            let this: id = msg_send![super(this, class!(NSObject)), init];
+            if this != nil {
+                (*this).set_ivar::<NSInteger>(CHOSEN_TAG_IVAR, -1);
+            }
+            this
+        }
+    }
+
+    extern "C" fn menu_item_chosen(this: &mut Object, _cmd: Sel, sender: id) {
+        unsafe {
+            let tag: NSInteger = msg_send![sender, tag];
+            this.set_ivar::<NSInteger>(CHOSEN_TAG_IVAR, tag);
+        }
+    }
+
+    unsafe {
+        let init: extern "C" fn(&mut Object, Sel) -> id = init;
+        decl.add_method(sel!(init), init);
+        let menu_item_chosen: extern "C" fn(&mut Object, Sel, id) = menu_item_chosen;
+        decl.add_method(sel!(menuItemChosen:), menu_item_chosen);
+    }
+
+    Some(decl.register())
+}
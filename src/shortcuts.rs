@@ -0,0 +1,116 @@
+//! Keyboard shortcuts.
+//!
+//! The application declares its shortcuts with [`Application::shortcuts`], and the matching key
+//! presses are turned into messages instead of being delivered to the widgets.
+//!
+//! [`Application::shortcuts`]: ../trait.Application.html#method.shortcuts
+
+use crate::keyboard::{KeyCode, ModifiersState};
+
+/// A table mapping the key combinations to messages.
+#[derive(Debug, Clone)]
+pub struct ShortcutMap<Message> {
+    shortcuts: Vec<(KeyCode, ModifiersState, Message)>,
+}
+
+impl<Message> ShortcutMap<Message> {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self {
+            shortcuts: Vec::new(),
+        }
+    }
+
+    /// Adds a shortcut producing the message when the key is pressed with exactly these
+    /// modifiers.
+    ///
+    /// If the combination is already in the table, the first message wins.
+    pub fn push(mut self, key_code: KeyCode, modifiers: ModifiersState, message: Message) -> Self {
+        self.shortcuts.push((key_code, modifiers, message));
+        self
+    }
+
+    /// Returns the message of the combination.
+    pub fn get(&self, key_code: KeyCode, modifiers: ModifiersState) -> Option<&Message> {
+        self.shortcuts
+            .iter()
+            .find(|(code, mods, _)| *code == key_code && *mods == modifiers)
+            .map(|(_, _, message)| message)
+    }
+
+    /// Returns whether the table has no shortcuts.
+    pub fn is_empty(&self) -> bool {
+        self.shortcuts.is_empty()
+    }
+
+    /// Returns the message of the combination, if the shortcut may fire.
+    ///
+    /// While a text input is focused, the keys without Command are typed, so only the shortcuts
+    /// with Command fire.
+    pub(crate) fn resolve(
+        &self,
+        key_code: KeyCode,
+        modifiers: ModifiersState,
+        is_text_focused: bool,
+    ) -> Option<&Message> {
+        if is_text_focused && !modifiers.logo {
+            None
+        } else {
+            self.get(key_code, modifiers)
+        }
+    }
+}
+
+impl<Message> Default for ShortcutMap<Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> ModifiersState {
+        ModifiersState {
+            logo: true,
+            ..Default::default()
+        }
+    }
+
+    fn shortcuts() -> ShortcutMap<&'static str> {
+        ShortcutMap::new()
+            .push(KeyCode::S, command(), "save")
+            .push(KeyCode::Space, ModifiersState::default(), "play")
+            .push(KeyCode::S, command(), "ignored")
+    }
+
+    #[test]
+    fn shortcuts_need_the_exact_modifiers() {
+        let shortcuts = shortcuts();
+        assert_eq!(shortcuts.get(KeyCode::S, command()), Some(&"save"));
+        let command_shift = ModifiersState {
+            shift: true,
+            ..command()
+        };
+        assert_eq!(shortcuts.get(KeyCode::S, command_shift), None);
+        assert_eq!(shortcuts.get(KeyCode::S, ModifiersState::default()), None);
+    }
+
+    #[test]
+    fn text_inputs_keep_the_keys_without_command() {
+        let shortcuts = shortcuts();
+        assert_eq!(
+            shortcuts.resolve(KeyCode::Space, ModifiersState::default(), false),
+            Some(&"play")
+        );
+        assert_eq!(
+            shortcuts.resolve(KeyCode::Space, ModifiersState::default(), true),
+            None
+        );
+        assert_eq!(
+            shortcuts.resolve(KeyCode::S, command(), true),
+            Some(&"save")
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! The Touch Bar of the MacBook Pro.
+//!
+//! The application describes the bar with [`Application::touch_bar`], and the interactions with
+//! its items are turned into messages.
+//!
+//! [`Application::touch_bar`]: ../trait.Application.html#method.touch_bar
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use cocoa::base::{id, nil, YES};
+use cocoa::foundation::{NSArray, NSString};
+
+use objc::runtime::Sel;
+use objc::{class, msg_send, sel, sel_impl};
+
+/// The description of the Touch Bar.
+pub struct TouchBarSpec<Message> {
+    pub(crate) items: Vec<TouchBarItem<Message>>,
+}
+
+impl<Message> TouchBarSpec<Message> {
+    /// Creates an empty Touch Bar.
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Adds an item to the end of the Touch Bar.
+    pub fn push(mut self, item: TouchBarItem<Message>) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Returns whether the items of the bar have to be recreated to match the other spec.
+    ///
+    /// The values of the sliders are updated in place, so they don't count.
+    pub(crate) fn has_same_layout(&self, other: &Self) -> bool {
+        self.items.len() == other.items.len()
+            && self
+                .items
+                .iter()
+                .zip(&other.items)
+                .all(|(item, other)| match (item, other) {
+                    (
+                        TouchBarItem::Button { id, label, .. },
+                        TouchBarItem::Button {
+                            id: other_id,
+                            label: other_label,
+                            ..
+                        },
+                    ) => id == other_id && label == other_label,
+                    (
+                        TouchBarItem::Slider {
+                            id, label, range, ..
+                        },
+                        TouchBarItem::Slider {
+                            id: other_id,
+                            label: other_label,
+                            range: other_range,
+                            ..
+                        },
+                    ) => id == other_id && label == other_label && range == other_range,
+                    _ => false,
+                })
+    }
+
+    pub(crate) fn item(&self, id: &str) -> Option<&TouchBarItem<Message>> {
+        self.items.iter().find(|item| item.id() == id)
+    }
+}
+
+impl<Message> Default for TouchBarSpec<Message> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Message> fmt::Debug for TouchBarSpec<Message> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.items.iter().map(TouchBarItem::id))
+            .finish()
+    }
+}
+
+/// An item of the Touch Bar.
+pub enum TouchBarItem<Message> {
+    /// A button.
+    Button {
+        /// The identifier of the item, unique within the bar.
+        id: String,
+        /// The title of the button.
+        label: String,
+        /// The message produced when the button is pressed.
+        on_press: Message,
+    },
+    /// A slider, which suits the parameters.
+    Slider {
+        /// The identifier of the item, unique within the bar.
+        id: String,
+        /// The label displayed before the slider.
+        label: String,
+        /// The range of the values.
+        range: RangeInclusive<f32>,
+        /// The current value.
+        value: f32,
+        /// Produces the message when the slider is moved.
+        on_change: Box<dyn Fn(f32) -> Message>,
+    },
+}
+
+impl<Message> TouchBarItem<Message> {
+    /// Creates a button.
+    pub fn button(id: impl Into<String>, label: impl Into<String>, on_press: Message) -> Self {
+        TouchBarItem::Button {
+            id: id.into(),
+            label: label.into(),
+            on_press,
+        }
+    }
+
+    /// Creates a slider.
+    pub fn slider<F>(
+        id: impl Into<String>,
+        label: impl Into<String>,
+        range: RangeInclusive<f32>,
+        value: f32,
+        on_change: F,
+    ) -> Self
+    where
+        F: 'static + Fn(f32) -> Message,
+    {
+        TouchBarItem::Slider {
+            id: id.into(),
+            label: label.into(),
+            range,
+            value,
+            on_change: Box::new(on_change),
+        }
+    }
+
+    /// The identifier of the item.
+    pub fn id(&self) -> &str {
+        match self {
+            TouchBarItem::Button { id, .. } | TouchBarItem::Slider { id, .. } => id,
+        }
+    }
+}
+
+/// Creates an `NSTouchBar` whose items are made by the delegate.
+pub(crate) unsafe fn make_touch_bar<Message>(spec: &TouchBarSpec<Message>, delegate: id) -> id {
+    let touch_bar: id = msg_send![class!(NSTouchBar), new];
+    let identifiers: Vec<id> = spec
+        .items
+        .iter()
+        .map(|item| NSString::alloc(nil).init_str(item.id()))
+        .collect();
+    let () = msg_send![touch_bar, setDelegate: delegate];
+    let () = msg_send![
+        touch_bar,
+        setDefaultItemIdentifiers: NSArray::arrayWithObjects(nil, &identifiers)
+    ];
+    identifiers.into_iter().for_each(|identifier| {
+        let () = msg_send![identifier, release];
+    });
+
+    msg_send![touch_bar, autorelease]
+}
+
+/// Creates the `NSTouchBarItem` for an item. The interactions are sent to the `target`.
+pub(crate) unsafe fn make_item<Message>(
+    item: &TouchBarItem<Message>,
+    identifier: id,
+    target: id,
+    button_action: Sel,
+    slider_action: Sel,
+) -> id {
+    let touch_bar_item: id = match item {
+        TouchBarItem::Button { label, .. } => {
+            let title = NSString::alloc(nil).init_str(label);
+            let button: id = msg_send![
+                class!(NSButton),
+                buttonWithTitle: title
+                target: target
+                action: button_action
+            ];
+            let () = msg_send![title, release];
+            // The identifier of the item tells which button has been pressed.
+            let () = msg_send![button, setIdentifier: identifier];
+
+            let alloc: id = msg_send![class!(NSCustomTouchBarItem), alloc];
+            let touch_bar_item: id = msg_send![alloc, initWithIdentifier: identifier];
+            let () = msg_send![touch_bar_item, setView: button];
+            touch_bar_item
+        }
+        TouchBarItem::Slider {
+            label,
+            range,
+            value,
+            ..
+        } => {
+            let alloc: id = msg_send![class!(NSSliderTouchBarItem), alloc];
+            let touch_bar_item: id = msg_send![alloc, initWithIdentifier: identifier];
+            let label = NSString::alloc(nil).init_str(label);
+            let () = msg_send![touch_bar_item, setLabel: label];
+            let () = msg_send![label, release];
+
+            let slider: id = msg_send![touch_bar_item, slider];
+            let () = msg_send![slider, setMinValue: f64::from(*range.start())];
+            let () = msg_send![slider, setMaxValue: f64::from(*range.end())];
+            let () = msg_send![slider, setDoubleValue: f64::from(*value)];
+            let () = msg_send![slider, setContinuous: YES];
+            let () = msg_send![touch_bar_item, setTarget: target];
+            let () = msg_send![touch_bar_item, setAction: slider_action];
+            touch_bar_item
+        }
+    };
+
+    msg_send![touch_bar_item, autorelease]
+}
+
+/// Updates the values of the sliders of the bar made from a spec with the same layout.
+pub(crate) unsafe fn update_values<Message>(spec: &TouchBarSpec<Message>, touch_bar: id) {
+    spec.items.iter().for_each(|item| {
+        if let TouchBarItem::Slider { id, value, .. } = item {
+            let identifier = NSString::alloc(nil).init_str(id);
+            let touch_bar_item: id = msg_send![touch_bar, itemForIdentifier: identifier];
+            let () = msg_send![identifier, release];
+            if touch_bar_item == nil {
+                return;
+            }
+
+            let slider: id = msg_send![touch_bar_item, slider];
+            let current: f64 = msg_send![slider, doubleValue];
+            if current != f64::from(*value) {
+                let () = msg_send![slider, setDoubleValue: f64::from(*value)];
+            }
+        }
+    });
+}
+
+/// Returns the value of the slider item which has sent an action.
+pub(crate) unsafe fn slider_value(touch_bar_item: id) -> f32 {
+    let slider: id = msg_send![touch_bar_item, slider];
+    let value: f64 = msg_send![slider, doubleValue];
+    value as f32
+}
@@ -0,0 +1,53 @@
+use iced_nsview::headless::HeadlessRenderer;
+use iced_nsview::{
+    Application, Color, ColorSpace, Command, Element, Length, Settings, Size, Space, Viewport,
+};
+
+const BACKGROUND: [u8; 3] = [0x33, 0x66, 0x99];
+
+#[test]
+fn default_settings_keep_the_colors_untagged() {
+    let settings = Settings::default();
+    assert_eq!(settings.color_space, ColorSpace::Untagged);
+
+    assert_background_is_drawn_as_given(&settings);
+}
+
+#[test]
+fn srgb_keeps_the_colors_as_they_are() {
+    assert_background_is_drawn_as_given(&Settings {
+        color_space: ColorSpace::Srgb,
+        ..Settings::default()
+    });
+}
+
+fn assert_background_is_drawn_as_given(settings: &Settings) {
+    let viewport = Viewport::with_physical_size(Size::new(8, 8), 1.0);
+    let mut iced = HeadlessRenderer::with_settings(Background, &viewport, settings);
+    let frame = iced.capture(&viewport).expect("Capture the frame");
+
+    assert_eq!(frame.pixels.len(), 8 * 8 * 4);
+    frame
+        .pixels
+        .chunks(4)
+        .for_each(|pixel| assert_eq!(pixel, &[BACKGROUND[0], BACKGROUND[1], BACKGROUND[2], 0xff]));
+}
+
+struct Background;
+
+impl Application for Background {
+    type Message = ();
+
+    fn update(&mut self, _message: ()) -> Command<()> {
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<'_, ()> {
+        Space::new(Length::Fill, Length::Fill).into()
+    }
+
+    fn background_color(&self) -> Color {
+        let [r, g, b] = BACKGROUND;
+        Color::from_rgb8(r, g, b)
+    }
+}
@@ -0,0 +1,78 @@
+//! The interface shared by the integration tests.
+
+// Each test uses only a part of it.
+#![allow(dead_code)]
+
+use iced_native::Event;
+
+use iced_nsview::headless::HeadlessRenderer;
+use iced_nsview::{
+    button, mouse, Application, Button, Column, Command, Element, Length, Point, Text,
+};
+
+// The button is placed at the top-left corner, so its center is known without the layout.
+const PADDING: u16 = 20;
+const BUTTON_WIDTH: u16 = 160;
+const BUTTON_HEIGHT: u16 = 40;
+
+/// A button counting its presses.
+#[derive(Default)]
+pub struct Counter {
+    pub count: u32,
+    button: button::State,
+}
+
+impl Counter {
+    /// The center of the button, in logical pixels.
+    pub fn button_center() -> Point {
+        Point::new(
+            f32::from(PADDING + BUTTON_WIDTH / 2),
+            f32::from(PADDING + BUTTON_HEIGHT / 2),
+        )
+    }
+
+    /// A point on the right of the button, in logical pixels.
+    pub fn beside_the_button() -> Point {
+        Point::new(
+            f32::from(PADDING + BUTTON_WIDTH + PADDING),
+            f32::from(PADDING + BUTTON_HEIGHT / 2),
+        )
+    }
+}
+
+impl Application for Counter {
+    type Message = ();
+
+    fn update(&mut self, _message: ()) -> Command<()> {
+        self.count += 1;
+        Command::none()
+    }
+
+    fn view(&mut self) -> Element<'_, ()> {
+        Column::new()
+            .padding(PADDING)
+            .spacing(20)
+            .push(
+                Button::new(&mut self.button, Text::new("Press me"))
+                    .width(Length::Units(BUTTON_WIDTH))
+                    .height(Length::Units(BUTTON_HEIGHT))
+                    .on_press(()),
+            )
+            .push(Text::new(format!("Pressed {} times", self.count)))
+            .into()
+    }
+}
+
+/// Queues a left click at the position, which is handled by the next update.
+pub fn queue_click<A: Application>(iced: &mut HeadlessRenderer<A>, position: Point) {
+    vec![
+        Event::Mouse(mouse::Event::CursorMoved {
+            x: position.x,
+            y: position.y,
+        }),
+        Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)),
+        Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)),
+    ]
+    .into_iter()
+    .for_each(|event| iced.queue_event(event));
+}
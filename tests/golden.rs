@@ -0,0 +1,100 @@
+//! Compares the frames of an interface with the reference images in `tests/golden`.
+//!
+//! A missing reference fails the test. Setting `ICED_NSVIEW_BLESS` records the references from
+//! the frames instead, e.g. for a new test or after a deliberate change of the appearance, so
+//! they're reviewed and committed with the change which draws them.
+
+mod common;
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use iced_nsview::capture::RgbaImage;
+use iced_nsview::headless::HeadlessRenderer;
+use iced_nsview::{Settings, Size, Viewport};
+
+use common::Counter;
+
+/// How much a channel may differ, as the rasterization of the text differs slightly between
+/// the GPUs.
+const TOLERANCE: u8 = 2;
+
+#[test]
+fn counter_matches_its_reference() {
+    let viewport = Viewport::with_physical_size(Size::new(200, 120), 2.0);
+    let mut iced =
+        HeadlessRenderer::with_settings(Counter::default(), &viewport, &Settings::default());
+    let frame = iced.capture(&viewport).expect("Capture the frame");
+
+    assert_matches_reference("counter", &frame);
+}
+
+fn assert_matches_reference(name: &str, frame: &RgbaImage) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.png", name));
+    if std::env::var_os("ICED_NSVIEW_BLESS").is_some() {
+        write_png(&path, frame);
+        println!("The reference {} has been recorded.", path.display());
+        return;
+    }
+
+    assert!(
+        path.exists(),
+        "The reference {} is missing, record it with ICED_NSVIEW_BLESS=1.",
+        path.display()
+    );
+
+    let reference = read_png(&path);
+    assert_eq!(
+        (frame.width, frame.height),
+        (reference.width, reference.height),
+        "The frame has another size than {}.",
+        path.display()
+    );
+    let differing = frame
+        .pixels
+        .iter()
+        .zip(&reference.pixels)
+        .filter(|(drawn, expected)| {
+            (i16::from(**drawn) - i16::from(**expected)).abs() > i16::from(TOLERANCE)
+        })
+        .count();
+    assert_eq!(
+        differing,
+        0,
+        "{} channels differ from {}.",
+        differing,
+        path.display()
+    );
+}
+
+fn read_png(path: &PathBuf) -> RgbaImage {
+    let decoder = png::Decoder::new(File::open(path).expect("Open the reference"));
+    let (info, mut reader) = decoder.read_info().expect("Read the reference");
+    assert_eq!(info.color_type, png::ColorType::RGBA);
+    let mut pixels = vec![0; info.buffer_size()];
+    reader
+        .next_frame(&mut pixels)
+        .expect("Decode the reference");
+
+    RgbaImage {
+        width: info.width,
+        height: info.height,
+        pixels,
+    }
+}
+
+fn write_png(path: &PathBuf, frame: &RgbaImage) {
+    std::fs::create_dir_all(path.parent().unwrap()).expect("Create the directory");
+    let file = File::create(path).expect("Create the reference");
+    let mut encoder = png::Encoder::new(BufWriter::new(file), frame.width, frame.height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&frame.pixels))
+        .expect("Write the reference");
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use iced_nsview::headless::HeadlessRenderer;
+use iced_nsview::{Settings, Size, Viewport};
+
+use common::{queue_click, Counter};
+
+#[test]
+fn a_click_on_the_button_is_handled_in_one_update() {
+    let viewport = Viewport::with_physical_size(Size::new(400, 300), 1.0);
+    let mut iced =
+        HeadlessRenderer::with_settings(Counter::default(), &viewport, &Settings::default());
+
+    queue_click(&mut iced, Counter::button_center());
+    iced.update(&viewport, None);
+
+    assert_eq!(iced.application().count, 1);
+}
+
+#[test]
+fn a_click_beside_the_button_is_ignored() {
+    let viewport = Viewport::with_physical_size(Size::new(400, 300), 1.0);
+    let mut iced =
+        HeadlessRenderer::with_settings(Counter::default(), &viewport, &Settings::default());
+
+    queue_click(&mut iced, Counter::beside_the_button());
+    iced.update(&viewport, None);
+
+    assert_eq!(iced.application().count, 0);
+}